@@ -36,6 +36,19 @@ fn main() {
     singletons.push("UART2".to_string());
     singletons.push("SPI0".to_string());
     singletons.push("SPI1".to_string());
+    singletons.push("PWM0".to_string());
+    singletons.push("PWM1".to_string());
+    singletons.push("USB_OTG".to_string());
+    singletons.push("TVD".to_string());
+    singletons.push("CSI".to_string());
+    singletons.push("VE".to_string());
+    singletons.push("DEINTERLACE".to_string());
+    for n in 0..4 {
+        singletons.push(format!("NDMA{}", n));
+    }
+    for n in 0..4 {
+        singletons.push(format!("DDMA{}", n));
+    }
 
     // _generated.rs
     let mut g = TokenStream::new();
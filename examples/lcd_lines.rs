@@ -35,9 +35,18 @@ async fn main(_spawner: Spawner) -> ! {
     let fb_addr = 0x8180_0000 as *mut u16;
 
     let lcd_config = LcdConfig::lcd_800x480();
-    let lcd = unsafe { Display::new(&lcd_config, fb_addr) };
-
-    println!("[lcd] display initialized: {}x{}", lcd.width(), lcd.height());
+    let lcd = unsafe { Display::new(&lcd_config, fb_addr) }.unwrap();
+
+    if let Some(t) = lcd.timing() {
+        println!(
+            "[lcd] display initialized: {}x{}, pixel clock {}Hz, {}.{:03}Hz refresh",
+            lcd.width(),
+            lcd.height(),
+            t.pixel_clock_hz,
+            t.refresh_millihz / 1000,
+            t.refresh_millihz % 1000
+        );
+    }
 
     // Colors
     let red = rgb565(255, 0, 0);
@@ -63,7 +63,7 @@ async fn main(_spawner: Spawner) -> ! {
 
     spi.cs_low();
     println!("[spi] starting DMA transfer (dst={:#010X})...", buf.as_mut_ptr() as u32);
-    match spi.dma_transfer_blocking(0, &tx, &mut buf) {
+    match spi.dma_transfer_blocking(p.NDMA0, &tx, &mut buf) {
         Ok(()) => println!("[spi] DMA transfer OK"),
         Err(e) => println!("[spi] DMA transfer error: {:?}", e),
     }
@@ -0,0 +1,603 @@
+//! CSI parallel camera interface (8-bit DVP) for F1C100S/F1C200S.
+//!
+//! The CSI samples D0..D7 on PCLK while HSYNC (HREF) and VSYNC are active,
+//! crops a capture window and writes the result to SDRAM:
+//! - YUV422 sensors: stored packed as received, or split into NV16/NV12
+//!   planes that the DEFE can scale directly
+//! - RAW (Bayer) and JPEG: bytes stored as received (pass-through)
+//!
+//! Capture is either single-shot ([`Csi::capture`]) or continuous into two
+//! alternating buffers ([`Csi::start`] + [`Csi::wait_frame`]). Frame-done
+//! and FIFO overflow are reported through the CSI interrupt.
+//!
+//! Pins (function 3 on port E): PE0 HSYNC, PE1 VSYNC, PE2 PCLK, PE3..PE10 D0..D7.
+//! PE0/PE1 are shared with UART0, so the `debug-uart0` feature cannot be
+//! used together with the CSI.
+//!
+//! The CSI registers are not in the PAC, so we use raw pointer access.
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::Poll;
+
+use embassy_sync::waitqueue::AtomicWaker;
+use portable_atomic::{AtomicU32, Ordering};
+
+use crate::gpio::{self, PinMode, Pull, SealedPin};
+use crate::interrupt::typelevel::Handler;
+use crate::interrupt::Interrupt;
+use crate::rcc::{ccu_clear, ccu_set, CCU_BUS_CLK_GATING1, CCU_BUS_SOFT_RST1, CCU_DRAM_GATING};
+use crate::{intc, peripherals, Peri};
+
+// ============================================================================
+// CSI base and register offsets (not in PAC)
+// ============================================================================
+const CSI_BASE: usize = 0x01CB_0000;
+
+const CSI_EN: usize = 0x00;
+const CSI_CFG: usize = 0x04;
+const CSI_CPT_CTRL: usize = 0x08;
+/// FIFO `f` buffer `b` address: 0x10 + 8*f + 4*b
+const CSI_BUF_ADDR: usize = 0x10;
+const CSI_BUF_CTRL: usize = 0x28;
+const CSI_INT_EN: usize = 0x30;
+const CSI_INT_STA: usize = 0x34;
+const CSI_WIN_H: usize = 0x40;
+const CSI_WIN_V: usize = 0x44;
+const CSI_BUF_LEN: usize = 0x48;
+
+// EN register
+const EN_CSI_EN: u32 = 1 << 0;
+
+// CFG register
+const CFG_INPUT_FMT_SHIFT: u32 = 20;
+const CFG_OUTPUT_FMT_SHIFT: u32 = 16;
+const CFG_YUV_SEQ_SHIFT: u32 = 8;
+const CFG_VREF_POL: u32 = 1 << 2;
+const CFG_HREF_POL: u32 = 1 << 1;
+const CFG_PCLK_POL: u32 = 1 << 0;
+
+const INPUT_RAW: u32 = 0;
+const INPUT_YUV422: u32 = 3;
+const OUTPUT_PASSTHROUGH: u32 = 0;
+const OUTPUT_YUV422_UV: u32 = 4;
+const OUTPUT_YUV420_UV: u32 = 5;
+
+// CPT_CTRL register
+const CPT_IMAGE_START: u32 = 1 << 0;
+const CPT_VIDEO_START: u32 = 1 << 1;
+
+// BUF_CTRL register
+const BUF_CTRL_DBE: u32 = 1 << 0;
+/// Buffer currently being written (0 = A, 1 = B)
+const BUF_CTRL_DBS: u32 = 1 << 1;
+
+// INT bits
+const INT_CPT_DONE: u32 = 1 << 0;
+const INT_FRM_DONE: u32 = 1 << 1;
+const INT_FIFO_OVERFLOW: u32 = 0x7 << 2;
+
+// CCU bits (raw)
+const BUS1_CSI: u32 = 1 << 8;
+const DRAM_GATING_CSI: u32 = 1 << 1;
+
+#[inline]
+fn reg(offset: usize) -> *mut u32 {
+    (CSI_BASE + offset) as *mut u32
+}
+
+#[inline]
+unsafe fn write(offset: usize, val: u32) {
+    reg(offset).write_volatile(val);
+}
+
+#[inline]
+unsafe fn read(offset: usize) -> u32 {
+    reg(offset).read_volatile()
+}
+
+static WAKER: AtomicWaker = AtomicWaker::new();
+static FRAME_COUNT: AtomicU32 = AtomicU32::new(0);
+static OVERFLOW_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// CSI IRQ handler — counts frames and overflows, wakes the waiter.
+fn csi_irq_handler() {
+    let status = unsafe { read(CSI_INT_STA) };
+    unsafe { write(CSI_INT_STA, status) };
+
+    if status & (INT_FRM_DONE | INT_CPT_DONE) != 0 {
+        FRAME_COUNT.fetch_add(1, Ordering::Release);
+    }
+    if status & INT_FIFO_OVERFLOW != 0 {
+        OVERFLOW_COUNT.fetch_add(1, Ordering::Release);
+    }
+    WAKER.wake();
+}
+
+/// Interrupt handler for CSI frame-done and overflow events.
+///
+/// The handler is registered automatically by [`Csi::new`], so
+/// `bind_interrupts!` is optional. It's provided for consistency with
+/// the embassy pattern:
+/// ```ignore
+/// bind_interrupts!(struct Irqs {
+///     CSI => csi::InterruptHandler;
+/// });
+/// ```
+pub struct InterruptHandler {
+    _private: (),
+}
+
+impl Handler<crate::interrupt::typelevel::CSI> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        csi_irq_handler();
+    }
+}
+
+/// Signal polarity / sampling edge.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Polarity {
+    /// HSYNC/VSYNC active low, or data sampled on the PCLK falling edge
+    Low,
+    /// HSYNC/VSYNC active high, or data sampled on the PCLK rising edge
+    High,
+}
+
+/// Byte order of a YUV422 sensor stream.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum YuvOrder {
+    Yuyv = 0,
+    Yvyu = 1,
+    Uyvy = 2,
+    Vyuy = 3,
+}
+
+/// How a YUV422 stream is stored.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum YuvLayout {
+    /// Packed, exactly as received (2 bytes per pixel)
+    Packed,
+    /// Y plane + interleaved CbCr plane, full chroma height (NV16)
+    Nv16,
+    /// Y plane + interleaved CbCr plane, half chroma height (NV12)
+    Nv12,
+}
+
+/// Sensor data format.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Format {
+    /// YUV422, 2 bytes per pixel on the bus
+    Yuv422 { order: YuvOrder, layout: YuvLayout },
+    /// 8-bit RAW (Bayer), 1 byte per pixel, passed through
+    Raw8,
+    /// Compressed JPEG stream, passed through. The window width is in bytes
+    /// per line; the end of the image is found by scanning for the EOI marker.
+    Jpeg,
+}
+
+impl Format {
+    /// Bytes per pixel on the DVP bus.
+    pub const fn bus_bytes_per_pixel(self) -> u32 {
+        match self {
+            Format::Yuv422 { .. } => 2,
+            Format::Raw8 | Format::Jpeg => 1,
+        }
+    }
+
+    const fn cfg_bits(self) -> u32 {
+        match self {
+            Format::Yuv422 {
+                layout: YuvLayout::Packed,
+                ..
+            }
+            | Format::Raw8
+            | Format::Jpeg => (INPUT_RAW << CFG_INPUT_FMT_SHIFT) | (OUTPUT_PASSTHROUGH << CFG_OUTPUT_FMT_SHIFT),
+            Format::Yuv422 { order, layout } => {
+                let out = match layout {
+                    YuvLayout::Nv12 => OUTPUT_YUV420_UV,
+                    _ => OUTPUT_YUV422_UV,
+                };
+                (INPUT_YUV422 << CFG_INPUT_FMT_SHIFT)
+                    | (out << CFG_OUTPUT_FMT_SHIFT)
+                    | ((order as u32) << CFG_YUV_SEQ_SHIFT)
+            }
+        }
+    }
+
+    /// Whether the output is split into Y and CbCr planes.
+    pub const fn is_planar(self) -> bool {
+        matches!(
+            self,
+            Format::Yuv422 {
+                layout: YuvLayout::Nv16 | YuvLayout::Nv12,
+                ..
+            }
+        )
+    }
+}
+
+/// Capture window within the sensor frame, in pixels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Window {
+    /// Pixels to skip after HSYNC becomes active
+    pub x: u16,
+    /// Lines to skip after VSYNC
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// CSI configuration
+#[derive(Copy, Clone)]
+pub struct Config {
+    pub format: Format,
+    pub window: Window,
+    pub hsync: Polarity,
+    pub vsync: Polarity,
+    pub pclk: Polarity,
+}
+
+impl Default for Config {
+    /// VGA YUYV, packed, HSYNC high, VSYNC low, sampled on PCLK rising
+    /// (OV2640 / GC0308 defaults).
+    fn default() -> Self {
+        Self {
+            format: Format::Yuv422 {
+                order: YuvOrder::Yuyv,
+                layout: YuvLayout::Packed,
+            },
+            window: Window {
+                x: 0,
+                y: 0,
+                width: 640,
+                height: 480,
+            },
+            hsync: Polarity::High,
+            vsync: Polarity::Low,
+            pclk: Polarity::High,
+        }
+    }
+}
+
+/// Destination of one captured frame.
+///
+/// `c` is the CbCr plane for NV16/NV12 and ignored otherwise.
+#[derive(Debug, Copy, Clone)]
+pub struct Buffer {
+    pub y: *mut u8,
+    pub c: *mut u8,
+}
+
+impl Buffer {
+    /// Single-plane buffer (packed YUV, RAW, JPEG).
+    pub const fn packed(data: *mut u8) -> Self {
+        Self {
+            y: data,
+            c: core::ptr::null_mut(),
+        }
+    }
+}
+
+/// CSI capture error
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// A write FIFO overflowed (SDRAM bandwidth); the frame is corrupt
+    Overflow,
+}
+
+/// CSI capture driver.
+pub struct Csi<'d> {
+    config: Config,
+    buffers: [Buffer; 2],
+    frames_seen: u32,
+    overflows_seen: u32,
+    _peri: PhantomData<&'d mut peripherals::CSI>,
+}
+
+impl<'d> Csi<'d> {
+    /// Create the CSI driver and mux the DVP pins.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        _peri: Peri<'d, peripherals::CSI>,
+        pclk: Peri<'d, impl PclkPin>,
+        hsync: Peri<'d, impl HsyncPin>,
+        vsync: Peri<'d, impl VsyncPin>,
+        d0: Peri<'d, impl D0Pin>,
+        d1: Peri<'d, impl D1Pin>,
+        d2: Peri<'d, impl D2Pin>,
+        d3: Peri<'d, impl D3Pin>,
+        d4: Peri<'d, impl D4Pin>,
+        d5: Peri<'d, impl D5Pin>,
+        d6: Peri<'d, impl D6Pin>,
+        d7: Peri<'d, impl D7Pin>,
+        config: Config,
+    ) -> Self {
+        into_csi_pin(&*pclk);
+        into_csi_pin(&*hsync);
+        into_csi_pin(&*vsync);
+        into_csi_pin(&*d0);
+        into_csi_pin(&*d1);
+        into_csi_pin(&*d2);
+        into_csi_pin(&*d3);
+        into_csi_pin(&*d4);
+        into_csi_pin(&*d5);
+        into_csi_pin(&*d6);
+        into_csi_pin(&*d7);
+
+        unsafe {
+            ccu_set(CCU_BUS_CLK_GATING1, BUS1_CSI);
+            ccu_set(CCU_DRAM_GATING, DRAM_GATING_CSI);
+            ccu_set(CCU_BUS_SOFT_RST1, BUS1_CSI);
+
+            write(CSI_EN, 0);
+            write(CSI_INT_EN, 0);
+            write(CSI_INT_STA, 0xFFFF_FFFF);
+        }
+
+        intc::set_irq_handler(Interrupt::CSI.number(), csi_irq_handler);
+        intc::enable_irq(Interrupt::CSI.number());
+
+        let mut this = Self {
+            config,
+            buffers: [Buffer::packed(core::ptr::null_mut()); 2],
+            frames_seen: 0,
+            overflows_seen: 0,
+            _peri: PhantomData,
+        };
+        this.set_config(&config);
+        this
+    }
+
+    /// Apply a new format, window and polarity. Stops any running capture.
+    pub fn set_config(&mut self, config: &Config) {
+        self.stop();
+        self.config = *config;
+
+        let mut cfg = config.format.cfg_bits();
+        if config.vsync == Polarity::High {
+            cfg |= CFG_VREF_POL;
+        }
+        if config.hsync == Polarity::High {
+            cfg |= CFG_HREF_POL;
+        }
+        if config.pclk == Polarity::High {
+            cfg |= CFG_PCLK_POL;
+        }
+
+        // Horizontal window is counted in PCLK cycles (bytes on the bus)
+        let bpp = config.format.bus_bytes_per_pixel();
+        let w = &config.window;
+        unsafe {
+            write(CSI_CFG, cfg);
+            write(CSI_WIN_H, ((w.width as u32 * bpp) << 16) | (w.x as u32 * bpp));
+            write(CSI_WIN_V, ((w.height as u32) << 16) | w.y as u32);
+            // Line stride of the (first) output plane in bytes
+            let stride = if config.format.is_planar() {
+                w.width as u32
+            } else {
+                w.width as u32 * bpp
+            };
+            write(CSI_BUF_LEN, stride);
+            write(CSI_EN, EN_CSI_EN);
+        }
+    }
+
+    /// Change only the capture window. Stops any running capture.
+    pub fn set_window(&mut self, window: Window) {
+        let mut config = self.config;
+        config.window = window;
+        self.set_config(&config);
+    }
+
+    /// Current configuration.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Size in bytes of the Y (or only) plane of one frame.
+    pub fn plane_size(&self) -> usize {
+        let w = &self.config.window;
+        let px = w.width as usize * w.height as usize;
+        if self.config.format.is_planar() {
+            px
+        } else {
+            px * self.config.format.bus_bytes_per_pixel() as usize
+        }
+    }
+
+    /// Size in bytes of the CbCr plane (0 for single-plane formats).
+    pub fn chroma_size(&self) -> usize {
+        let px = self.config.window.width as usize * self.config.window.height as usize;
+        match self.config.format {
+            Format::Yuv422 {
+                layout: YuvLayout::Nv16,
+                ..
+            } => px,
+            Format::Yuv422 {
+                layout: YuvLayout::Nv12,
+                ..
+            } => px / 2,
+            _ => 0,
+        }
+    }
+
+    /// Capture a single frame into `buf`.
+    ///
+    /// # Safety
+    /// `buf` planes must be valid SDRAM of at least [`Csi::plane_size`] /
+    /// [`Csi::chroma_size`] bytes, 4-byte aligned, and not accessed until
+    /// this returns.
+    pub async unsafe fn capture(&mut self, buf: Buffer) -> Result<(), Error> {
+        self.stop();
+        self.buffers = [buf, buf];
+        self.program_buffers();
+        self.arm_counters();
+
+        write(CSI_INT_EN, INT_CPT_DONE | INT_FIFO_OVERFLOW);
+        write(CSI_CPT_CTRL, CPT_IMAGE_START);
+
+        let result = self.wait_event().await;
+        self.stop();
+        self.invalidate(0);
+        result
+    }
+
+    /// Start continuous capture alternating between two buffers.
+    ///
+    /// # Safety
+    /// Same requirements as [`Csi::capture`] for both buffers; only the buffer
+    /// most recently returned by [`Csi::wait_frame`] may be read by the CPU.
+    pub unsafe fn start(&mut self, buffers: [Buffer; 2]) {
+        self.stop();
+        self.buffers = buffers;
+        self.program_buffers();
+        self.arm_counters();
+
+        write(CSI_BUF_CTRL, BUF_CTRL_DBE);
+        write(CSI_INT_EN, INT_FRM_DONE | INT_FIFO_OVERFLOW);
+        write(CSI_CPT_CTRL, CPT_VIDEO_START);
+    }
+
+    /// Wait for the next frame of a continuous capture. Returns the index
+    /// (0 or 1) of the buffer that now holds it.
+    pub async fn wait_frame(&mut self) -> Result<usize, Error> {
+        self.wait_event().await?;
+        // DBS points at the buffer being filled; the other one is complete
+        let writing = (unsafe { read(CSI_BUF_CTRL) } & BUF_CTRL_DBS != 0) as usize;
+        let done = writing ^ 1;
+        self.invalidate(done);
+        Ok(done)
+    }
+
+    /// Replace the buffer in slot `idx` (0 or 1) of a continuous capture.
+    ///
+    /// Only the slot just returned by [`Csi::wait_frame`] may be replaced;
+    /// the hardware is filling the other one.
+    ///
+    /// # Safety
+    /// Same requirements as the buffers passed to [`Csi::start`].
+    pub unsafe fn set_buffer(&mut self, idx: usize, buf: Buffer) {
+        self.buffers[idx] = buf;
+        write(CSI_BUF_ADDR + 4 * idx, buf.y as u32);
+        write(CSI_BUF_ADDR + 8 + 4 * idx, buf.c as u32);
+    }
+
+    /// Stop capturing. The current frame is abandoned.
+    pub fn stop(&mut self) {
+        unsafe {
+            write(CSI_CPT_CTRL, 0);
+            write(CSI_INT_EN, 0);
+            write(CSI_BUF_CTRL, 0);
+            write(CSI_INT_STA, 0xFFFF_FFFF);
+        }
+    }
+
+    fn arm_counters(&mut self) {
+        self.frames_seen = FRAME_COUNT.load(Ordering::Acquire);
+        self.overflows_seen = OVERFLOW_COUNT.load(Ordering::Acquire);
+    }
+
+    async fn wait_event(&mut self) -> Result<(), Error> {
+        poll_fn(|cx| {
+            WAKER.register(cx.waker());
+            let overflows = OVERFLOW_COUNT.load(Ordering::Acquire);
+            if overflows != self.overflows_seen {
+                self.overflows_seen = overflows;
+                return Poll::Ready(Err(Error::Overflow));
+            }
+            let frames = FRAME_COUNT.load(Ordering::Acquire);
+            if frames != self.frames_seen {
+                self.frames_seen = frames;
+                return Poll::Ready(Ok(()));
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Write buffer A/B addresses for FIFO0 (Y or packed) and FIFO1 (CbCr).
+    unsafe fn program_buffers(&self) {
+        for (b, buf) in self.buffers.into_iter().enumerate() {
+            write(CSI_BUF_ADDR + 4 * b, buf.y as u32);
+            write(CSI_BUF_ADDR + 8 + 4 * b, buf.c as u32);
+        }
+    }
+
+    /// Drop stale cache lines of buffer `idx` before the CPU reads it.
+    fn invalidate(&self, idx: usize) {
+        let buf = self.buffers[idx];
+        arm9::asm::invalidate_dcache_range(buf.y as u32, self.plane_size() as u32);
+        let c = self.chroma_size();
+        if c != 0 {
+            arm9::asm::invalidate_dcache_range(buf.c as u32, c as u32);
+        }
+    }
+}
+
+impl<'d> Drop for Csi<'d> {
+    fn drop(&mut self) {
+        self.stop();
+        intc::disable_irq(Interrupt::CSI.number());
+        unsafe {
+            write(CSI_EN, 0);
+            ccu_clear(CCU_BUS_SOFT_RST1, BUS1_CSI);
+            ccu_clear(CCU_DRAM_GATING, DRAM_GATING_CSI);
+            ccu_clear(CCU_BUS_CLK_GATING1, BUS1_CSI);
+        }
+    }
+}
+
+// ============================================================================
+// Pin traits
+// ============================================================================
+
+fn into_csi_pin<T: gpio::Pin>(pin: &T) {
+    pin.set_mode(PinMode::Func3);
+    pin.set_pull(Pull::None);
+}
+
+mod sealed {
+    pub trait PclkPin {}
+    pub trait HsyncPin {}
+    pub trait VsyncPin {}
+    pub trait D0Pin {}
+    pub trait D1Pin {}
+    pub trait D2Pin {}
+    pub trait D3Pin {}
+    pub trait D4Pin {}
+    pub trait D5Pin {}
+    pub trait D6Pin {}
+    pub trait D7Pin {}
+}
+
+macro_rules! csi_pin {
+    ($($trait:ident => $pin:ident),* $(,)?) => {
+        $(
+            #[allow(private_bounds)]
+            pub trait $trait: sealed::$trait + gpio::Pin {}
+            impl sealed::$trait for crate::peripherals::$pin {}
+            impl $trait for crate::peripherals::$pin {}
+        )*
+    };
+}
+
+csi_pin!(
+    HsyncPin => PE0,
+    VsyncPin => PE1,
+    PclkPin => PE2,
+    D0Pin => PE3,
+    D1Pin => PE4,
+    D2Pin => PE5,
+    D3Pin => PE6,
+    D4Pin => PE7,
+    D5Pin => PE8,
+    D6Pin => PE9,
+    D7Pin => PE10,
+);
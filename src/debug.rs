@@ -7,11 +7,15 @@
 //! - `debug-uart1` - Use UART1 (PA3=TX, PA2=RX)
 //! - `debug-uart2` - Use UART2 (PE7=TX, PE8=RX)
 //!
-//! If no debug feature is enabled, print!/println! are no-ops.
+//! If no debug feature is enabled, print!/println! only reach the mirror.
 //!
 //! Default baudrate: 115200 @ 6MHz APB clock
+//!
+//! [`DebugPrint::set_mirror`] copies all output to a second writer, e.g. a
+//! [`Console`](crate::display::Console) on boards without a serial cable.
 
 use core::fmt::{self, Write};
+use core::ptr::addr_of_mut;
 
 #[cfg(feature = "debug-uart0")]
 use f1c100s_pac::Uart0 as DebugUart;
@@ -21,10 +25,48 @@ use f1c100s_pac::Uart1 as DebugUart;
 use f1c100s_pac::Uart2 as DebugUart;
 use f1c100s_pac::{Ccu, Pio};
 
+/// Second sink for `print!` output, taken out while it is being written to
+static mut MIRROR: Option<&'static mut dyn Write> = None;
+
+/// Puts a taken mirror back when dropped, also if writing to it panics
+struct MirrorGuard(Option<&'static mut dyn Write>);
+
+impl Drop for MirrorGuard {
+    fn drop(&mut self) {
+        let mirror = self.0.take();
+        critical_section::with(|_| unsafe {
+            let slot = &mut *addr_of_mut!(MIRROR);
+            // Keep a mirror installed meanwhile
+            if slot.is_none() {
+                *slot = mirror;
+            }
+        });
+    }
+}
+
 /// Debug print output using UART
 pub struct DebugPrint;
 
 impl DebugPrint {
+    /// Copy all `print!`/`println!` output to `mirror` as well; `None` stops
+    /// mirroring. Returns the previous mirror.
+    ///
+    /// Output produced while the mirror is being written to (from an interrupt
+    /// or a panic inside it) skips the mirror.
+    pub fn set_mirror(mirror: Option<&'static mut dyn Write>) -> Option<&'static mut dyn Write> {
+        critical_section::with(|_| unsafe { core::mem::replace(&mut *addr_of_mut!(MIRROR), mirror) })
+    }
+
+    fn write_mirror(s: &str) {
+        let Some(mirror) = critical_section::with(|_| unsafe { (*addr_of_mut!(MIRROR)).take() }) else {
+            return;
+        };
+        let mut guard = MirrorGuard(Some(mirror));
+        if let Some(mirror) = guard.0.as_mut() {
+            let _ = mirror.write_str(s);
+        }
+    }
+
     /// Initialize UART for debug output (115200 baud, 8N1)
     ///
     /// Must be called after clock init so APB frequency is known.
@@ -117,27 +159,21 @@ impl DebugPrint {
     }
 }
 
-#[cfg(feature = "_debug-output")]
 impl Write for DebugPrint {
     fn write_str(&mut self, s: &str) -> fmt::Result {
+        #[cfg(feature = "_debug-output")]
         for byte in s.bytes() {
             if byte == b'\n' {
                 DebugPrint::write_byte(b'\r');
             }
             DebugPrint::write_byte(byte);
         }
+        DebugPrint::write_mirror(s);
         Ok(())
     }
 }
 
-#[cfg(not(feature = "_debug-output"))]
-impl Write for DebugPrint {
-    fn write_str(&mut self, _s: &str) -> fmt::Result {
-        Ok(())
-    }
-}
-
-/// Print to UART debug output
+/// Print to UART debug output (and the mirror, if set)
 #[macro_export]
 macro_rules! print {
     ($($arg:tt)*) => {
@@ -0,0 +1,518 @@
+//! De-interlacer: converts interlaced frames to progressive ones.
+//!
+//! Interlaced sources such as the TVD (or a CSI sensor in interlaced mode)
+//! deliver both fields woven line-by-line into one semi-planar frame. The
+//! de-interlacer reads one field of such a frame, reconstructs the missing
+//! lines and writes a full progressive frame back to SDRAM:
+//!
+//! - [`Mode::Weave`]: missing lines come from the other field of the same
+//!   frame. Perfect for static content, combs on motion.
+//! - [`Mode::Bob`]: missing lines are interpolated from the field itself.
+//!   No combing, half vertical resolution.
+//! - [`Mode::MotionAdaptive`]: weave where the picture is static, bob (with
+//!   diagonal interpolation) where it moves, using the previous frame and a
+//!   pair of motion flag buffers.
+//!
+//! The output is NV12/NV16 with a tightly packed stride, ready for the DEFE
+//! via [`Deinterlacer::defe_frame`].
+//!
+//! The de-interlacer registers are not in the PAC, so we use raw pointer access.
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::Poll;
+
+use embassy_sync::waitqueue::AtomicWaker;
+use portable_atomic::{AtomicU32, Ordering};
+
+use crate::display::defe::{InputFormat, InputFrame};
+use crate::interrupt::typelevel::Handler;
+use crate::interrupt::Interrupt;
+use crate::rcc::{
+    ccu_clear, ccu_set, ccu_write, CCU_BUS_CLK_GATING1, CCU_BUS_SOFT_RST1, CCU_DEINTERLACE_CLK, CCU_DRAM_GATING,
+};
+use crate::{intc, peripherals, Peri};
+
+// ============================================================================
+// De-interlacer base and register offsets (not in PAC)
+// ============================================================================
+const DI_BASE: usize = 0x01E7_0000;
+
+const DI_MOD_ENABLE: usize = 0x000;
+const DI_FRM_CTRL: usize = 0x004;
+const DI_BYPASS: usize = 0x008;
+const DI_AGTH_SEL: usize = 0x00C;
+const DI_BUF_ADDR0: usize = 0x020;
+const DI_BUF_ADDR1: usize = 0x024;
+const DI_FIELD_CTRL: usize = 0x02C;
+const DI_LINE_STRIDE0: usize = 0x040;
+const DI_LINE_STRIDE1: usize = 0x044;
+const DI_IN_FMT: usize = 0x04C;
+const DI_WB_ADDR0: usize = 0x050;
+const DI_WB_ADDR1: usize = 0x054;
+const DI_OUT_FMT: usize = 0x05C;
+const DI_INT_ENABLE: usize = 0x060;
+const DI_INT_STATUS: usize = 0x064;
+const DI_STATUS: usize = 0x068;
+const DI_CTRL: usize = 0x0A0;
+const DI_DIAG_INTP: usize = 0x0A4;
+const DI_TEMP_DIFF: usize = 0x0A8;
+const DI_LUMA_TH: usize = 0x0AC;
+const DI_SPAT_COMP: usize = 0x0B0;
+const DI_CHROMA_DIFF: usize = 0x0B4;
+const DI_PRELUMA: usize = 0x0B8;
+const DI_PRECHROMA: usize = 0x0BC;
+const DI_TILE_FLAG0: usize = 0x0C0;
+const DI_TILE_FLAG1: usize = 0x0C4;
+const DI_FLAG_LINE_STRIDE: usize = 0x0C8;
+const DI_FLAG_SEQ: usize = 0x0CC;
+const DI_WB_LINE_STRIDE_CTRL: usize = 0x0D0;
+const DI_WB_LINE_STRIDE0: usize = 0x0D4;
+const DI_WB_LINE_STRIDE1: usize = 0x0D8;
+const DI_CH0_IN_SIZE: usize = 0x100;
+const DI_CH0_OUT_SIZE: usize = 0x104;
+const DI_CH0_HORZ_FACT: usize = 0x108;
+const DI_CH0_VERT_FACT: usize = 0x10C;
+const DI_CH1_IN_SIZE: usize = 0x200;
+const DI_CH1_OUT_SIZE: usize = 0x204;
+const DI_CH1_HORZ_FACT: usize = 0x208;
+const DI_CH1_VERT_FACT: usize = 0x20C;
+const DI_CH0_HORZ_COEF0: usize = 0x400;
+const DI_CH0_VERT_COEF: usize = 0x500;
+const DI_CH1_HORZ_COEF0: usize = 0x600;
+const DI_CH1_VERT_COEF: usize = 0x700;
+
+// MOD_ENABLE / FRM_CTRL bits
+const MOD_ENABLE_EN: u32 = 1 << 0;
+const FRM_CTRL_REG_READY: u32 = 1 << 0;
+const FRM_CTRL_WB_EN: u32 = 1 << 2;
+const FRM_CTRL_OUT_CTRL: u32 = 1 << 11;
+const FRM_CTRL_START: u32 = 1 << 16;
+const FRM_CTRL_COEF_ACCESS: u32 = 1 << 23;
+
+const BYPASS_CSC: u32 = 1 << 1;
+const AGTH_SEL_LINEBUF: u32 = 1 << 8;
+
+// IN_FMT: mode [10:8] 2 = UV combined, format [6:4] 1 = YUV422 / 2 = YUV420
+const IN_FMT_NV16: u32 = (2 << 8) | (1 << 4);
+const IN_FMT_NV12: u32 = (2 << 8) | (2 << 4);
+// OUT_FMT [3:0]: 5 = YUV422 semi-planar, 6 = YUV420 semi-planar
+const OUT_FMT_NV16: u32 = 5;
+const OUT_FMT_NV12: u32 = 6;
+
+const WB_LINE_STRIDE_CTRL_EN: u32 = 1 << 0;
+
+const INT_WB: u32 = 1 << 7;
+const STATUS_WB_ERROR: u32 = 1 << 12;
+
+// CTRL bits
+const CTRL_EN: u32 = 1 << 0;
+const CTRL_FLAG_OUT_EN: u32 = 1 << 8;
+const CTRL_MODE_WEAVE: u32 = 1 << 16;
+const CTRL_MODE_BOB: u32 = 2 << 16;
+const CTRL_MODE_MIXED: u32 = 3 << 16;
+const CTRL_DIAG_INTP_EN: u32 = 1 << 24;
+const CTRL_TEMP_DIFF_EN: u32 = 1 << 25;
+
+// Motion detection thresholds (vendor defaults)
+const DIAG_INTP_DEFAULT: u32 = (40 << 24) | (5 << 16) | 10;
+const TEMP_DIFF_DEFAULT: u32 = (4 << 16) | 10;
+const LUMA_TH_DEFAULT: u32 = (1 << 24) | (4 << 16) | (10 << 8) | 10;
+const SPAT_COMP_DEFAULT: u32 = (10 << 16) | (1 << 8) | 20;
+const CHROMA_DIFF_DEFAULT: u32 = 31;
+
+/// Motion flag line stride; supports widths up to 2048
+const FLAG_LINE_STRIDE: u32 = 0x200;
+const MAX_WIDTH: u16 = 2048;
+
+/// Number of polyphase filter phases
+const FILTER_PHASES: usize = 32;
+
+// CCU bits (raw, de-interlacer bits are shared registers)
+const BUS1_DEINTERLACE: u32 = 1 << 5;
+const DRAM_GATING_DEINTERLACE: u32 = 1 << 2;
+/// SCLK gating, PLL_VIDEO(1X) / 1
+const DEINTERLACE_CLK_EN: u32 = 1 << 31;
+
+#[inline]
+fn reg(offset: usize) -> *mut u32 {
+    (DI_BASE + offset) as *mut u32
+}
+
+#[inline]
+unsafe fn write(offset: usize, val: u32) {
+    reg(offset).write_volatile(val);
+}
+
+#[inline]
+unsafe fn read(offset: usize) -> u32 {
+    reg(offset).read_volatile()
+}
+
+static WAKER: AtomicWaker = AtomicWaker::new();
+static DONE_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// De-interlacer IRQ handler — counts finished write-backs, wakes the waiter.
+fn di_irq_handler() {
+    let status = unsafe { read(DI_INT_STATUS) };
+    unsafe { write(DI_INT_STATUS, status) };
+
+    if status & INT_WB != 0 {
+        DONE_COUNT.fetch_add(1, Ordering::Release);
+        WAKER.wake();
+    }
+}
+
+/// Interrupt handler for de-interlacer write-back completion.
+///
+/// The handler is registered automatically by [`Deinterlacer::new`], so
+/// `bind_interrupts!` is optional. It's provided for consistency with
+/// the embassy pattern:
+/// ```ignore
+/// bind_interrupts!(struct Irqs {
+///     DE_INTERLACER => deinterlace::InterruptHandler;
+/// });
+/// ```
+pub struct InterruptHandler {
+    _private: (),
+}
+
+impl Handler<crate::interrupt::typelevel::DE_INTERLACER> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        di_irq_handler();
+    }
+}
+
+/// Frame layout (input and output).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Format {
+    /// YUV 4:2:0 semi-planar (TVD output)
+    Nv12,
+    /// YUV 4:2:2 semi-planar
+    Nv16,
+}
+
+impl Format {
+    /// Chroma rows per luma row divisor.
+    const fn vsub(self) -> u32 {
+        match self {
+            Format::Nv12 => 2,
+            Format::Nv16 => 1,
+        }
+    }
+}
+
+/// Field of an interlaced frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Field {
+    /// Even lines (0, 2, 4, ...)
+    Top,
+    /// Odd lines (1, 3, 5, ...)
+    Bottom,
+}
+
+/// De-interlacing algorithm.
+#[derive(Debug, Copy, Clone)]
+pub enum Mode {
+    /// Combine both fields of the same frame
+    Weave,
+    /// Interpolate the missing lines from one field
+    Bob,
+    /// Per-pixel choice between weave and bob based on motion against the
+    /// previous frame. The two flag buffers each need
+    /// [`Config::flag_buffer_size`] bytes of SDRAM.
+    MotionAdaptive { flags: [*mut u8; 2] },
+}
+
+/// De-interlacer configuration
+#[derive(Debug, Copy, Clone)]
+pub struct Config {
+    /// Full frame size (both fields)
+    pub width: u16,
+    pub height: u16,
+    pub format: Format,
+    pub mode: Mode,
+}
+
+impl Config {
+    /// Bob-deinterlace NV12 frames of the given size.
+    pub const fn nv12(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            format: Format::Nv12,
+            mode: Mode::Bob,
+        }
+    }
+
+    /// Bytes needed for each [`Mode::MotionAdaptive`] flag buffer.
+    pub const fn flag_buffer_size(&self) -> usize {
+        FLAG_LINE_STRIDE as usize * self.height as usize
+    }
+
+    /// Size in bytes of the Y plane.
+    pub const fn luma_size(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// Size in bytes of the CbCr plane.
+    pub const fn chroma_size(&self) -> usize {
+        self.luma_size() / self.format.vsub() as usize
+    }
+}
+
+/// Y and CbCr planes of a frame in SDRAM, tightly packed.
+#[derive(Debug, Copy, Clone)]
+pub struct Buffer {
+    pub y: *mut u8,
+    pub c: *mut u8,
+}
+
+/// De-interlacer error
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// Write-back could not keep up (SDRAM bandwidth); the output is corrupt
+    Writeback,
+}
+
+/// De-interlacer driver.
+pub struct Deinterlacer<'d> {
+    config: Config,
+    /// Which flag buffer the next motion-adaptive pass writes
+    flag_idx: usize,
+    /// Motion flags from a previous pass are valid
+    flags_valid: bool,
+    _peri: PhantomData<&'d mut peripherals::DEINTERLACE>,
+}
+
+impl<'d> Deinterlacer<'d> {
+    /// Power up the de-interlacer and apply `config`.
+    pub fn new(_peri: Peri<'d, peripherals::DEINTERLACE>, config: Config) -> Self {
+        unsafe {
+            ccu_write(CCU_DEINTERLACE_CLK, DEINTERLACE_CLK_EN);
+            ccu_set(CCU_BUS_CLK_GATING1, BUS1_DEINTERLACE);
+            ccu_set(CCU_DRAM_GATING, DRAM_GATING_DEINTERLACE);
+            ccu_set(CCU_BUS_SOFT_RST1, BUS1_DEINTERLACE);
+
+            write(DI_MOD_ENABLE, MOD_ENABLE_EN);
+            // YUV in, YUV out
+            write(DI_BYPASS, BYPASS_CSC);
+            write(DI_AGTH_SEL, AGTH_SEL_LINEBUF);
+            write(DI_DIAG_INTP, DIAG_INTP_DEFAULT);
+            write(DI_TEMP_DIFF, TEMP_DIFF_DEFAULT);
+            write(DI_LUMA_TH, LUMA_TH_DEFAULT);
+            write(DI_SPAT_COMP, SPAT_COMP_DEFAULT);
+            write(DI_CHROMA_DIFF, CHROMA_DIFF_DEFAULT);
+            Self::load_filter_coefficients();
+
+            write(DI_INT_STATUS, 0xFFFF_FFFF);
+            write(DI_INT_ENABLE, INT_WB);
+        }
+
+        intc::set_irq_handler(Interrupt::DE_INTERLACER.number(), di_irq_handler);
+        intc::enable_irq(Interrupt::DE_INTERLACER.number());
+
+        let mut di = Self {
+            config,
+            flag_idx: 0,
+            flags_valid: false,
+            _peri: PhantomData,
+        };
+        di.set_config(config);
+        di
+    }
+
+    /// Change the frame size, format or mode.
+    pub fn set_config(&mut self, config: Config) {
+        assert!(config.width > 0 && config.width <= MAX_WIDTH);
+        assert!(config.height >= 4 && config.height % 4 == 0);
+        self.config = config;
+        self.flags_valid = false;
+
+        let w = config.width as u32;
+        let h = config.height as u32;
+        let vsub = config.format.vsub();
+        let (in_fmt, out_fmt) = match config.format {
+            Format::Nv12 => (IN_FMT_NV12, OUT_FMT_NV12),
+            Format::Nv16 => (IN_FMT_NV16, OUT_FMT_NV16),
+        };
+
+        unsafe {
+            write(DI_IN_FMT, in_fmt);
+            write(DI_OUT_FMT, out_fmt);
+
+            // Each channel reads one field (every other line) and doubles it vertically
+            write(DI_LINE_STRIDE0, w * 2);
+            write(DI_LINE_STRIDE1, w * 2);
+            write(DI_CH0_IN_SIZE, size_bits(w, h / 2));
+            write(DI_CH0_OUT_SIZE, size_bits(w, h));
+            write(DI_CH0_HORZ_FACT, 1 << 16);
+            write(DI_CH0_VERT_FACT, 1 << 15);
+            write(DI_CH1_IN_SIZE, size_bits(w / 2, h / vsub / 2));
+            write(DI_CH1_OUT_SIZE, size_bits(w / 2, h / vsub));
+            write(DI_CH1_HORZ_FACT, 1 << 16);
+            write(DI_CH1_VERT_FACT, 1 << 15);
+
+            write(DI_WB_LINE_STRIDE_CTRL, WB_LINE_STRIDE_CTRL_EN);
+            write(DI_WB_LINE_STRIDE0, w);
+            write(DI_WB_LINE_STRIDE1, w);
+            write(DI_FLAG_LINE_STRIDE, FLAG_LINE_STRIDE);
+        }
+    }
+
+    /// Current configuration.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Build a progressive frame in `out` from `field` of the woven frame `cur`.
+    ///
+    /// `prev` is the previous woven frame; it is only read in
+    /// [`Mode::MotionAdaptive`], which falls back to bob without it. Call
+    /// once per field (top, then bottom) for full temporal resolution, or
+    /// once per frame for half.
+    ///
+    /// # Safety
+    /// All buffers must be valid SDRAM sized for [`Config::luma_size`] /
+    /// [`Config::chroma_size`], 4-byte aligned; `out` must not be accessed
+    /// until this returns and its planes must not share a cache line with
+    /// other data (32-byte aligned, sizes a multiple of 32).
+    pub async unsafe fn process(
+        &mut self,
+        prev: Option<Buffer>,
+        cur: Buffer,
+        field: Field,
+        out: Buffer,
+    ) -> Result<(), Error> {
+        let w = self.config.width as u32;
+        let (this, other) = match field {
+            Field::Top => (0, w),
+            Field::Bottom => (w, 0),
+        };
+
+        write(DI_BUF_ADDR0, cur.y as u32 + this);
+        write(DI_BUF_ADDR1, cur.c as u32 + this);
+        write(DI_FIELD_CTRL, field as u32);
+        write(DI_WB_ADDR0, out.y as u32);
+        write(DI_WB_ADDR1, out.c as u32);
+
+        let mode = match (self.config.mode, prev) {
+            (Mode::Weave, _) => {
+                write(DI_PRELUMA, cur.y as u32 + other);
+                write(DI_PRECHROMA, cur.c as u32 + other);
+                CTRL_MODE_WEAVE
+            }
+            (Mode::MotionAdaptive { flags }, Some(prev)) => {
+                write(DI_PRELUMA, prev.y as u32 + this);
+                write(DI_PRECHROMA, prev.c as u32 + this);
+                // Read the flags of the last pass, write this pass's into the other buffer
+                write(DI_TILE_FLAG0, flags[self.flag_idx ^ 1] as u32);
+                write(DI_TILE_FLAG1, flags[self.flag_idx] as u32);
+                write(DI_FLAG_SEQ, self.flag_idx as u32);
+                let flag_out = if self.flags_valid { CTRL_FLAG_OUT_EN } else { 0 };
+                CTRL_MODE_MIXED | CTRL_DIAG_INTP_EN | CTRL_TEMP_DIFF_EN | flag_out
+            }
+            (Mode::Bob, _) | (Mode::MotionAdaptive { .. }, None) => CTRL_MODE_BOB,
+        };
+        write(DI_CTRL, CTRL_EN | mode);
+
+        // Drop every line of `out`, so no dirty line can be evicted over
+        // what the write-back produces
+        let luma = self.config.luma_size() as u32;
+        let chroma = self.config.chroma_size() as u32;
+        for (addr, len) in [(out.y as u32, luma), (out.c as u32, chroma)] {
+            arm9::asm::clean_dcache_range(addr, len);
+            arm9::asm::invalidate_dcache_range(addr, len);
+        }
+
+        let done = DONE_COUNT.load(Ordering::Acquire);
+        write(
+            DI_FRM_CTRL,
+            FRM_CTRL_REG_READY | FRM_CTRL_WB_EN | FRM_CTRL_OUT_CTRL | FRM_CTRL_START,
+        );
+
+        poll_fn(|cx| {
+            WAKER.register(cx.waker());
+            if DONE_COUNT.load(Ordering::Acquire) != done {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+
+        write(DI_FRM_CTRL, 0);
+        if mode & (3 << 16) == CTRL_MODE_MIXED {
+            self.flag_idx ^= 1;
+            self.flags_valid = true;
+        }
+
+        arm9::asm::invalidate_dcache_range(out.y as u32, luma);
+        arm9::asm::invalidate_dcache_range(out.c as u32, chroma);
+
+        if read(DI_STATUS) & STATUS_WB_ERROR != 0 {
+            Err(Error::Writeback)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// DEFE input frame describing a progressive output buffer.
+    pub fn defe_frame(&self, out: Buffer) -> InputFrame {
+        let format = match self.config.format {
+            Format::Nv12 => InputFormat::Nv12,
+            Format::Nv16 => InputFormat::Nv16,
+        };
+        InputFrame::semi_planar(
+            format,
+            out.y as u32,
+            out.c as u32,
+            self.config.width,
+            self.config.height,
+        )
+    }
+
+    /// Load bilinear polyphase coefficients into both channels.
+    ///
+    /// Same layout as the DEFE: 4 horizontal taps (center tap 1 of the
+    /// first register) and 4 vertical taps per phase, signed 8-bit, sum 64.
+    unsafe fn load_filter_coefficients() {
+        write(DI_FRM_CTRL, FRM_CTRL_COEF_ACCESS);
+        for phase in 0..FILTER_PHASES {
+            let next = (phase as u32 * 64 / FILTER_PHASES as u32) & 0xFF;
+            let center = 64 - next;
+            let coef = (center << 8) | (next << 16);
+
+            write(DI_CH0_HORZ_COEF0 + phase * 4, coef);
+            write(DI_CH0_VERT_COEF + phase * 4, coef);
+            write(DI_CH1_HORZ_COEF0 + phase * 4, coef);
+            write(DI_CH1_VERT_COEF + phase * 4, coef);
+        }
+        write(DI_FRM_CTRL, 0);
+    }
+}
+
+impl<'d> Drop for Deinterlacer<'d> {
+    fn drop(&mut self) {
+        intc::disable_irq(Interrupt::DE_INTERLACER.number());
+        unsafe {
+            write(DI_INT_ENABLE, 0);
+            write(DI_FRM_CTRL, 0);
+            write(DI_CTRL, 0);
+            write(DI_MOD_ENABLE, 0);
+            ccu_clear(CCU_BUS_SOFT_RST1, BUS1_DEINTERLACE);
+            ccu_clear(CCU_DRAM_GATING, DRAM_GATING_DEINTERLACE);
+            ccu_clear(CCU_BUS_CLK_GATING1, BUS1_DEINTERLACE);
+            ccu_write(CCU_DEINTERLACE_CLK, 0);
+        }
+    }
+}
+
+/// Pack a (width, height) size into the `(h - 1) << 16 | (w - 1)` register layout.
+#[inline]
+fn size_bits(w: u32, h: u32) -> u32 {
+    ((h - 1) << 16) | (w - 1)
+}
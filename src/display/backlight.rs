@@ -0,0 +1,132 @@
+//! Panel backlight owned by the [`Display`].
+//!
+//! A backlight is either a PWM channel (dimmable) or a plain enable GPIO
+//! (on for any non-zero brightness). Fades step once per vblank, so they
+//! follow the panel refresh and need the display to be scanning out; in i8080
+//! mode that means auto refresh or repeated [`Display::cpu_refresh`] calls.
+
+use super::{Display, PixelFormat};
+use crate::gpio::Output;
+use crate::pwm::SimplePwm;
+
+enum Driver {
+    Pwm(SimplePwm<'static>),
+    Gpio(Output<'static>),
+}
+
+/// Backlight output with its current brightness.
+pub struct Backlight {
+    driver: Driver,
+    level: u8,
+}
+
+impl Backlight {
+    /// Backlight dimmed by a PWM channel. Starts off.
+    pub fn pwm(pwm: SimplePwm<'static>) -> Self {
+        let mut this = Self {
+            driver: Driver::Pwm(pwm),
+            level: 0,
+        };
+        this.set_brightness(0);
+        this
+    }
+
+    /// Backlight switched by an active-high enable pin. Starts off.
+    pub fn gpio(pin: Output<'static>) -> Self {
+        let mut this = Self {
+            driver: Driver::Gpio(pin),
+            level: 0,
+        };
+        this.set_brightness(0);
+        this
+    }
+
+    /// Current brightness.
+    pub fn brightness(&self) -> u8 {
+        self.level
+    }
+
+    /// Set the brightness, 0 = off, 255 = full.
+    pub fn set_brightness(&mut self, level: u8) {
+        self.level = level;
+        self.apply(level);
+    }
+
+    /// Switch the output off, keeping the brightness for [`Backlight::restore`].
+    pub(super) fn off(&mut self) {
+        self.apply(0);
+    }
+
+    /// Drive the output at the stored brightness again.
+    pub(super) fn restore(&mut self) {
+        self.apply(self.level);
+    }
+
+    fn apply(&mut self, level: u8) {
+        match &mut self.driver {
+            Driver::Pwm(pwm) => {
+                let duty = pwm.max_duty() as u32 * level as u32 / 255;
+                pwm.set_duty(duty as u16);
+                if level == 0 {
+                    pwm.disable();
+                } else if !pwm.is_enabled() {
+                    pwm.enable();
+                }
+            }
+            Driver::Gpio(pin) => {
+                if level == 0 {
+                    pin.set_low();
+                } else {
+                    pin.set_high();
+                }
+            }
+        }
+    }
+}
+
+impl<P: PixelFormat> Display<P> {
+    /// Attach the panel backlight; returns the previously attached one.
+    ///
+    /// The backlight keeps its current brightness.
+    pub fn set_backlight(&mut self, backlight: Backlight) -> Option<Backlight> {
+        self.backlight.replace(backlight)
+    }
+
+    /// Detach the backlight.
+    pub fn take_backlight(&mut self) -> Option<Backlight> {
+        self.backlight.take()
+    }
+
+    /// Current backlight brightness, 0 without a backlight.
+    pub fn brightness(&self) -> u8 {
+        self.backlight.as_ref().map_or(0, Backlight::brightness)
+    }
+
+    /// Set the backlight brightness, 0 = off, 255 = full. Ignored without a backlight.
+    ///
+    /// While the display is powered off the level is only stored and applied
+    /// by [`Display::power_on`].
+    pub fn set_brightness(&mut self, level: u8) {
+        let powered = self.powered;
+        if let Some(backlight) = &mut self.backlight {
+            if powered {
+                backlight.set_brightness(level);
+            } else {
+                backlight.level = level;
+            }
+        }
+    }
+
+    /// Ramp the brightness linearly to `level` over `frames` vblanks.
+    pub async fn fade_brightness(&mut self, level: u8, frames: u16) {
+        let Some(start) = self.backlight.as_ref().map(Backlight::brightness) else {
+            return;
+        };
+        let frames = frames.max(1) as i32;
+        let delta = level as i32 - start as i32;
+        for frame in 1..=frames {
+            self.wait_vblank().await;
+            self.set_brightness((start as i32 + delta * frame / frames) as u8);
+        }
+    }
+}
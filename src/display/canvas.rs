@@ -0,0 +1,110 @@
+//! Panel-agnostic drawing API.
+//!
+//! [`Canvas`] is implemented by the TCON/DEBE [`Display`] and by the
+//! [`SpiPanel`](super::spi_panel::SpiPanel) backend, so drawing code can be
+//! written once against `impl Canvas`. Implementors provide pixel and
+//! rectangle access; lines and outlines are built on top of them.
+
+use super::{Display, PixelFormat};
+
+/// Drawing surface with a width, a height and a pixel colour type.
+///
+/// Coordinates outside the surface are clipped.
+pub trait Canvas {
+    /// Value of one pixel: a colour, or a palette index
+    type Color: Copy;
+
+    /// Width in pixels.
+    fn width(&self) -> u16;
+
+    /// Height in pixels.
+    fn height(&self) -> u16;
+
+    /// Set a pixel at (x, y).
+    fn set_pixel(&self, x: u16, y: u16, color: Self::Color);
+
+    /// Fill a rectangle, clipped to the surface.
+    fn fill_rect(&self, x: u16, y: u16, w: u16, h: u16, color: Self::Color);
+
+    /// Fill the entire surface.
+    fn fill(&self, color: Self::Color) {
+        self.fill_rect(0, 0, self.width(), self.height(), color);
+    }
+
+    /// Draw a horizontal line, both ends included.
+    fn draw_hline(&self, x0: u16, x1: u16, y: u16, color: Self::Color) {
+        let (start, end) = (x0.min(x1), x0.max(x1));
+        self.fill_rect(start, y, (end - start).saturating_add(1), 1, color);
+    }
+
+    /// Draw a vertical line, both ends included.
+    fn draw_vline(&self, x: u16, y0: u16, y1: u16, color: Self::Color) {
+        let (start, end) = (y0.min(y1), y0.max(y1));
+        self.fill_rect(x, start, 1, (end - start).saturating_add(1), color);
+    }
+
+    /// Draw a line using Bresenham's algorithm.
+    fn draw_line(&self, x0: i16, y0: i16, x1: i16, y1: i16, color: Self::Color) {
+        let mut x0 = x0;
+        let mut y0 = y0;
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx: i16 = if x0 < x1 { 1 } else { -1 };
+        let sy: i16 = if y0 < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+
+        loop {
+            if x0 >= 0 && y0 >= 0 {
+                self.set_pixel(x0 as u16, y0 as u16, color);
+            }
+            if x0 == x1 && y0 == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x0 += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y0 += sy;
+            }
+        }
+    }
+
+    /// Draw a rectangle outline.
+    fn draw_rect(&self, x: u16, y: u16, w: u16, h: u16, color: Self::Color) {
+        if w == 0 || h == 0 {
+            return;
+        }
+        self.draw_hline(x, x + w - 1, y, color);
+        self.draw_hline(x, x + w - 1, y + h - 1, color);
+        self.draw_vline(x, y, y + h - 1, color);
+        self.draw_vline(x + w - 1, y, y + h - 1, color);
+    }
+}
+
+impl<P: PixelFormat> Canvas for Display<P> {
+    type Color = P::Color;
+
+    fn width(&self) -> u16 {
+        self.width
+    }
+
+    fn height(&self) -> u16 {
+        self.height
+    }
+
+    #[inline]
+    fn set_pixel(&self, x: u16, y: u16, color: P::Color) {
+        Display::set_pixel(self, x, y, color);
+    }
+
+    fn fill_rect(&self, x: u16, y: u16, w: u16, h: u16, color: P::Color) {
+        Display::fill_rect(self, x, y, w, h, color);
+    }
+
+    fn fill(&self, color: P::Color) {
+        Display::fill(self, color);
+    }
+}
@@ -0,0 +1,423 @@
+//! Text console on the framebuffer, for boot logs and diagnostics.
+//!
+//! Text is drawn in 8x16 cells with the built-in font; the screen scrolls by
+//! one text row when output runs past the bottom. Control characters:
+//! `\n` (new line), `\r`, `\t` (8-column stops) and backspace.
+//!
+//! ANSI escape sequences (`ESC [ ... final`):
+//! - `m`: SGR 0 reset, 1/22 bright on/off, 7/27 reverse on/off, 30-37/90-97
+//!   foreground, 40-47/100-107 background, 39/49 default colours
+//! - `H`/`f`: cursor to row;column (1-based), `A`/`B`/`C`/`D`: cursor moves
+//! - `J`: 0 clears to the end of the screen, 2 the whole screen
+//! - `K`: 0 clears to the end of the line, 2 the whole line
+//!
+//! Other sequences are swallowed. Colours are the 16 VGA ANSI colours.
+//!
+//! The console writes straight into the display's draw buffer and cleans the
+//! touched lines from the data cache after every `write_str`, so text shows up
+//! even when nothing else flushes the framebuffer (e.g. from a panic handler).
+//! [`Console::mirror_print`] copies all `print!`/`println!` output to it.
+
+use core::fmt;
+
+use super::font::{glyph, FONT_HEIGHT, FONT_WIDTH};
+use super::{Display, RgbFormat};
+use crate::debug::DebugPrint;
+
+/// The 16 ANSI colours (VGA palette) as RGB888.
+const ANSI_COLORS: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xAA, 0x00, 0x00),
+    (0x00, 0xAA, 0x00),
+    (0xAA, 0x55, 0x00),
+    (0x00, 0x00, 0xAA),
+    (0xAA, 0x00, 0xAA),
+    (0x00, 0xAA, 0xAA),
+    (0xAA, 0xAA, 0xAA),
+    (0x55, 0x55, 0x55),
+    (0xFF, 0x55, 0x55),
+    (0x55, 0xFF, 0x55),
+    (0xFF, 0xFF, 0x55),
+    (0x55, 0x55, 0xFF),
+    (0xFF, 0x55, 0xFF),
+    (0x55, 0xFF, 0xFF),
+    (0xFF, 0xFF, 0xFF),
+];
+
+/// Font rows covered by the underline cursor
+const CURSOR_ROWS: core::ops::Range<u16> = 14..16;
+
+const TAB_WIDTH: u16 = 8;
+const MAX_PARAMS: usize = 4;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Escape {
+    None,
+    /// Got ESC
+    Esc,
+    /// Inside `ESC [`
+    Csi,
+}
+
+/// Text console drawing into a [`Display`] framebuffer.
+pub struct Console<P: RgbFormat> {
+    fb: *mut P::Raw,
+    width: u16,
+    cols: u16,
+    rows: u16,
+    col: u16,
+    row: u16,
+    /// ANSI colour indices (0..=15) set by SGR and [`Console::set_colors`]
+    fg: u8,
+    bg: u8,
+    default_fg: u8,
+    default_bg: u8,
+    bright: bool,
+    reverse: bool,
+    cursor_visible: bool,
+    escape: Escape,
+    params: [u16; MAX_PARAMS],
+    nparams: usize,
+    /// Text rows written since the last cache clean
+    dirty: Option<(u16, u16)>,
+}
+
+impl<P: RgbFormat> Console<P> {
+    /// Console covering the whole screen, drawing into the display's current
+    /// draw buffer ([`Display::framebuffer`]). Clears it to black; light grey
+    /// text, cursor shown.
+    ///
+    /// Panics if the display is smaller than one 8x16 character cell.
+    ///
+    /// # Safety
+    /// The draw buffer must stay valid and keep its size for as long as the
+    /// console is used, even after `display` is dropped.
+    pub unsafe fn new(display: &Display<P>) -> Self {
+        let mut this = Self {
+            fb: display.framebuffer(),
+            width: display.width(),
+            cols: display.width() / FONT_WIDTH,
+            rows: display.height() / FONT_HEIGHT,
+            col: 0,
+            row: 0,
+            fg: 7,
+            bg: 0,
+            default_fg: 7,
+            default_bg: 0,
+            bright: false,
+            reverse: false,
+            cursor_visible: true,
+            escape: Escape::None,
+            params: [0; MAX_PARAMS],
+            nparams: 0,
+            dirty: None,
+        };
+        assert!(this.cols > 0 && this.rows > 0);
+        this.clear();
+        this
+    }
+
+    /// Size in characters (columns, rows).
+    pub fn size(&self) -> (u16, u16) {
+        (self.cols, self.rows)
+    }
+
+    /// Cursor position (column, row), 0-based.
+    pub fn position(&self) -> (u16, u16) {
+        (self.col, self.row)
+    }
+
+    /// Move the cursor, clamped to the screen.
+    pub fn set_position(&mut self, col: u16, row: u16) {
+        self.hide_cursor();
+        self.col = col.min(self.cols - 1);
+        self.row = row.min(self.rows - 1);
+        self.show_cursor();
+        self.flush();
+    }
+
+    /// Set the default ANSI colours (0..=15), also used after SGR 0.
+    pub fn set_colors(&mut self, fg: u8, bg: u8) {
+        self.default_fg = fg & 0xF;
+        self.default_bg = bg & 0xF;
+        self.fg = self.default_fg;
+        self.bg = self.default_bg;
+        self.bright = false;
+        self.reverse = false;
+    }
+
+    /// Show or hide the underline cursor.
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.hide_cursor();
+        self.cursor_visible = visible;
+        self.show_cursor();
+        self.flush();
+    }
+
+    /// Clear the screen to the background colour and home the cursor.
+    pub fn clear(&mut self) {
+        self.clear_rows(0, self.rows);
+        self.col = 0;
+        self.row = 0;
+        self.show_cursor();
+        self.flush();
+    }
+
+    /// Send all `print!`/`println!` output to this console as well.
+    ///
+    /// Output written while the console is busy (from an interrupt or a
+    /// panic inside `write_str`) goes to the debug UART only.
+    pub fn mirror_print(&'static mut self) {
+        DebugPrint::set_mirror(Some(self));
+    }
+
+    fn write_char(&mut self, c: char) {
+        match self.escape {
+            Escape::Esc => {
+                self.escape = if c == '[' { Escape::Csi } else { Escape::None };
+                self.params = [0; MAX_PARAMS];
+                self.nparams = 0;
+                return;
+            }
+            Escape::Csi => {
+                self.csi_char(c);
+                return;
+            }
+            Escape::None => {}
+        }
+
+        match c {
+            '\x1B' => self.escape = Escape::Esc,
+            '\n' => {
+                self.hide_cursor();
+                self.new_line();
+                self.show_cursor();
+            }
+            '\r' => self.move_to(0, self.row),
+            '\t' => self.move_to((self.col / TAB_WIDTH + 1) * TAB_WIDTH, self.row),
+            '\x08' => self.move_to(self.col.saturating_sub(1), self.row),
+            c if c.is_control() => {}
+            c => {
+                // Wrap lazily so a full last column does not scroll early
+                if self.col >= self.cols {
+                    self.new_line();
+                }
+                self.draw_glyph(c);
+                self.col += 1;
+                self.show_cursor();
+            }
+        }
+    }
+
+    fn csi_char(&mut self, c: char) {
+        match c {
+            '0'..='9' => {
+                if self.nparams == 0 {
+                    self.nparams = 1;
+                }
+                if let Some(p) = self.params.get_mut(self.nparams - 1) {
+                    *p = p.saturating_mul(10).saturating_add(c as u16 - '0' as u16);
+                }
+            }
+            ';' => self.nparams = (self.nparams.max(1) + 1).min(MAX_PARAMS + 1),
+            '\x40'..='\x7E' => {
+                self.escape = Escape::None;
+                self.csi_dispatch(c);
+            }
+            // Intermediate/private bytes: keep collecting
+            _ => {}
+        }
+    }
+
+    fn csi_dispatch(&mut self, c: char) {
+        let params = self.params;
+        let n = self.nparams.min(MAX_PARAMS);
+        let arg = |i: usize, default: u16| match params[i] {
+            0 => default,
+            v => v,
+        };
+        match c {
+            'm' => {
+                if n == 0 {
+                    self.sgr(0);
+                }
+                for &code in &params[..n] {
+                    self.sgr(code);
+                }
+            }
+            'H' | 'f' => self.move_to(arg(1, 1) - 1, arg(0, 1) - 1),
+            'A' => self.move_to(self.col, self.row.saturating_sub(arg(0, 1))),
+            'B' => self.move_to(self.col, self.row.saturating_add(arg(0, 1))),
+            'C' => self.move_to(self.col.saturating_add(arg(0, 1)), self.row),
+            'D' => self.move_to(self.col.saturating_sub(arg(0, 1)), self.row),
+            'J' => {
+                match params[0] {
+                    0 => {
+                        self.clear_line_from(self.col.min(self.cols));
+                        self.clear_rows(self.row + 1, self.rows);
+                    }
+                    2 => self.clear_rows(0, self.rows),
+                    _ => {}
+                }
+                self.show_cursor();
+            }
+            'K' => {
+                match params[0] {
+                    0 => self.clear_line_from(self.col.min(self.cols)),
+                    2 => self.clear_line_from(0),
+                    _ => {}
+                }
+                self.show_cursor();
+            }
+            _ => {}
+        }
+    }
+
+    fn sgr(&mut self, code: u16) {
+        match code {
+            0 => {
+                self.fg = self.default_fg;
+                self.bg = self.default_bg;
+                self.bright = false;
+                self.reverse = false;
+            }
+            1 => self.bright = true,
+            22 => self.bright = false,
+            7 => self.reverse = true,
+            27 => self.reverse = false,
+            30..=37 => self.fg = (code - 30) as u8,
+            39 => self.fg = self.default_fg,
+            40..=47 => self.bg = (code - 40) as u8,
+            49 => self.bg = self.default_bg,
+            90..=97 => self.fg = (code - 90) as u8 + 8,
+            100..=107 => self.bg = (code - 100) as u8 + 8,
+            _ => {}
+        }
+    }
+
+    /// Foreground and background pixel values for the current attributes.
+    fn colors(&self) -> (P::Color, P::Color) {
+        let fg = if self.bright { self.fg | 8 } else { self.fg };
+        let (fg, bg) = if self.reverse { (self.bg, fg) } else { (fg, self.bg) };
+        (ansi_color::<P>(fg), ansi_color::<P>(bg))
+    }
+
+    fn draw_glyph(&mut self, c: char) {
+        let (fg, bg) = self.colors();
+        let bits = glyph(c);
+        let x0 = (self.col * FONT_WIDTH) as usize;
+        for (dy, &line) in bits.iter().enumerate() {
+            let start = (self.row * FONT_HEIGHT) as usize + dy;
+            let start = start * self.width as usize + x0;
+            for dx in 0..FONT_WIDTH as usize {
+                let color = if line & (0x80 >> dx) != 0 { fg } else { bg };
+                unsafe { P::write(self.fb, start + dx, color) };
+            }
+        }
+        self.mark_dirty(self.row, self.row + 1);
+    }
+
+    /// Go to the start of the next line, scrolling at the bottom.
+    fn new_line(&mut self) {
+        self.col = 0;
+        if self.row + 1 < self.rows {
+            self.row += 1;
+            return;
+        }
+
+        let line = P::buffer_size(self.width, FONT_HEIGHT);
+        unsafe {
+            let fb = self.fb as *mut u8;
+            core::ptr::copy(fb.add(line), fb, line * (self.rows as usize - 1));
+        }
+        self.clear_rows(self.rows - 1, self.rows);
+        self.mark_dirty(0, self.rows);
+    }
+
+    fn move_to(&mut self, col: u16, row: u16) {
+        self.hide_cursor();
+        self.col = col.min(self.cols - 1);
+        self.row = row.min(self.rows - 1);
+        self.show_cursor();
+    }
+
+    /// Fill text rows `first..end` with the background colour.
+    fn clear_rows(&mut self, first: u16, end: u16) {
+        if first >= end {
+            return;
+        }
+        let bg = self.colors().1;
+        let start = first as usize * FONT_HEIGHT as usize * self.width as usize;
+        let len = (end - first) as usize * FONT_HEIGHT as usize * self.width as usize;
+        unsafe { P::fill(self.fb, start, len, bg) };
+        self.mark_dirty(first, end);
+    }
+
+    /// Clear the current line from column `col` on.
+    fn clear_line_from(&mut self, col: u16) {
+        let bg = self.colors().1;
+        let x0 = (col * FONT_WIDTH) as usize;
+        let len = self.width as usize - x0;
+        for dy in 0..FONT_HEIGHT {
+            let y = (self.row * FONT_HEIGHT + dy) as usize;
+            unsafe { P::fill(self.fb, y * self.width as usize + x0, len, bg) };
+        }
+        self.mark_dirty(self.row, self.row + 1);
+    }
+
+    fn show_cursor(&mut self) {
+        if self.cursor_visible && self.col < self.cols {
+            let fg = self.colors().0;
+            self.fill_cursor(fg);
+        }
+    }
+
+    fn hide_cursor(&mut self) {
+        if self.cursor_visible && self.col < self.cols {
+            let bg = self.colors().1;
+            self.fill_cursor(bg);
+        }
+    }
+
+    /// The glyphs never use [`CURSOR_ROWS`], so the cursor can be drawn and
+    /// erased without knowing the character underneath.
+    fn fill_cursor(&mut self, color: P::Color) {
+        let x0 = (self.col * FONT_WIDTH) as usize;
+        for dy in CURSOR_ROWS {
+            let y = (self.row * FONT_HEIGHT + dy) as usize;
+            unsafe { P::fill(self.fb, y * self.width as usize + x0, FONT_WIDTH as usize, color) };
+        }
+        self.mark_dirty(self.row, self.row + 1);
+    }
+
+    fn mark_dirty(&mut self, first: u16, end: u16) {
+        self.dirty = Some(match self.dirty {
+            Some((a, b)) => (a.min(first), b.max(end)),
+            None => (first, end),
+        });
+    }
+
+    /// Write back the text rows touched since the last flush.
+    fn flush(&mut self) {
+        if let Some((first, end)) = self.dirty.take() {
+            let line = P::buffer_size(self.width, FONT_HEIGHT);
+            let addr = self.fb as usize + first as usize * line;
+            arm9::asm::clean_dcache_range(addr as u32, ((end - first) as usize * line) as u32);
+        }
+    }
+}
+
+impl<P: RgbFormat> fmt::Write for Console<P> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.write_char(c);
+        }
+        self.flush();
+        Ok(())
+    }
+}
+
+fn ansi_color<P: RgbFormat>(index: u8) -> P::Color {
+    let (r, g, b) = ANSI_COLORS[index as usize & 0xF];
+    P::rgb(r, g, b)
+}
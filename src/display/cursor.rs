@@ -0,0 +1,195 @@
+//! DEBE hardware cursor (HWC).
+//!
+//! The cursor is a small palette image composited above all layers. Its
+//! pattern (1KB) and 256-entry ARGB8888 palette live in DEBE internal SRAM at
+//! +0x4800 and +0x4C00, so moving it only rewrites the coordinate register and
+//! never touches a framebuffer.
+//!
+//! - DEBE_MODE_CTRL[16]: cursor enable
+//! - DEBE_HWC_COORD (0x8D8): [31:16] y, [15:0] x of the top-left corner, signed
+//! - DEBE_HWC_FBCTL (0x8E0): [1:0] bits per pixel, [3:2] width, [5:4] height
+//!   (0 = 32, 1 = 64)
+//!
+//! Palette entry 0 is typically fully transparent (alpha 0).
+
+use super::layer::debe_reg;
+use super::{Display, PixelFormat};
+use crate::pac;
+
+const DEBE_HWC_COORD: usize = 0x8D8;
+const DEBE_HWC_FBCTL: usize = 0x8E0;
+const DEBE_HWC_PATTERN: usize = 0x4800;
+const DEBE_HWC_PALETTE: usize = 0x4C00;
+
+const MODE_CTRL_HWC_EN: u32 = 1 << 16;
+
+const FBCTL_XSIZE_SHIFT: u32 = 2;
+const FBCTL_YSIZE_SHIFT: u32 = 4;
+
+/// Size of the cursor pattern memory in bytes.
+pub const CURSOR_PATTERN_SIZE: usize = 1024;
+
+/// Cursor image error.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CursorError {
+    /// The pattern is larger than [`CURSOR_PATTERN_SIZE`]
+    PatternTooLarge,
+    /// `pixels` is shorter than the pattern
+    PixelsTooShort,
+    /// `palette` has more than `1 << bpp` entries
+    PaletteTooLong,
+    /// The hotspot lies outside the image
+    HotspotOutside,
+}
+
+/// Cursor pixel depth (DEBE_HWC_FBCTL[1:0]).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum CursorFormat {
+    Bpp1 = 0,
+    Bpp2 = 1,
+    Bpp4 = 2,
+    Bpp8 = 3,
+}
+
+impl CursorFormat {
+    pub const fn bits_per_pixel(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// Cursor dimensions (width x height).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CursorSize {
+    W32H32,
+    W32H64,
+    W64H32,
+    W64H64,
+}
+
+impl CursorSize {
+    pub const fn width(self) -> u16 {
+        match self {
+            CursorSize::W32H32 | CursorSize::W32H64 => 32,
+            CursorSize::W64H32 | CursorSize::W64H64 => 64,
+        }
+    }
+
+    pub const fn height(self) -> u16 {
+        match self {
+            CursorSize::W32H32 | CursorSize::W64H32 => 32,
+            CursorSize::W32H64 | CursorSize::W64H64 => 64,
+        }
+    }
+
+    /// FBCTL size bits
+    const fn bits(self) -> u32 {
+        let x = (self.width() == 64) as u32;
+        let y = (self.height() == 64) as u32;
+        (x << FBCTL_XSIZE_SHIFT) | (y << FBCTL_YSIZE_SHIFT)
+    }
+}
+
+/// Cursor bitmap with its palette and hotspot.
+#[derive(Debug, Copy, Clone)]
+pub struct CursorImage<'a> {
+    pub size: CursorSize,
+    pub format: CursorFormat,
+    /// Palette indices row by row, packed like the palette framebuffer
+    /// formats (first pixel in the most significant bits). At most
+    /// [`CURSOR_PATTERN_SIZE`] bytes: 64x64 needs 2bpp or less, 32x64 4bpp.
+    pub pixels: &'a [u8],
+    /// ARGB8888 (`0xAARRGGBB`) palette, up to `1 << bpp` entries
+    pub palette: &'a [u32],
+    /// Pixel of the image placed at the pointer position
+    pub hotspot_x: u8,
+    pub hotspot_y: u8,
+}
+
+impl<'a> CursorImage<'a> {
+    /// Pattern size in bytes.
+    pub const fn pattern_size(&self) -> usize {
+        self.size.width() as usize * self.size.height() as usize * self.format.bits_per_pixel() as usize / 8
+    }
+}
+
+impl<P: PixelFormat> Display<P> {
+    /// Upload a cursor image and its palette. The cursor keeps its position
+    /// and visibility; see [`Display::set_cursor_visible`].
+    pub fn set_cursor(&mut self, image: &CursorImage) -> Result<(), CursorError> {
+        let size = image.pattern_size();
+        if size > CURSOR_PATTERN_SIZE {
+            return Err(CursorError::PatternTooLarge);
+        }
+        if image.pixels.len() < size {
+            return Err(CursorError::PixelsTooShort);
+        }
+        if image.palette.len() > 1 << image.format.bits_per_pixel() {
+            return Err(CursorError::PaletteTooLong);
+        }
+        if image.hotspot_x as u16 >= image.size.width() || image.hotspot_y as u16 >= image.size.height() {
+            return Err(CursorError::HotspotOutside);
+        }
+
+        unsafe {
+            let pattern = debe_reg(DEBE_HWC_PATTERN);
+            for (i, word) in image.pixels[..size].chunks(4).enumerate() {
+                // SRAM takes word writes; little-endian keeps the byte order
+                let mut bytes = [0u8; 4];
+                bytes[..word.len()].copy_from_slice(word);
+                pattern.add(i).write_volatile(u32::from_le_bytes(bytes));
+            }
+
+            let palette = debe_reg(DEBE_HWC_PALETTE);
+            for (i, &color) in image.palette.iter().enumerate() {
+                palette.add(i).write_volatile(color);
+            }
+
+            debe_reg(DEBE_HWC_FBCTL).write_volatile(image.size.bits() | image.format as u32);
+        }
+
+        let (x, y) = self.cursor_pos;
+        self.cursor_hotspot = (image.hotspot_x as i16, image.hotspot_y as i16);
+        self.move_cursor(x, y);
+        Ok(())
+    }
+
+    /// Change the hotspot without re-uploading the image.
+    pub fn set_cursor_hotspot(&mut self, x: u8, y: u8) {
+        self.cursor_hotspot = (x as i16, y as i16);
+        let (x, y) = self.cursor_pos;
+        self.move_cursor(x, y);
+    }
+
+    /// Put the cursor hotspot at screen position (x, y). Only the coordinate
+    /// register is written; the cursor may hang off any screen edge.
+    pub fn move_cursor(&mut self, x: i16, y: i16) {
+        self.cursor_pos = (x, y);
+        let cx = x.wrapping_sub(self.cursor_hotspot.0);
+        let cy = y.wrapping_sub(self.cursor_hotspot.1);
+        unsafe {
+            debe_reg(DEBE_HWC_COORD).write_volatile(((cy as u16 as u32) << 16) | cx as u16 as u32);
+            Self::debe_load_regs();
+        }
+    }
+
+    /// Current cursor hotspot position on screen.
+    pub fn cursor_position(&self) -> (i16, i16) {
+        self.cursor_pos
+    }
+
+    /// Show or hide the cursor.
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        let debe = unsafe { &*pac::Debe::ptr() };
+        debe.debe_mode_ctrl_reg().modify(|r, w| unsafe {
+            w.bits(if visible {
+                r.bits() | MODE_CTRL_HWC_EN
+            } else {
+                r.bits() & !MODE_CTRL_HWC_EN
+            })
+        });
+    }
+}
@@ -0,0 +1,445 @@
+//! Display Engine Front-End (DEFE): hardware scaler and color-space converter.
+//!
+//! The DEFE reads a frame from SDRAM, scales it to an arbitrary output size
+//! and converts YUV to RGB, then feeds the result straight into a DEBE layer
+//! (video channel) without going back through memory.
+//!
+//! Supported inputs:
+//! - XRGB8888 packed
+//! - YUV420 / YUV422 planar (three planes)
+//! - YUV420 / YUV422 semi-planar (NV12/NV21/NV16/NV61)
+//! - YUV420 / YUV422 semi-planar in 32x32 tiles (video engine output)
+//!
+//! Channel 0 processes luma (or RGB), channel 1 processes chroma.
+//! Both use 32-phase polyphase filters; we load bilinear coefficients.
+//!
+//! The DEFE registers are not in the PAC, so we use raw pointer access.
+//! Clocks and resets are enabled by [`Display::new`](super::Display::new).
+
+use core::marker::PhantomData;
+
+use super::{Display, PixelFormat};
+
+// ============================================================================
+// DEFE base and register offsets (not in PAC)
+// ============================================================================
+const DEFE_BASE: usize = 0x01E0_0000;
+
+const DEFE_EN: usize = 0x000;
+const DEFE_FRM_CTRL: usize = 0x004;
+const DEFE_BYPASS: usize = 0x008;
+const DEFE_BUF_ADDR0: usize = 0x020;
+const DEFE_LINESTRD0: usize = 0x040;
+const DEFE_INPUT_FMT: usize = 0x04C;
+const DEFE_OUTPUT_FMT: usize = 0x05C;
+const DEFE_CSC_COEF0: usize = 0x070;
+const DEFE_CH0_INSIZE: usize = 0x100;
+const DEFE_CH0_OUTSIZE: usize = 0x104;
+const DEFE_CH0_HORZFACT: usize = 0x108;
+const DEFE_CH0_VERTFACT: usize = 0x10C;
+const DEFE_CH1_INSIZE: usize = 0x200;
+const DEFE_CH1_OUTSIZE: usize = 0x204;
+const DEFE_CH1_HORZFACT: usize = 0x208;
+const DEFE_CH1_VERTFACT: usize = 0x20C;
+const DEFE_CH0_HORZCOEF0: usize = 0x400;
+const DEFE_CH0_HORZCOEF1: usize = 0x480;
+const DEFE_CH0_VERTCOEF: usize = 0x500;
+const DEFE_CH1_HORZCOEF0: usize = 0x600;
+const DEFE_CH1_HORZCOEF1: usize = 0x680;
+const DEFE_CH1_VERTCOEF: usize = 0x700;
+
+// EN register bits
+const EN_EN: u32 = 1 << 0;
+
+// FRM_CTRL register bits
+/// Self-clears once the shadow registers were latched at frame start
+const FRM_CTRL_REG_RDY: u32 = 1 << 0;
+const FRM_CTRL_COEF_RDY: u32 = 1 << 1;
+const FRM_CTRL_FRM_START: u32 = 1 << 16;
+const FRM_CTRL_COEF_ACCESS_CTRL: u32 = 1 << 23;
+
+// BYPASS register bits
+const BYPASS_CSC_EN: u32 = 1 << 1;
+
+/// Output format: XRGB8888 interleaved (what the DEBE video channel expects)
+const OUTPUT_FMT_XRGB8888: u32 = 2;
+
+/// Number of polyphase filter phases
+const FILTER_PHASES: usize = 32;
+
+#[inline]
+fn reg(offset: usize) -> *mut u32 {
+    (DEFE_BASE + offset) as *mut u32
+}
+
+#[inline]
+unsafe fn write(offset: usize, val: u32) {
+    reg(offset).write_volatile(val);
+}
+
+#[inline]
+unsafe fn modify(offset: usize, clear: u32, set: u32) {
+    let val = reg(offset).read_volatile();
+    reg(offset).write_volatile((val & !clear) | set);
+}
+
+/// DEFE input pixel format.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InputFormat {
+    /// 32-bit packed XRGB (one plane)
+    Xrgb8888,
+    /// YUV 4:2:0 planar, Y + U + V planes (I420)
+    Yuv420Planar,
+    /// YUV 4:2:2 planar, Y + U + V planes
+    Yuv422Planar,
+    /// YUV 4:2:0 semi-planar, Y + interleaved UV
+    Nv12,
+    /// YUV 4:2:0 semi-planar, Y + interleaved VU
+    Nv21,
+    /// YUV 4:2:2 semi-planar, Y + interleaved UV
+    Nv16,
+    /// YUV 4:2:2 semi-planar, Y + interleaved VU
+    Nv61,
+    /// NV12 stored in 32x32 tiles, as written by the video engine
+    Nv12Tiled,
+    /// NV16 stored in 32x32 tiles, as written by the video engine
+    Nv16Tiled,
+}
+
+impl InputFormat {
+    /// Whether this is a YUV format (needs color-space conversion).
+    pub const fn is_yuv(self) -> bool {
+        !matches!(self, InputFormat::Xrgb8888)
+    }
+
+    /// Chroma subsampling as (horizontal, vertical) divisors.
+    pub const fn chroma_subsampling(self) -> (u32, u32) {
+        match self {
+            InputFormat::Xrgb8888 => (1, 1),
+            InputFormat::Yuv420Planar | InputFormat::Nv12 | InputFormat::Nv21 | InputFormat::Nv12Tiled => (2, 2),
+            InputFormat::Yuv422Planar | InputFormat::Nv16 | InputFormat::Nv61 | InputFormat::Nv16Tiled => (2, 1),
+        }
+    }
+
+    /// INPUT_FMT register value: data mode [10:8], data format [6:4], pixel sequence [1:0].
+    fn input_fmt_bits(self) -> u32 {
+        // data mode: 0 = non-tiled planar, 1 = interleaved, 2 = non-tiled UV combined,
+        //            6 = tiled UV combined
+        // data format: 1 = YUV422, 2 = YUV420, 5 = RGB
+        // pixel sequence: semi-planar 0 = UV, 1 = VU; RGB 1 = XRGB
+        let (mode, fmt, ps) = match self {
+            InputFormat::Xrgb8888 => (1, 5, 1),
+            InputFormat::Yuv420Planar => (0, 2, 0),
+            InputFormat::Yuv422Planar => (0, 1, 0),
+            InputFormat::Nv12 => (2, 2, 0),
+            InputFormat::Nv21 => (2, 2, 1),
+            InputFormat::Nv16 => (2, 1, 0),
+            InputFormat::Nv61 => (2, 1, 1),
+            InputFormat::Nv12Tiled => (6, 2, 0),
+            InputFormat::Nv16Tiled => (6, 1, 0),
+        };
+        (mode << 8) | (fmt << 4) | ps
+    }
+}
+
+/// An input frame in SDRAM.
+#[derive(Debug, Copy, Clone)]
+pub struct InputFrame {
+    pub width: u16,
+    pub height: u16,
+    pub format: InputFormat,
+    /// Plane base addresses. Unused planes are ignored.
+    pub planes: [u32; 3],
+    /// Plane line strides in bytes. Unused planes are ignored.
+    pub strides: [u32; 3],
+}
+
+impl InputFrame {
+    /// Packed XRGB8888 frame with a tightly packed stride.
+    pub const fn xrgb8888(addr: u32, width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            format: InputFormat::Xrgb8888,
+            planes: [addr, 0, 0],
+            strides: [width as u32 * 4, 0, 0],
+        }
+    }
+
+    /// Semi-planar frame (NV12/NV21/NV16/NV61) with tightly packed strides.
+    pub const fn semi_planar(format: InputFormat, y: u32, uv: u32, width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            format,
+            planes: [y, uv, 0],
+            strides: [width as u32, width as u32, 0],
+        }
+    }
+
+    /// Tiled semi-planar frame (NV12/NV16 in 32x32 tiles).
+    ///
+    /// Both planes are `width` rounded up to 32 bytes wide. In tiled mode the
+    /// stride register holds the jump from the end of one tile row to the
+    /// start of the next, not a line pitch.
+    pub const fn tiled(format: InputFormat, y: u32, uv: u32, width: u16, height: u16) -> Self {
+        let stride = ((width as u32 + 31) & !31) - 31;
+        Self {
+            width,
+            height,
+            format,
+            planes: [y, uv, 0],
+            strides: [stride << 5, stride << 5, 0],
+        }
+    }
+
+    /// Three-plane YUV420/YUV422 frame with tightly packed strides.
+    pub const fn planar(format: InputFormat, y: u32, u: u32, v: u32, width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            format,
+            planes: [y, u, v],
+            strides: [width as u32, width as u32 / 2, width as u32 / 2],
+        }
+    }
+}
+
+/// YUV matrix standard.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ColorSpace {
+    /// ITU-R BT.601 (SD video, most cameras and JPEG)
+    Bt601,
+    /// ITU-R BT.709 (HD video)
+    Bt709,
+}
+
+/// YUV quantization range.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ColorRange {
+    /// Y in 16..=235, UV in 16..=240 (video)
+    Limited,
+    /// Y and UV in 0..=255 (JPEG)
+    Full,
+}
+
+/// Color-space conversion selection.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Csc {
+    pub space: ColorSpace,
+    pub range: ColorRange,
+}
+
+impl Default for Csc {
+    fn default() -> Self {
+        Self {
+            space: ColorSpace::Bt601,
+            range: ColorRange::Limited,
+        }
+    }
+}
+
+impl Csc {
+    /// Compute the 12 YUV->RGB coefficients in DEFE register order.
+    ///
+    /// Rows are G, R, B; each row is Y, U, V multipliers (signed 13-bit,
+    /// 10 fractional bits) followed by a constant (signed 14-bit, 4 fractional bits).
+    fn coefficients(&self) -> [u32; 12] {
+        let (kr, kb) = match self.space {
+            ColorSpace::Bt601 => (0.299f32, 0.114f32),
+            ColorSpace::Bt709 => (0.2126f32, 0.0722f32),
+        };
+        let kg = 1.0 - kr - kb;
+        let (ys, cs, yoff) = match self.range {
+            ColorRange::Limited => (255.0 / 219.0, 255.0 / 224.0, 16.0),
+            ColorRange::Full => (1.0, 1.0, 0.0),
+        };
+
+        let rv = 2.0 * (1.0 - kr) * cs;
+        let gu = -2.0 * kb * (1.0 - kb) / kg * cs;
+        let gv = -2.0 * kr * (1.0 - kr) / kg * cs;
+        let bu = 2.0 * (1.0 - kb) * cs;
+
+        let rows: [[f32; 3]; 3] = [[ys, gu, gv], [ys, 0.0, rv], [ys, bu, 0.0]];
+        let mut out = [0u32; 12];
+        for (i, row) in rows.iter().enumerate() {
+            let c = -row[0] * yoff - (row[1] + row[2]) * 128.0;
+            out[i * 4] = (fixed(row[0], 1024.0) as u32) & 0x1FFF;
+            out[i * 4 + 1] = (fixed(row[1], 1024.0) as u32) & 0x1FFF;
+            out[i * 4 + 2] = (fixed(row[2], 1024.0) as u32) & 0x1FFF;
+            out[i * 4 + 3] = (fixed(c, 16.0) as u32) & 0x3FFF;
+        }
+        out
+    }
+}
+
+/// Round `x * scale` to the nearest integer.
+fn fixed(x: f32, scale: f32) -> i32 {
+    let v = x * scale;
+    if v >= 0.0 {
+        (v + 0.5) as i32
+    } else {
+        (v - 0.5) as i32
+    }
+}
+
+/// DEFE driver. Borrows the [`Display`] so the display engine clocks stay on.
+pub struct Defe<'a> {
+    format: Option<InputFormat>,
+    _display: PhantomData<&'a ()>,
+}
+
+impl<'a> Defe<'a> {
+    /// Enable the front-end and load the scaler filter coefficients.
+    pub fn new<P: PixelFormat>(_display: &'a Display<P>) -> Self {
+        unsafe {
+            write(DEFE_EN, EN_EN);
+            Self::load_filter_coefficients();
+        }
+        Self {
+            format: None,
+            _display: PhantomData,
+        }
+    }
+
+    /// Configure scaling of `frame` to `out_width` x `out_height` with the given CSC,
+    /// and start processing.
+    ///
+    /// The output goes to the DEBE video channel; show it on a layer with
+    /// [`LayerConfig::defe`](super::LayerConfig::defe).
+    pub fn configure(&mut self, frame: &InputFrame, out_width: u16, out_height: u16, csc: Csc) {
+        assert!(frame.width > 0 && frame.height > 0);
+        assert!(out_width > 0 && out_height > 0);
+
+        let fmt = frame.format;
+        let (hsub, vsub) = fmt.chroma_subsampling();
+        let in_w = frame.width as u32;
+        let in_h = frame.height as u32;
+        let out_w = out_width as u32;
+        let out_h = out_height as u32;
+
+        unsafe {
+            // Input/output formats
+            write(DEFE_INPUT_FMT, fmt.input_fmt_bits());
+            write(DEFE_OUTPUT_FMT, OUTPUT_FMT_XRGB8888);
+
+            // Color-space conversion
+            if fmt.is_yuv() {
+                for (i, c) in csc.coefficients().iter().enumerate() {
+                    write(DEFE_CSC_COEF0 + i * 4, *c);
+                }
+                modify(DEFE_BYPASS, 0, BYPASS_CSC_EN);
+            } else {
+                modify(DEFE_BYPASS, BYPASS_CSC_EN, 0);
+            }
+
+            // Channel 0: luma / RGB
+            write(DEFE_CH0_INSIZE, size_bits(in_w, in_h));
+            write(DEFE_CH0_OUTSIZE, size_bits(out_w, out_h));
+            write(DEFE_CH0_HORZFACT, (in_w << 16) / out_w);
+            write(DEFE_CH0_VERTFACT, (in_h << 16) / out_h);
+
+            // Channel 1: chroma (same output size, subsampled input)
+            let c_w = (in_w / hsub).max(1);
+            let c_h = (in_h / vsub).max(1);
+            write(DEFE_CH1_INSIZE, size_bits(c_w, c_h));
+            write(DEFE_CH1_OUTSIZE, size_bits(out_w, out_h));
+            write(DEFE_CH1_HORZFACT, (c_w << 16) / out_w);
+            write(DEFE_CH1_VERTFACT, (c_h << 16) / out_h);
+        }
+
+        self.format = Some(fmt);
+        self.set_input(frame);
+
+        unsafe {
+            modify(DEFE_FRM_CTRL, 0, FRM_CTRL_REG_RDY);
+            modify(DEFE_FRM_CTRL, 0, FRM_CTRL_FRM_START);
+        }
+    }
+
+    /// Point the front-end at a new input buffer with the same geometry and format.
+    ///
+    /// The new addresses are latched at the start of the next frame.
+    pub fn set_input(&mut self, frame: &InputFrame) {
+        debug_assert_eq!(Some(frame.format), self.format);
+        let planes = match frame.format {
+            InputFormat::Xrgb8888 => 1,
+            InputFormat::Nv12
+            | InputFormat::Nv21
+            | InputFormat::Nv16
+            | InputFormat::Nv61
+            | InputFormat::Nv12Tiled
+            | InputFormat::Nv16Tiled => 2,
+            InputFormat::Yuv420Planar | InputFormat::Yuv422Planar => 3,
+        };
+
+        unsafe {
+            for i in 0..3 {
+                let (addr, stride) = if i < planes {
+                    (frame.planes[i], frame.strides[i])
+                } else {
+                    (0, 0)
+                };
+                write(DEFE_BUF_ADDR0 + i * 4, addr);
+                write(DEFE_LINESTRD0 + i * 4, stride);
+            }
+            modify(DEFE_FRM_CTRL, 0, FRM_CTRL_REG_RDY);
+        }
+    }
+
+    /// Whether registers written by [`Defe::set_input`] or [`Defe::configure`]
+    /// are still waiting to be latched at the next frame start.
+    pub fn input_pending(&self) -> bool {
+        unsafe { reg(DEFE_FRM_CTRL).read_volatile() & FRM_CTRL_REG_RDY != 0 }
+    }
+
+    /// Stop processing. The DEBE layer fed by the front-end shows nothing afterwards.
+    pub fn stop(&mut self) {
+        unsafe {
+            modify(DEFE_FRM_CTRL, FRM_CTRL_FRM_START, 0);
+        }
+        self.format = None;
+    }
+
+    /// Load bilinear polyphase coefficients into both channels.
+    ///
+    /// Horizontal filters have 8 taps (two registers per phase, center tap 3),
+    /// vertical filters have 4 taps (center tap 1). Taps are signed 8-bit, sum 64.
+    unsafe fn load_filter_coefficients() {
+        // The CPU owns the coefficient RAM while COEF_ACCESS_CTRL is set
+        modify(DEFE_FRM_CTRL, 0, FRM_CTRL_COEF_ACCESS_CTRL);
+        for phase in 0..FILTER_PHASES {
+            let next = (phase as u32 * 64 / FILTER_PHASES as u32) & 0xFF;
+            let center = 64 - next;
+
+            let horz0 = center << 24;
+            let horz1 = next;
+            let vert = (center << 8) | (next << 16);
+
+            write(DEFE_CH0_HORZCOEF0 + phase * 4, horz0);
+            write(DEFE_CH0_HORZCOEF1 + phase * 4, horz1);
+            write(DEFE_CH0_VERTCOEF + phase * 4, vert);
+            write(DEFE_CH1_HORZCOEF0 + phase * 4, horz0);
+            write(DEFE_CH1_HORZCOEF1 + phase * 4, horz1);
+            write(DEFE_CH1_VERTCOEF + phase * 4, vert);
+        }
+        modify(DEFE_FRM_CTRL, FRM_CTRL_COEF_ACCESS_CTRL, FRM_CTRL_COEF_RDY);
+    }
+}
+
+impl<'a> Drop for Defe<'a> {
+    fn drop(&mut self) {
+        self.stop();
+        unsafe { write(DEFE_EN, 0) };
+    }
+}
+
+/// Pack a (width, height) size into the `(h - 1) << 16 | (w - 1)` register layout.
+#[inline]
+fn size_bits(w: u32, h: u32) -> u32 {
+    ((h - 1) << 16) | (w - 1)
+}
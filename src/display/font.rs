@@ -0,0 +1,215 @@
+//! Built-in 8x16 bitmap font for the text [`Console`](super::Console).
+//!
+//! Covers printable ASCII (0x20..=0x7E). One byte per row, most significant
+//! bit on the left. Capitals use rows 1..=10, descenders reach row 13, and
+//! rows 14..=15 stay empty for the console's underline cursor.
+
+/// Glyph width in pixels.
+pub const FONT_WIDTH: u16 = 8;
+/// Glyph height in pixels.
+pub const FONT_HEIGHT: u16 = 16;
+
+const FIRST: u8 = 0x20;
+
+/// Bitmap for `c`; characters outside printable ASCII render as `?`.
+pub(super) fn glyph(c: char) -> &'static [u8; 16] {
+    let index = match c {
+        ' '..='~' => c as u8 - FIRST,
+        _ => b'?' - FIRST,
+    };
+    &FONT[index as usize]
+}
+
+#[rustfmt::skip]
+static FONT: [[u8; 16]; 95] = [
+    // ' '
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '!'
+    [0x00, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '"'
+    [0x00, 0x6C, 0x6C, 0x24, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '#'
+    [0x00, 0x00, 0x48, 0x48, 0xFC, 0x48, 0x48, 0xFC, 0x48, 0x48, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '$'
+    [0x10, 0x7C, 0x92, 0x90, 0x90, 0x7C, 0x12, 0x12, 0x92, 0x7C, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '%'
+    [0x00, 0x00, 0xC2, 0xC4, 0x08, 0x10, 0x20, 0x40, 0x86, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '&'
+    [0x00, 0x30, 0x48, 0x48, 0x30, 0x70, 0x8A, 0x84, 0x84, 0x8A, 0x72, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '\''
+    [0x00, 0x10, 0x10, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '('
+    [0x00, 0x08, 0x10, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x10, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // ')'
+    [0x00, 0x20, 0x10, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x10, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '*'
+    [0x00, 0x00, 0x00, 0x00, 0x44, 0x28, 0xFE, 0x28, 0x44, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '+'
+    [0x00, 0x00, 0x00, 0x00, 0x10, 0x10, 0xFE, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // ','
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x10, 0x20, 0x00, 0x00, 0x00],
+    // '-'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '.'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '/'
+    [0x00, 0x02, 0x04, 0x04, 0x08, 0x10, 0x10, 0x20, 0x40, 0x40, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '0'
+    [0x00, 0x38, 0x44, 0x82, 0x86, 0x8A, 0x92, 0xA2, 0xC2, 0x44, 0x38, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '1'
+    [0x00, 0x10, 0x30, 0x50, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '2'
+    [0x00, 0x7C, 0x82, 0x02, 0x02, 0x04, 0x18, 0x20, 0x40, 0x80, 0xFE, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '3'
+    [0x00, 0x7C, 0x82, 0x02, 0x02, 0x3C, 0x02, 0x02, 0x02, 0x82, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '4'
+    [0x00, 0x0C, 0x14, 0x24, 0x44, 0x84, 0xFE, 0x04, 0x04, 0x04, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '5'
+    [0x00, 0xFE, 0x80, 0x80, 0x80, 0xFC, 0x02, 0x02, 0x02, 0x82, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '6'
+    [0x00, 0x3C, 0x40, 0x80, 0x80, 0xFC, 0x82, 0x82, 0x82, 0x82, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '7'
+    [0x00, 0xFE, 0x02, 0x02, 0x04, 0x08, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '8'
+    [0x00, 0x7C, 0x82, 0x82, 0x82, 0x7C, 0x82, 0x82, 0x82, 0x82, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '9'
+    [0x00, 0x7C, 0x82, 0x82, 0x82, 0x82, 0x7E, 0x02, 0x02, 0x04, 0x78, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // ':'
+    [0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // ';'
+    [0x00, 0x00, 0x00, 0x00, 0x18, 0x18, 0x00, 0x00, 0x00, 0x18, 0x18, 0x10, 0x20, 0x00, 0x00, 0x00],
+    // '<'
+    [0x00, 0x00, 0x04, 0x08, 0x10, 0x20, 0x40, 0x20, 0x10, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '='
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x7C, 0x00, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '>'
+    [0x00, 0x00, 0x40, 0x20, 0x10, 0x08, 0x04, 0x08, 0x10, 0x20, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '?'
+    [0x00, 0x7C, 0x82, 0x02, 0x04, 0x08, 0x10, 0x10, 0x00, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '@'
+    [0x00, 0x7C, 0x82, 0x82, 0x9E, 0xA2, 0xA2, 0x9E, 0x80, 0x80, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'A'
+    [0x00, 0x10, 0x28, 0x44, 0x82, 0x82, 0xFE, 0x82, 0x82, 0x82, 0x82, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'B'
+    [0x00, 0xFC, 0x82, 0x82, 0x82, 0xFC, 0x82, 0x82, 0x82, 0x82, 0xFC, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'C'
+    [0x00, 0x7C, 0x82, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x82, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'D'
+    [0x00, 0xF8, 0x84, 0x82, 0x82, 0x82, 0x82, 0x82, 0x82, 0x84, 0xF8, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'E'
+    [0x00, 0xFE, 0x80, 0x80, 0x80, 0xF8, 0x80, 0x80, 0x80, 0x80, 0xFE, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'F'
+    [0x00, 0xFE, 0x80, 0x80, 0x80, 0xF8, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'G'
+    [0x00, 0x7C, 0x82, 0x80, 0x80, 0x9E, 0x82, 0x82, 0x82, 0x82, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'H'
+    [0x00, 0x82, 0x82, 0x82, 0x82, 0xFE, 0x82, 0x82, 0x82, 0x82, 0x82, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'I'
+    [0x00, 0x7C, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'J'
+    [0x00, 0x3E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x84, 0x84, 0x78, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'K'
+    [0x00, 0x82, 0x84, 0x88, 0x90, 0xE0, 0x90, 0x88, 0x84, 0x82, 0x82, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'L'
+    [0x00, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0xFE, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'M'
+    [0x00, 0x82, 0xC6, 0xAA, 0x92, 0x82, 0x82, 0x82, 0x82, 0x82, 0x82, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'N'
+    [0x00, 0x82, 0xC2, 0xC2, 0xA2, 0x92, 0x8A, 0x86, 0x86, 0x82, 0x82, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'O'
+    [0x00, 0x7C, 0x82, 0x82, 0x82, 0x82, 0x82, 0x82, 0x82, 0x82, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'P'
+    [0x00, 0xFC, 0x82, 0x82, 0x82, 0xFC, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'Q'
+    [0x00, 0x7C, 0x82, 0x82, 0x82, 0x82, 0x82, 0x82, 0x8A, 0x84, 0x7A, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'R'
+    [0x00, 0xFC, 0x82, 0x82, 0x82, 0xFC, 0x90, 0x88, 0x84, 0x82, 0x82, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'S'
+    [0x00, 0x7C, 0x82, 0x80, 0x80, 0x7C, 0x02, 0x02, 0x02, 0x82, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'T'
+    [0x00, 0xFE, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'U'
+    [0x00, 0x82, 0x82, 0x82, 0x82, 0x82, 0x82, 0x82, 0x82, 0x82, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'V'
+    [0x00, 0x82, 0x82, 0x82, 0x82, 0x82, 0x44, 0x44, 0x28, 0x28, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'W'
+    [0x00, 0x82, 0x82, 0x82, 0x82, 0x82, 0x92, 0x92, 0xAA, 0xC6, 0x82, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'X'
+    [0x00, 0x82, 0x82, 0x44, 0x28, 0x10, 0x10, 0x28, 0x44, 0x82, 0x82, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'Y'
+    [0x00, 0x82, 0x82, 0x44, 0x28, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'Z'
+    [0x00, 0xFE, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x80, 0xFE, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '['
+    [0x00, 0x3C, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x3C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '\\'
+    [0x00, 0x80, 0x40, 0x40, 0x20, 0x10, 0x10, 0x08, 0x04, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // ']'
+    [0x00, 0x78, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x78, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '^'
+    [0x00, 0x10, 0x28, 0x44, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '_'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFE, 0x00, 0x00, 0x00],
+    // '`'
+    [0x00, 0x20, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'a'
+    [0x00, 0x00, 0x00, 0x00, 0x7C, 0x02, 0x02, 0x7E, 0x82, 0x82, 0x7E, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'b'
+    [0x00, 0x80, 0x80, 0x80, 0xFC, 0x82, 0x82, 0x82, 0x82, 0x82, 0xFC, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'c'
+    [0x00, 0x00, 0x00, 0x00, 0x7C, 0x82, 0x80, 0x80, 0x80, 0x82, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'd'
+    [0x00, 0x02, 0x02, 0x02, 0x7E, 0x82, 0x82, 0x82, 0x82, 0x82, 0x7E, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'e'
+    [0x00, 0x00, 0x00, 0x00, 0x7C, 0x82, 0x82, 0xFE, 0x80, 0x80, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'f'
+    [0x00, 0x1C, 0x20, 0x20, 0xF8, 0x20, 0x20, 0x20, 0x20, 0x20, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'g'
+    [0x00, 0x00, 0x00, 0x00, 0x7E, 0x82, 0x82, 0x82, 0x82, 0x82, 0x7E, 0x02, 0x02, 0x7C, 0x00, 0x00],
+    // 'h'
+    [0x00, 0x80, 0x80, 0x80, 0xFC, 0x82, 0x82, 0x82, 0x82, 0x82, 0x82, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'i'
+    [0x00, 0x10, 0x00, 0x00, 0x30, 0x10, 0x10, 0x10, 0x10, 0x10, 0x38, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'j'
+    [0x00, 0x04, 0x00, 0x00, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x84, 0x78, 0x00, 0x00],
+    // 'k'
+    [0x00, 0x80, 0x80, 0x80, 0x84, 0x88, 0x90, 0xE0, 0x90, 0x88, 0x84, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'l'
+    [0x00, 0x30, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x38, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'm'
+    [0x00, 0x00, 0x00, 0x00, 0xEC, 0x92, 0x92, 0x92, 0x92, 0x92, 0x92, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'n'
+    [0x00, 0x00, 0x00, 0x00, 0xBC, 0xC2, 0x82, 0x82, 0x82, 0x82, 0x82, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'o'
+    [0x00, 0x00, 0x00, 0x00, 0x7C, 0x82, 0x82, 0x82, 0x82, 0x82, 0x7C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'p'
+    [0x00, 0x00, 0x00, 0x00, 0xFC, 0x82, 0x82, 0x82, 0x82, 0x82, 0xFC, 0x80, 0x80, 0x80, 0x00, 0x00],
+    // 'q'
+    [0x00, 0x00, 0x00, 0x00, 0x7E, 0x82, 0x82, 0x82, 0x82, 0x82, 0x7E, 0x02, 0x02, 0x02, 0x00, 0x00],
+    // 'r'
+    [0x00, 0x00, 0x00, 0x00, 0xBC, 0xC2, 0x80, 0x80, 0x80, 0x80, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 's'
+    [0x00, 0x00, 0x00, 0x00, 0x7C, 0x80, 0x80, 0x7C, 0x02, 0x02, 0xFC, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 't'
+    [0x00, 0x20, 0x20, 0x20, 0xFC, 0x20, 0x20, 0x20, 0x20, 0x20, 0x1C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'u'
+    [0x00, 0x00, 0x00, 0x00, 0x82, 0x82, 0x82, 0x82, 0x82, 0x82, 0x7E, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'v'
+    [0x00, 0x00, 0x00, 0x00, 0x82, 0x82, 0x44, 0x44, 0x28, 0x28, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'w'
+    [0x00, 0x00, 0x00, 0x00, 0x82, 0x82, 0x92, 0x92, 0x92, 0x92, 0x6C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'x'
+    [0x00, 0x00, 0x00, 0x00, 0x82, 0x44, 0x28, 0x10, 0x28, 0x44, 0x82, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // 'y'
+    [0x00, 0x00, 0x00, 0x00, 0x82, 0x82, 0x82, 0x82, 0x82, 0x82, 0x7E, 0x02, 0x02, 0x7C, 0x00, 0x00],
+    // 'z'
+    [0x00, 0x00, 0x00, 0x00, 0xFE, 0x04, 0x08, 0x10, 0x20, 0x40, 0xFE, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '{'
+    [0x00, 0x0C, 0x10, 0x10, 0x10, 0x60, 0x10, 0x10, 0x10, 0x10, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '|'
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x00, 0x00, 0x00],
+    // '}'
+    [0x00, 0x60, 0x10, 0x10, 0x10, 0x0C, 0x10, 0x10, 0x10, 0x10, 0x60, 0x00, 0x00, 0x00, 0x00, 0x00],
+    // '~'
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x62, 0x92, 0x8C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+];
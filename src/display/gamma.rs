@@ -0,0 +1,112 @@
+//! TCON gamma correction.
+//!
+//! The TCON maps every output pixel through a 256-entry table before FRM
+//! dithering and the panel interface. Entry `i` holds `0x00RRGGBB`, the output
+//! for input level `i` of each channel, so the three channels can use different
+//! curves. The table lives at TCON + 0x400 and is enabled by TCON_CTRL_REG[30].
+
+use super::{Display, PixelFormat};
+use crate::pac;
+
+const TCON_GAMMA_TABLE: usize = 0x400;
+const TCON_CTRL_GAMMA_EN: u32 = 1 << 30;
+
+/// Number of entries in the gamma table.
+pub const GAMMA_TABLE_LEN: usize = 256;
+
+/// Per-channel curve: output level for every input level.
+pub fn gamma_curve(gamma: f32) -> [u8; GAMMA_TABLE_LEN] {
+    let mut curve = [0u8; GAMMA_TABLE_LEN];
+    for (i, out) in curve.iter_mut().enumerate() {
+        let x = i as f32 / 255.0;
+        *out = (powf(x, gamma) * 255.0 + 0.5).clamp(0.0, 255.0) as u8;
+    }
+    curve
+}
+
+/// Gamma table applying the same exponent to all three channels.
+///
+/// `gamma > 1.0` darkens mid-tones, `1.0` is the identity.
+pub fn gamma_table(gamma: f32) -> [u32; GAMMA_TABLE_LEN] {
+    let curve = gamma_curve(gamma);
+    gamma_table_rgb(&curve, &curve, &curve)
+}
+
+/// Gamma table from separate red, green and blue curves.
+pub fn gamma_table_rgb(
+    r: &[u8; GAMMA_TABLE_LEN],
+    g: &[u8; GAMMA_TABLE_LEN],
+    b: &[u8; GAMMA_TABLE_LEN],
+) -> [u32; GAMMA_TABLE_LEN] {
+    let mut table = [0u32; GAMMA_TABLE_LEN];
+    for (i, entry) in table.iter_mut().enumerate() {
+        *entry = ((r[i] as u32) << 16) | ((g[i] as u32) << 8) | b[i] as u32;
+    }
+    table
+}
+
+/// `x^y` for `x` in `[0, 1]`, accurate to well below one 8-bit step.
+fn powf(x: f32, y: f32) -> f32 {
+    if x <= 0.0 {
+        return if y == 0.0 { 1.0 } else { 0.0 };
+    }
+    exp2(y * log2(x))
+}
+
+fn log2(x: f32) -> f32 {
+    // x = m * 2^e with m in [1, 2)
+    let bits = x.to_bits();
+    let e = ((bits >> 23) & 0xFF) as i32 - 127;
+    let t = f32::from_bits((bits & 0x007F_FFFF) | 0x3F80_0000) - 1.0;
+    // log2(1 + t) for t in [0, 1)
+    let p = t * (1.438_545_4 + t * (-0.678_071_5 + t * (0.323_610_5 - t * 0.084_273_16)));
+    e as f32 + p
+}
+
+fn exp2(y: f32) -> f32 {
+    if y < -126.0 {
+        return 0.0;
+    }
+    if y >= 128.0 {
+        return f32::INFINITY;
+    }
+    let i = y as i32 - (y < 0.0 && y != (y as i32) as f32) as i32;
+    let f = y - i as f32;
+    // 2^f for f in [0, 1)
+    let p = 1.0 + f * (0.695_430_0 + f * (0.226_940_1 + f * 0.077_380_64));
+    f32::from_bits(((i + 127) as u32) << 23) * p
+}
+
+impl<P: PixelFormat> Display<P> {
+    /// Load a gamma table and enable gamma correction.
+    ///
+    /// Entry `i` is `0x00RRGGBB`, see [`gamma_table`] and [`gamma_table_rgb`].
+    /// Correction is switched off while the table is written, so call this
+    /// right after [`Display::wait_vblank`] to avoid a visibly wrong frame.
+    pub fn set_gamma(&mut self, table: &[u32; GAMMA_TABLE_LEN]) {
+        let tcon = unsafe { &*pac::Tcon::ptr() };
+        tcon.tcon_ctrl_reg()
+            .modify(|r, w| unsafe { w.bits(r.bits() & !TCON_CTRL_GAMMA_EN) });
+
+        let lut = unsafe { (pac::Tcon::ptr() as *mut u8).add(TCON_GAMMA_TABLE) as *mut u32 };
+        for (i, &entry) in table.iter().enumerate() {
+            unsafe { lut.add(i).write_volatile(entry & 0x00FF_FFFF) };
+        }
+
+        tcon.tcon_ctrl_reg()
+            .modify(|r, w| unsafe { w.bits(r.bits() | TCON_CTRL_GAMMA_EN) });
+    }
+
+    /// Disable gamma correction; pixels reach the panel unchanged.
+    pub fn disable_gamma(&mut self) {
+        let tcon = unsafe { &*pac::Tcon::ptr() };
+        tcon.tcon_ctrl_reg()
+            .modify(|r, w| unsafe { w.bits(r.bits() & !TCON_CTRL_GAMMA_EN) });
+    }
+
+    /// Whether gamma correction is enabled.
+    pub fn gamma_enabled(&self) -> bool {
+        let tcon = unsafe { &*pac::Tcon::ptr() };
+        tcon.tcon_ctrl_reg().read().bits() & TCON_CTRL_GAMMA_EN != 0
+    }
+}
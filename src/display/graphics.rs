@@ -0,0 +1,189 @@
+//! embedded-graphics support: `DrawTarget` for the framebuffer display.
+//!
+//! Implemented for the direct colour formats listed on [`GraphicsFormat`];
+//! palette framebuffers are drawn with the index-based methods instead.
+//! Pixels are written straight into the draw buffer (see
+//! [`Display::framebuffer`]). Rectangle fills go row by row through
+//! [`Display::fill_rect`], and fully visible `fill_contiguous` areas are
+//! streamed row by row without per-pixel bounds checks.
+//!
+//! [`SpiPanel`] gets a `DrawTarget<Color = Rgb565>` on top of its [`Canvas`]
+//! methods; changes reach the panel with [`SpiPanel::flush`].
+
+use core::convert::Infallible;
+
+use embedded_graphics_core::draw_target::DrawTarget;
+use embedded_graphics_core::geometry::{Dimensions, OriginDimensions, Size};
+use embedded_graphics_core::pixelcolor::raw::{RawData, RawU16, RawU24};
+use embedded_graphics_core::pixelcolor::{PixelColor, Rgb555, Rgb565, Rgb888};
+use embedded_graphics_core::primitives::Rectangle;
+use embedded_graphics_core::Pixel;
+
+use super::spi_panel::SpiPanel;
+use super::{pixel, Canvas, Display, PixelFormat};
+use crate::spi;
+
+/// Framebuffer format with a matching embedded-graphics colour type.
+pub trait GraphicsFormat: PixelFormat {
+    type EgColor: PixelColor;
+
+    /// Framebuffer value of `color`, opaque for formats with alpha.
+    fn raw(color: Self::EgColor) -> Self::Color;
+}
+
+impl GraphicsFormat for pixel::Rgb565 {
+    type EgColor = Rgb565;
+
+    #[inline]
+    fn raw(color: Rgb565) -> u16 {
+        RawU16::from(color).into_inner()
+    }
+}
+
+impl GraphicsFormat for pixel::Argb1555 {
+    type EgColor = Rgb555;
+
+    #[inline]
+    fn raw(color: Rgb555) -> u16 {
+        RawU16::from(color).into_inner() | 0x8000
+    }
+}
+
+impl GraphicsFormat for pixel::Xrgb8888 {
+    type EgColor = Rgb888;
+
+    #[inline]
+    fn raw(color: Rgb888) -> u32 {
+        RawU24::from(color).into_inner()
+    }
+}
+
+impl GraphicsFormat for pixel::Argb8888 {
+    type EgColor = Rgb888;
+
+    #[inline]
+    fn raw(color: Rgb888) -> u32 {
+        RawU24::from(color).into_inner() | 0xFF00_0000
+    }
+}
+
+impl<P: PixelFormat> OriginDimensions for Display<P> {
+    fn size(&self) -> Size {
+        Size::new(self.width as u32, self.height as u32)
+    }
+}
+
+impl<P: GraphicsFormat> DrawTarget for Display<P> {
+    type Color = P::EgColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (w, h) = (self.width as i32, self.height as i32);
+        for Pixel(p, color) in pixels {
+            if p.x >= 0 && p.x < w && p.y >= 0 && p.y < h {
+                unsafe {
+                    let offset = p.y as usize * w as usize + p.x as usize;
+                    P::write(self.fb, offset, P::raw(color));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let drawable = area.intersection(&self.bounding_box());
+        if drawable.is_zero_sized() {
+            return Ok(());
+        }
+        if drawable != *area {
+            // Partially off-screen: fall back to per-pixel clipping
+            return self.draw_iter(
+                area.points()
+                    .zip(colors)
+                    .filter(|(p, _)| drawable.contains(*p))
+                    .map(|(p, c)| Pixel(p, c)),
+            );
+        }
+
+        let stride = self.width as usize;
+        let x = area.top_left.x as usize;
+        let row_len = area.size.width as usize;
+        let mut colors = colors.into_iter();
+        for row in 0..area.size.height as usize {
+            let offset = (area.top_left.y as usize + row) * stride + x;
+            for (i, color) in (offset..offset + row_len).zip(&mut colors) {
+                unsafe { P::write(self.fb, i, P::raw(color)) };
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.bounding_box());
+        if !area.is_zero_sized() {
+            self.fill_rect(
+                area.top_left.x as u16,
+                area.top_left.y as u16,
+                area.size.width as u16,
+                area.size.height as u16,
+                P::raw(color),
+            );
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.fill(P::raw(color));
+        Ok(())
+    }
+}
+
+impl<'d, T: spi::Instance> OriginDimensions for SpiPanel<'d, T> {
+    fn size(&self) -> Size {
+        Size::new(Canvas::width(self) as u32, Canvas::height(self) as u32)
+    }
+}
+
+impl<'d, T: spi::Instance> DrawTarget for SpiPanel<'d, T> {
+    type Color = Rgb565;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (w, h) = (Canvas::width(self) as i32, Canvas::height(self) as i32);
+        for Pixel(p, color) in pixels {
+            if p.x >= 0 && p.x < w && p.y >= 0 && p.y < h {
+                Canvas::set_pixel(self, p.x as u16, p.y as u16, pixel::Rgb565::raw(color));
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.bounding_box());
+        if !area.is_zero_sized() {
+            Canvas::fill_rect(
+                self,
+                area.top_left.x as u16,
+                area.top_left.y as u16,
+                area.size.width as u16,
+                area.size.height as u16,
+                pixel::Rgb565::raw(color),
+            );
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        Canvas::fill(self, pixel::Rgb565::raw(color));
+        Ok(())
+    }
+}
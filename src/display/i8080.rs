@@ -0,0 +1,221 @@
+//! TCON0 CPU (i8080) interface for panels with their own GRAM
+//! (ILI9341, ST7789, ST7796, ...).
+//!
+//! Pin usage in CPU mode (function 2 on port D):
+//! - LCD_CLK -> WR, LCD_DE -> RD, LCD_HSYNC -> RS (A1), LCD_VSYNC -> CS
+//! - LCD_Dx -> data bus, width selected by [`CpuBus`]
+//!
+//! Panel init sequences are sent with [`Display::cpu_write`]. Pixel data is
+//! pushed by the TCON in trigger (TRI) mode: each trigger sends the
+//! memory-write command, then streams one full frame from the DEBE.
+//! Frames are triggered either one at a time with [`Display::cpu_refresh`]
+//! or continuously from the TCON interrupt after
+//! [`Display::cpu_set_auto_refresh`].
+//!
+//! The CPU interface and trigger registers are accessed by raw offset from
+//! the TCON base.
+
+use core::future::poll_fn;
+use core::task::Poll;
+
+use embassy_sync::waitqueue::AtomicWaker;
+use portable_atomic::{AtomicBool, AtomicU32, Ordering};
+
+use super::{Display, LcdConfig, PixelFormat};
+use crate::pac;
+
+// TCON0 CPU interface register offsets
+const TCON0_CPU_IF: usize = 0x060;
+const TCON0_CPU_WR: usize = 0x064;
+const TCON0_CPU_RD0: usize = 0x068;
+const TCON0_CPU_RD1: usize = 0x06C;
+const TCON0_CPU_TRI0: usize = 0x160;
+const TCON0_CPU_TRI1: usize = 0x164;
+const TCON0_CPU_TRI2: usize = 0x168;
+
+// TCON0_CPU_IF fields
+const CPU_IF_TRI_EN: u32 = 1 << 0;
+const CPU_IF_TRI_START: u32 = 1 << 1;
+const CPU_IF_RD_FLAG: u32 = 1 << 22;
+const CPU_IF_WR_FLAG: u32 = 1 << 23;
+/// A1 level for `CPU_WR`/`CPU_RD` accesses (0 = command, 1 = data)
+const CPU_IF_CA: u32 = 1 << 25;
+/// A1 level while the trigger streams pixel data
+const CPU_IF_DA: u32 = 1 << 26;
+const CPU_IF_MODE_SHIFT: u32 = 28;
+
+// TCON_INT_REG0 trigger-finish bits
+pub(super) const INT0_TCON0_TRI_FINISH_EN: u32 = 1 << 26;
+pub(super) const INT0_TCON0_TRI_FINISH_FLAG: u32 = 1 << 10;
+
+/// MIPI DCS `write_memory_start`, sent before every triggered frame.
+pub const CPU_MEMORY_WRITE: u32 = 0x2C;
+
+static TRI_WAKER: AtomicWaker = AtomicWaker::new();
+static TRI_COUNT: AtomicU32 = AtomicU32::new(0);
+static AUTO_REFRESH: AtomicBool = AtomicBool::new(false);
+
+/// i8080 data bus width and pixel packing (TCON0_CPU_IF CPU_MOD).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum CpuBus {
+    /// 18-bit bus, one RGB666 pixel per write
+    Bus18 = 0b0000,
+    /// 16-bit bus, one RGB565 pixel per write
+    Bus16 = 0b0010,
+}
+
+impl CpuBus {
+    /// Color depth the panel receives, for FRM dithering.
+    pub const fn bits_per_pixel(self) -> u8 {
+        match self {
+            CpuBus::Bus18 => 18,
+            CpuBus::Bus16 => 16,
+        }
+    }
+}
+
+#[inline]
+fn tcon_reg(offset: usize) -> *mut u32 {
+    (pac::Tcon::ptr() as usize + offset) as *mut u32
+}
+
+#[inline]
+unsafe fn cpu_if_modify(clear: u32, set: u32) {
+    let r = tcon_reg(TCON0_CPU_IF);
+    r.write_volatile((r.read_volatile() & !clear) | set);
+}
+
+#[inline]
+fn wait_wr_idle() {
+    while unsafe { tcon_reg(TCON0_CPU_IF).read_volatile() } & CPU_IF_WR_FLAG != 0 {
+        core::hint::spin_loop();
+    }
+}
+
+#[inline]
+fn wait_rd_idle() {
+    while unsafe { tcon_reg(TCON0_CPU_IF).read_volatile() } & CPU_IF_RD_FLAG != 0 {
+        core::hint::spin_loop();
+    }
+}
+
+#[inline]
+fn wait_frame_idle() {
+    while unsafe { tcon_reg(TCON0_CPU_IF).read_volatile() } & CPU_IF_TRI_START != 0 {
+        core::hint::spin_loop();
+    }
+}
+
+/// Write one word on the bus with A1 = `data`.
+unsafe fn bus_write(data: bool, value: u32) {
+    wait_wr_idle();
+    if data {
+        cpu_if_modify(0, CPU_IF_CA);
+    } else {
+        cpu_if_modify(CPU_IF_CA, 0);
+    }
+    tcon_reg(TCON0_CPU_WR).write_volatile(value & 0x00FF_FFFF);
+}
+
+/// Send the memory-write command and start streaming one frame.
+unsafe fn start_frame() {
+    bus_write(false, CPU_MEMORY_WRITE);
+    wait_wr_idle();
+    cpu_if_modify(0, CPU_IF_TRI_START);
+}
+
+/// Called from the TCON IRQ when a triggered frame has been sent.
+pub(super) fn on_tri_finish() {
+    TRI_COUNT.fetch_add(1, Ordering::Release);
+    TRI_WAKER.wake();
+    if AUTO_REFRESH.load(Ordering::Acquire) {
+        unsafe { start_frame() };
+    }
+}
+
+impl<P: PixelFormat> Display<P> {
+    /// Program the TCON0 CPU interface and trigger block for `bus`.
+    pub(super) unsafe fn cpu_if_init(config: &LcdConfig, bus: CpuBus) {
+        let w = config.width as u32;
+        let h = config.height as u32;
+        let h_blank = config.h_front_porch as u32 + config.h_back_porch as u32 + config.h_sync_len as u32;
+
+        tcon_reg(TCON0_CPU_IF).write_volatile(((bus as u32) << CPU_IF_MODE_SHIFT) | CPU_IF_DA | CPU_IF_TRI_EN);
+
+        // One block per line; the horizontal blanking becomes the gap between blocks
+        tcon_reg(TCON0_CPU_TRI0).write_volatile(((h_blank.max(1) - 1) & 0xFFF) << 16 | ((w - 1) & 0xFFF));
+        tcon_reg(TCON0_CPU_TRI1).write_volatile((h - 1) & 0xFFFF);
+        // Start on TRI_START, no start delay
+        tcon_reg(TCON0_CPU_TRI2).write_volatile(0);
+    }
+
+    /// Send a command followed by its parameters (A1 low, then high).
+    ///
+    /// Waits for a running frame transfer to finish first. Auto refresh
+    /// should be disabled while sending panel init sequences.
+    pub fn cpu_write(&self, cmd: u32, params: &[u32]) {
+        critical_section::with(|_| unsafe {
+            wait_frame_idle();
+            bus_write(false, cmd);
+            for &p in params {
+                bus_write(true, p);
+            }
+            wait_wr_idle();
+        });
+    }
+
+    /// Send a command, then read `buf.len()` words back (A1 high).
+    ///
+    /// Many controllers return a dummy word first; that is left to the caller.
+    pub fn cpu_read(&self, cmd: u32, buf: &mut [u32]) {
+        critical_section::with(|_| unsafe {
+            wait_frame_idle();
+            bus_write(false, cmd);
+            wait_wr_idle();
+            cpu_if_modify(0, CPU_IF_CA);
+            for word in buf.iter_mut() {
+                // RD0 launches a bus read, RD1 returns the latched value
+                let _ = tcon_reg(TCON0_CPU_RD0).read_volatile();
+                wait_rd_idle();
+                *word = tcon_reg(TCON0_CPU_RD1).read_volatile() & 0x00FF_FFFF;
+            }
+        });
+    }
+
+    /// Push the current layer contents to the panel once and wait for the
+    /// transfer to finish.
+    pub async fn cpu_refresh(&self) {
+        let size = self.framebuffer_size() as u32;
+        arm9::asm::clean_dcache_range(self.front as u32, size);
+
+        let start = TRI_COUNT.load(Ordering::Acquire);
+        critical_section::with(|_| unsafe {
+            wait_frame_idle();
+            start_frame();
+        });
+
+        poll_fn(|cx| {
+            TRI_WAKER.register(cx.waker());
+            if TRI_COUNT.load(Ordering::Acquire) != start {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Keep re-triggering frames from the TCON interrupt so the panel GRAM
+    /// follows the DEBE output continuously.
+    pub fn cpu_set_auto_refresh(&self, enable: bool) {
+        let was = AUTO_REFRESH.swap(enable, Ordering::AcqRel);
+        if enable && !was {
+            critical_section::with(|_| unsafe {
+                wait_frame_idle();
+                start_frame();
+            });
+        }
+    }
+}
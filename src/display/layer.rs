@@ -0,0 +1,374 @@
+//! DEBE layer compositing.
+//!
+//! The back-end blends four layers in hardware. Each layer has its own
+//! framebuffer (or the DEFE video channel), size, position, pixel format,
+//! alpha mode, color key enable, blending pipe and priority.
+//!
+//! Blending order: within each pipe, layers are stacked by priority
+//! (3 = top). Pipe 1 is then alpha-blended over pipe 0.
+//!
+//! The PAC exposes one named register per layer, so index-based access
+//! uses raw offsets from the DEBE base.
+//!
+//! Palette formats look every pixel up in one shared 256-entry ARGB table in
+//! DEBE internal SRAM at +0x5000.
+
+use super::{Display, PixelFormat};
+use crate::pac;
+
+// DEBE per-layer register offsets (layer n at +4*n)
+const DEBE_LAY_SIZE: usize = 0x810;
+const DEBE_LAY_CODNT: usize = 0x820;
+const DEBE_LAY_LINEWIDTH: usize = 0x840;
+const DEBE_LAY_FB_ADDR: usize = 0x850;
+const DEBE_LAY_ATTCTL0: usize = 0x890;
+const DEBE_LAY_ATTCTL1: usize = 0x8A0;
+
+// DEBE global registers
+const DEBE_BACK_COLOR: usize = 0x804;
+/// High 4 bits of each layer's bit-address, 8 bits per layer
+const DEBE_LAY_FB_HI_ADDR: usize = 0x860;
+const DEBE_CK_MAX: usize = 0x880;
+const DEBE_CK_MIN: usize = 0x884;
+const DEBE_CK_CFG: usize = 0x888;
+const DEBE_PALETTE_TABLE: usize = 0x5000;
+
+// ATTCTL_REG0 fields
+const ATTCTL0_GLB_ALPHA_EN: u32 = 1 << 0;
+pub(super) const ATTCTL0_VDO_EN: u32 = 1 << 1;
+const ATTCTL0_PRISEL_SHIFT: u32 = 10;
+const ATTCTL0_PIPESEL: u32 = 1 << 15;
+/// Color key mode: 01 = pixels of this layer matching the key are transparent
+const ATTCTL0_CKEN: u32 = 0x1 << 18;
+/// Framebuffer values index the palette
+const ATTCTL0_PALETTE_EN: u32 = 1 << 22;
+const ATTCTL0_GLB_ALPHA_SHIFT: u32 = 24;
+
+// ATTCTL_REG1 fields
+const ATTCTL1_FBFMT_SHIFT: u32 = 8;
+
+/// Color key match for one channel: min <= value <= max
+const CK_MATCH_IN_RANGE: u32 = 0x2;
+
+#[inline]
+pub(super) fn debe_reg(offset: usize) -> *mut u32 {
+    (pac::Debe::ptr() as usize + offset) as *mut u32
+}
+
+#[inline]
+pub(super) fn debe_layer_reg(offset: usize, layer: u8) -> *mut u32 {
+    debe_reg(offset + layer as usize * 4)
+}
+
+/// Number of DEBE layers.
+pub const LAYER_COUNT: u8 = 4;
+
+/// Number of DEBE palette entries.
+pub const PALETTE_LEN: usize = 256;
+
+/// Layer framebuffer pixel format (DEBE_ATTCTL_REG1 LAY_FBFMT).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum LayerFormat {
+    Palette1 = 0x00,
+    Palette2 = 0x01,
+    Palette4 = 0x02,
+    Palette8 = 0x03,
+    Rgb655 = 0x04,
+    Rgb565 = 0x05,
+    Rgb556 = 0x06,
+    Argb1555 = 0x07,
+    Rgba5551 = 0x08,
+    Xrgb8888 = 0x09,
+    Argb8888 = 0x0A,
+    Rgb888 = 0x0B,
+    Argb4444 = 0x0C,
+    Rgba4444 = 0x0D,
+}
+
+impl LayerFormat {
+    /// Bits per pixel in memory.
+    pub const fn bits_per_pixel(self) -> u32 {
+        match self {
+            LayerFormat::Xrgb8888 | LayerFormat::Argb8888 => 32,
+            LayerFormat::Rgb888 => 24,
+            LayerFormat::Palette1 => 1,
+            LayerFormat::Palette2 => 2,
+            LayerFormat::Palette4 => 4,
+            LayerFormat::Palette8 => 8,
+            _ => 16,
+        }
+    }
+
+    /// Whether pixels are indices into the DEBE palette.
+    pub const fn is_palette(self) -> bool {
+        matches!(
+            self,
+            LayerFormat::Palette1 | LayerFormat::Palette2 | LayerFormat::Palette4 | LayerFormat::Palette8
+        )
+    }
+
+    /// Whether the format carries a per-pixel alpha channel.
+    pub const fn has_alpha(self) -> bool {
+        matches!(
+            self,
+            LayerFormat::Argb1555
+                | LayerFormat::Rgba5551
+                | LayerFormat::Argb8888
+                | LayerFormat::Argb4444
+                | LayerFormat::Rgba4444
+        )
+    }
+}
+
+/// Where a layer takes its pixels from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LayerSource {
+    /// Framebuffer in memory.
+    Framebuffer {
+        /// Byte address of the first pixel
+        addr: u32,
+        /// Line stride in bytes (0 = tightly packed)
+        stride: u32,
+        format: LayerFormat,
+    },
+    /// Scaled/converted output of the front-end ([`defe::Defe`](super::defe::Defe)).
+    /// The layer size must match the DEFE output size.
+    Defe,
+}
+
+/// Layer alpha mode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LayerAlpha {
+    /// Use the alpha channel of each pixel (ARGB formats).
+    PerPixel,
+    /// Use one alpha value for the whole layer (255 = opaque).
+    Global(u8),
+}
+
+/// Alpha-blending pipe.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Pipe {
+    /// Bottom pipe
+    Pipe0,
+    /// Top pipe, blended over pipe 0
+    Pipe1,
+}
+
+/// Color key range, shared by all layers. Colors are RGB888 (`0x00RRGGBB`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ColorKey {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl ColorKey {
+    /// Match exactly one color.
+    pub const fn exact(rgb: u32) -> Self {
+        Self { min: rgb, max: rgb }
+    }
+}
+
+/// Complete configuration of one DEBE layer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LayerConfig {
+    pub source: LayerSource,
+    /// Position of the top-left corner on screen. May be negative or off-screen.
+    pub x: i16,
+    pub y: i16,
+    pub width: u16,
+    pub height: u16,
+    pub alpha: LayerAlpha,
+    /// Make pixels matching the display's [`ColorKey`] transparent.
+    pub color_key: bool,
+    pub pipe: Pipe,
+    /// Stacking priority within the pipe (0..=3, 3 = top).
+    pub priority: u8,
+}
+
+impl LayerConfig {
+    /// Opaque, full-alpha framebuffer layer at (0, 0), tightly packed.
+    pub const fn framebuffer(addr: u32, width: u16, height: u16, format: LayerFormat) -> Self {
+        Self {
+            source: LayerSource::Framebuffer {
+                addr,
+                stride: 0,
+                format,
+            },
+            x: 0,
+            y: 0,
+            width,
+            height,
+            alpha: LayerAlpha::Global(255),
+            color_key: false,
+            pipe: Pipe::Pipe0,
+            priority: 0,
+        }
+    }
+
+    /// Opaque layer fed by the DEFE at (0, 0).
+    pub const fn defe(width: u16, height: u16) -> Self {
+        Self {
+            source: LayerSource::Defe,
+            x: 0,
+            y: 0,
+            width,
+            height,
+            alpha: LayerAlpha::Global(255),
+            color_key: false,
+            pipe: Pipe::Pipe0,
+            priority: 0,
+        }
+    }
+}
+
+impl<P: PixelFormat> Display<P> {
+    /// Configure and enable a DEBE layer (0..=3).
+    ///
+    /// The new settings are latched together at the next register load.
+    pub fn configure_layer(&self, layer: u8, config: &LayerConfig) {
+        assert!(layer < LAYER_COUNT);
+        unsafe {
+            Self::write_layer(layer, config);
+            Self::layer_enable(layer, true);
+            Self::debe_load_regs();
+        }
+    }
+
+    /// Enable or disable a DEBE layer without touching its configuration.
+    pub fn set_layer_enabled(&self, layer: u8, enable: bool) {
+        assert!(layer < LAYER_COUNT);
+        unsafe {
+            Self::layer_enable(layer, enable);
+            Self::debe_load_regs();
+        }
+    }
+
+    /// Move a layer. Only the coordinate register is written.
+    pub fn set_layer_position(&self, layer: u8, x: i16, y: i16) {
+        assert!(layer < LAYER_COUNT);
+        unsafe {
+            debe_layer_reg(DEBE_LAY_CODNT, layer).write_volatile(coord_bits(x, y));
+            Self::debe_load_regs();
+        }
+    }
+
+    /// Change the framebuffer address of a layer (e.g. to scroll within a larger image).
+    pub fn set_layer_framebuffer(&self, layer: u8, addr: u32) {
+        assert!(layer < LAYER_COUNT);
+        unsafe {
+            Self::write_layer_addr(layer, addr);
+            Self::debe_load_regs();
+        }
+    }
+
+    /// Change the alpha mode of a layer.
+    pub fn set_layer_alpha(&self, layer: u8, alpha: LayerAlpha) {
+        assert!(layer < LAYER_COUNT);
+        unsafe {
+            let reg = debe_layer_reg(DEBE_LAY_ATTCTL0, layer);
+            let mut val = reg.read_volatile() & !(ATTCTL0_GLB_ALPHA_EN | (0xFF << ATTCTL0_GLB_ALPHA_SHIFT));
+            if let LayerAlpha::Global(a) = alpha {
+                val |= ATTCTL0_GLB_ALPHA_EN | ((a as u32) << ATTCTL0_GLB_ALPHA_SHIFT);
+            }
+            reg.write_volatile(val);
+            Self::debe_load_regs();
+        }
+    }
+
+    /// Set the color key range used by layers with `color_key` enabled.
+    pub fn set_color_key(&self, key: ColorKey) {
+        unsafe {
+            debe_reg(DEBE_CK_MAX).write_volatile(key.max & 0x00FF_FFFF);
+            debe_reg(DEBE_CK_MIN).write_volatile(key.min & 0x00FF_FFFF);
+            // Match when min <= R, G, B <= max
+            debe_reg(DEBE_CK_CFG)
+                .write_volatile((CK_MATCH_IN_RANGE << 16) | (CK_MATCH_IN_RANGE << 8) | CK_MATCH_IN_RANGE);
+            Self::debe_load_regs();
+        }
+    }
+
+    /// Set the background color shown where no layer covers the screen (`0x00RRGGBB`).
+    pub fn set_background_color(&self, rgb: u32) {
+        unsafe {
+            debe_reg(DEBE_BACK_COLOR).write_volatile(rgb & 0x00FF_FFFF);
+            Self::debe_load_regs();
+        }
+    }
+
+    /// Load palette entries starting at index `first`. Colors are ARGB8888
+    /// (`0xAARRGGBB`); alpha applies with [`LayerAlpha::PerPixel`].
+    ///
+    /// The table is shared by all layers using a palette format.
+    pub fn set_palette(&self, first: u8, colors: &[u32]) {
+        assert!(first as usize + colors.len() <= PALETTE_LEN);
+        let table = debe_reg(DEBE_PALETTE_TABLE);
+        for (i, &color) in colors.iter().enumerate() {
+            unsafe { table.add(first as usize + i).write_volatile(color) };
+        }
+    }
+
+    /// Write all registers of one layer (not latched until the next register load).
+    pub(super) unsafe fn write_layer(layer: u8, config: &LayerConfig) {
+        assert!(config.width > 0 && config.height > 0);
+        assert!(config.priority <= 3);
+
+        debe_layer_reg(DEBE_LAY_SIZE, layer)
+            .write_volatile(((config.height as u32 - 1) << 16) | (config.width as u32 - 1));
+        debe_layer_reg(DEBE_LAY_CODNT, layer).write_volatile(coord_bits(config.x, config.y));
+
+        let mut att0 = (config.priority as u32) << ATTCTL0_PRISEL_SHIFT;
+        if let LayerAlpha::Global(a) = config.alpha {
+            att0 |= ATTCTL0_GLB_ALPHA_EN | ((a as u32) << ATTCTL0_GLB_ALPHA_SHIFT);
+        }
+        if config.pipe == Pipe::Pipe1 {
+            att0 |= ATTCTL0_PIPESEL;
+        }
+        if config.color_key {
+            att0 |= ATTCTL0_CKEN;
+        }
+
+        match config.source {
+            LayerSource::Framebuffer { addr, stride, format } => {
+                // Line width register is in bits
+                let stride_bits = if stride == 0 {
+                    config.width as u32 * format.bits_per_pixel()
+                } else {
+                    stride * 8
+                };
+                debe_layer_reg(DEBE_LAY_LINEWIDTH, layer).write_volatile(stride_bits);
+                Self::write_layer_addr(layer, addr);
+                debe_layer_reg(DEBE_LAY_ATTCTL1, layer).write_volatile((format as u32) << ATTCTL1_FBFMT_SHIFT);
+                if format.is_palette() {
+                    att0 |= ATTCTL0_PALETTE_EN;
+                }
+            }
+            LayerSource::Defe => {
+                att0 |= ATTCTL0_VDO_EN;
+            }
+        }
+
+        debe_layer_reg(DEBE_LAY_ATTCTL0, layer).write_volatile(att0);
+    }
+
+    /// Write a layer framebuffer byte address.
+    ///
+    /// The hardware takes a 35-bit bit-address: the low 32 bits go to the
+    /// per-layer FB_ADDR register, the high bits to this layer's byte of FB_HI_ADDR.
+    pub(super) unsafe fn write_layer_addr(layer: u8, addr: u32) {
+        debe_layer_reg(DEBE_LAY_FB_ADDR, layer).write_volatile(addr << 3);
+        let hi = debe_reg(DEBE_LAY_FB_HI_ADDR);
+        let shift = layer as u32 * 8;
+        let val = hi.read_volatile() & !(0xFF << shift);
+        hi.write_volatile(val | ((addr >> 29) << shift));
+    }
+}
+
+/// Pack signed (x, y) into the LAYCOOR register layout.
+#[inline]
+fn coord_bits(x: i16, y: i16) -> u32 {
+    ((y as u16 as u32) << 16) | (x as u16 as u32)
+}
@@ -0,0 +1,777 @@
+//! Display driver for F1C100S/F1C200S LCD controller (TCON0 + DEBE).
+//!
+//! Supports parallel (24/18/16-bit) RGB HV interfaces with FRM dithering and
+//! the 18/16-bit i8080 CPU interface (see [`LcdInterface`]). Serial RGB and
+//! the 8/9-bit CPU buses use LCD_D0/D1/D8, which this package lacks.
+//! Only the port D pins used by the selected interface are muxed to the LCD.
+//! The framebuffer lives in SDRAM; its format is the [`PixelFormat`] type
+//! parameter of [`Display`] (RGB565 by default, see [`pixel`]).
+//! All register access uses the `f1c100s-pac` typed register API, except
+//! index-based per-layer DEBE registers which use raw offsets.
+//!
+//! Layer 0 shows the framebuffer by default; all four DEBE layers can be
+//! reconfigured with [`Display::configure_layer`]. The [`defe`] module
+//! provides hardware scaling and YUV->RGB conversion into a DEBE layer.
+//! The [`tve`] module drives a composite TV instead of an LCD through TCON1.
+//! [`Preview`] ties CSI capture buffers to the DEFE for a zero-copy camera view.
+//!
+//! The TCON gamma table is loaded with [`Display::set_gamma`], and a PWM or
+//! GPIO [`Backlight`] can be attached for brightness control and fades.
+//! A hardware cursor ([`Display::set_cursor`]) floats above all layers.
+//! [`Display::capture`] writes the composited screen back to SDRAM.
+//! The [`spi_panel`] module drives SPI TFTs (ST7789/ILI9341/GC9A01) through the
+//! same [`Canvas`] drawing API as `Display`.
+//! [`Console`] renders text with a built-in 8x16 font and can mirror `print!`.
+//! [`Display::power_off`] / [`Display::power_on`] sequence the panel supply
+//! ([`PanelPower`]), backlight and clocks; [`Display::set_mode`] switches
+//! LCD timing at runtime, and dropping the `Display` leaves the hardware off.
+//!
+//! With the `embedded-graphics` feature, `Display` implements
+//! `embedded_graphics_core::draw_target::DrawTarget<Color = Rgb565>`.
+
+use crate::gpio::{AnyPin, DriveStrength, PinMode, Pull, SealedPin};
+use crate::pac;
+
+mod backlight;
+mod canvas;
+mod console;
+mod cursor;
+pub mod defe;
+mod font;
+mod gamma;
+#[cfg(feature = "embedded-graphics")]
+mod graphics;
+mod i8080;
+mod layer;
+pub mod pixel;
+mod power;
+mod preview;
+pub mod spi_panel;
+mod timing;
+pub mod tve;
+mod vsync;
+mod writeback;
+
+pub use backlight::Backlight;
+pub use canvas::Canvas;
+pub use console::Console;
+pub use cursor::{CursorError, CursorFormat, CursorImage, CursorSize, CURSOR_PATTERN_SIZE};
+pub use font::{FONT_HEIGHT, FONT_WIDTH};
+pub use gamma::{gamma_curve, gamma_table, gamma_table_rgb, GAMMA_TABLE_LEN};
+#[cfg(feature = "embedded-graphics")]
+pub use graphics::GraphicsFormat;
+pub use i8080::{CpuBus, CPU_MEMORY_WRITE};
+pub use layer::{ColorKey, LayerAlpha, LayerConfig, LayerFormat, LayerSource, Pipe, LAYER_COUNT, PALETTE_LEN};
+pub use pixel::{
+    argb1555, argb8888, rgb565, xrgb8888, Argb1555, Argb8888, Palette1, Palette2, Palette4, Palette8, PixelFormat,
+    Rgb565, RgbFormat, Xrgb8888,
+};
+pub use power::PanelPower;
+pub use preview::{Preview, PreviewConfig, PreviewError};
+pub use timing::{Error, LcdTiming};
+pub use vsync::InterruptHandler;
+
+/// RGB (HV) parallel data bus width.
+///
+/// The F1C100S bonds out LCD_D2..7, D10..15 and D18..23 on PD0..PD17, i.e. the
+/// top 6 bits of each channel; missing LSBs read as 0 on the panel side.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RgbBus {
+    /// 24-bit parallel, one pixel per DCLK (6 bits per channel bonded out)
+    Parallel24,
+    /// 18-bit parallel RGB666 with FRM dithering, PD0..PD17
+    Parallel18,
+    /// 16-bit parallel RGB565 with FRM dithering; PD0 and PD12 stay free
+    Parallel16,
+}
+
+/// TCON0 panel interface.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LcdInterface {
+    /// RGB with HSYNC/VSYNC/DE, continuously scanned out
+    Rgb(RgbBus),
+    /// i8080 CPU bus to a panel with its own GRAM, frames sent by trigger
+    Cpu(CpuBus),
+}
+
+impl LcdInterface {
+    /// Color depth the panel receives (16/18 enable FRM dithering).
+    pub const fn bits_per_pixel(self) -> u8 {
+        match self {
+            LcdInterface::Rgb(RgbBus::Parallel18) => 18,
+            LcdInterface::Rgb(RgbBus::Parallel16) => 16,
+            LcdInterface::Rgb(_) => 24,
+            LcdInterface::Cpu(bus) => bus.bits_per_pixel(),
+        }
+    }
+
+    /// LCD_D[23:0] lines driven by this interface, as a bit mask.
+    const fn data_lines(self) -> u32 {
+        match self {
+            LcdInterface::Rgb(RgbBus::Parallel24 | RgbBus::Parallel18) | LcdInterface::Cpu(CpuBus::Bus18) => {
+                0x00FF_FFFF
+            }
+            // RGB565 sits in the top bits of each channel: D[23:19], D[15:10], D[7:3]
+            LcdInterface::Rgb(RgbBus::Parallel16) | LcdInterface::Cpu(CpuBus::Bus16) => 0x00F8_FCF8,
+        }
+    }
+}
+
+/// Port D pin carrying LCD_D`n`, if it is bonded out.
+const fn lcd_data_pin(n: u32) -> Option<u8> {
+    match n {
+        2..=7 => Some(n as u8 - 2),
+        10..=15 => Some(n as u8 - 4),
+        18..=23 => Some(n as u8 - 6),
+        _ => None,
+    }
+}
+
+/// PD18..PD21: LCD_CLK, LCD_DE, LCD_HSYNC, LCD_VSYNC (WR, RD, RS, CS in CPU mode)
+const LCD_CTRL_PINS: u32 = 0xF << 18;
+
+/// Port D pins (bit n = PDn) used by `interface`.
+fn lcd_pin_mask(interface: LcdInterface) -> u32 {
+    let data = interface.data_lines();
+    let mut pins = LCD_CTRL_PINS;
+    for n in 0..24 {
+        if data & (1 << n) != 0 {
+            if let Some(pin) = lcd_data_pin(n) {
+                pins |= 1 << pin;
+            }
+        }
+    }
+    pins
+}
+
+/// LCD timing configuration.
+///
+/// Timings are in pixels.
+/// PLL_VIDEO and the DCLK divider are chosen to match `pixel_clock_hz` as
+/// closely as possible, see [`LcdConfig::timing`].
+/// In CPU mode the timing still paces the WR strobe: `pixel_clock_hz` is the
+/// write clock and the horizontal blanking is inserted between lines.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LcdConfig {
+    /// Panel interface
+    pub interface: LcdInterface,
+    pub width: u16,
+    pub height: u16,
+    pub pixel_clock_hz: u32,
+    pub h_front_porch: u16,
+    pub h_back_porch: u16,
+    pub h_sync_len: u16,
+    pub v_front_porch: u16,
+    pub v_back_porch: u16,
+    pub v_sync_len: u16,
+    /// Invert DCLK polarity
+    pub dclk_invert: bool,
+}
+
+impl LcdConfig {
+    /// 800x480 panel with typical timing (AT070TN92 / EK9716 style).
+    /// 33MHz pixel clock (PLL_VIDEO = 198MHz, divider = 6).
+    pub const fn lcd_800x480() -> Self {
+        Self {
+            interface: LcdInterface::Rgb(RgbBus::Parallel18),
+            width: 800,
+            height: 480,
+            pixel_clock_hz: 33_000_000,
+            h_front_porch: 40,
+            h_back_porch: 88,
+            h_sync_len: 48,
+            v_front_porch: 13,
+            v_back_porch: 32,
+            v_sync_len: 3,
+            dclk_invert: true,
+        }
+    }
+
+    /// 240x320 ILI9341-style panel on a 16-bit i8080 bus.
+    /// 16.5MHz write clock (PLL_VIDEO = 198MHz, divider = 12).
+    pub const fn i8080_240x320() -> Self {
+        Self {
+            interface: LcdInterface::Cpu(CpuBus::Bus16),
+            width: 240,
+            height: 320,
+            pixel_clock_hz: 16_500_000,
+            h_front_porch: 1,
+            h_back_porch: 1,
+            h_sync_len: 1,
+            v_front_porch: 1,
+            v_back_porch: 1,
+            v_sync_len: 1,
+            dclk_invert: false,
+        }
+    }
+}
+
+/// Output driven by a [`Display`].
+#[derive(Copy, Clone)]
+enum Mode {
+    Lcd(LcdConfig),
+    Tv(tve::TvConfig),
+}
+
+/// Display controller. Manages TCON0 + DEBE + framebuffer.
+///
+/// `P` is the pixel format of the framebuffer shown on layer 0.
+pub struct Display<P: PixelFormat = Rgb565> {
+    /// Output the display was set up for, restored by `power_on`
+    mode: Mode,
+    /// Achieved LCD clocking, `None` when driving the TV encoder
+    timing: Option<LcdTiming>,
+    powered: bool,
+    panel_power: Option<PanelPower>,
+    backlight: Option<Backlight>,
+    /// Hardware cursor hotspot position and hotspot offset in the image
+    cursor_pos: (i16, i16),
+    cursor_hotspot: (i16, i16),
+    /// Continuous write-back buffer, null when not capturing
+    capture_buf: *mut u32,
+    width: u16,
+    height: u16,
+    /// Buffer the drawing methods render into
+    fb: *mut P::Raw,
+    /// Buffer currently scanned out by layer 0
+    front: *mut P::Raw,
+}
+
+impl Display<Rgb565> {
+    /// Initialize the LCD display subsystem with an RGB565 framebuffer.
+    ///
+    /// `fb_addr` must point to a framebuffer in SDRAM, aligned to at least 4 bytes,
+    /// with size >= width * height * 2 bytes.
+    ///
+    /// # Safety
+    /// See [`Display::new_with_format`].
+    pub unsafe fn new(config: &LcdConfig, fb_addr: *mut u16) -> Result<Self, Error> {
+        Self::new_with_format(config, fb_addr)
+    }
+}
+
+impl<P: PixelFormat> Display<P> {
+    /// Initialize the LCD display subsystem with a framebuffer of format `P`:
+    /// ```ignore
+    /// let lcd = unsafe { Display::<Xrgb8888>::new_with_format(&config, fb) }?;
+    /// ```
+    ///
+    /// `fb_addr` must point to a framebuffer in SDRAM, aligned to at least 4 bytes,
+    /// of [`PixelFormat::buffer_size`] bytes.
+    ///
+    /// The timing is validated and the pixel clock planned before any hardware
+    /// is touched; PLL_VIDEO is reprogrammed if that gets closer to
+    /// `pixel_clock_hz`. The achieved rates are available from [`Display::timing`].
+    ///
+    /// # Safety
+    /// - Must be called after clock init.
+    /// - `fb_addr` must be a valid, writable SDRAM address.
+    /// - Only one `Display` may exist at a time.
+    pub unsafe fn new_with_format(config: &LcdConfig, fb_addr: *mut P::Raw) -> Result<Self, Error> {
+        let timing = config.timing()?;
+        Self::lcd_init(config, &timing, fb_addr);
+
+        // Clear framebuffer to black (palette index 0)
+        core::ptr::write_bytes(fb_addr as *mut u8, 0, P::buffer_size(config.width, config.height));
+
+        Ok(Self::from_parts(Mode::Lcd(*config), Some(timing), fb_addr))
+    }
+
+    /// Driver state for hardware that was just initialized.
+    fn from_parts(mode: Mode, timing: Option<LcdTiming>, fb_addr: *mut P::Raw) -> Self {
+        let (width, height) = match mode {
+            Mode::Lcd(config) => (config.width, config.height),
+            Mode::Tv(config) => (config.standard.width(), config.standard.height()),
+        };
+        Self {
+            mode,
+            timing,
+            powered: true,
+            panel_power: None,
+            backlight: None,
+            cursor_pos: (0, 0),
+            cursor_hotspot: (0, 0),
+            capture_buf: core::ptr::null_mut(),
+            width,
+            height,
+            fb: fb_addr,
+            front: fb_addr,
+        }
+    }
+
+    /// Bring up clocks, pins, DEBE and TCON0 for `config`, scanning out `fb_addr`.
+    unsafe fn lcd_init(config: &LcdConfig, timing: &LcdTiming, fb_addr: *mut P::Raw) {
+        // 1. Pixel clock source, then DEFE, DEBE, TCON clocks and bus gating
+        timing.apply_pll();
+        Self::enable_clocks();
+
+        // 2. Configure the LCD GPIO pins used by the interface as function 2 (LCD)
+        Self::init_lcd_pins(config.interface);
+
+        // 3. Clear DEBE register area 0x800..0x1000 via SRAM blocks
+        Self::clear_debe_sram();
+
+        // 4. Disable TCON first
+        Self::tcon_disable();
+
+        // 5. Configure DEBE
+        Self::debe_set_mode(config.width, config.height, fb_addr);
+
+        // 6. Configure TCON0
+        Self::tcon_set_mode(config, timing);
+
+        // 7. Enable TCON
+        Self::tcon_enable();
+
+        // 8. Enable layer 0
+        Self::layer_enable(0, true);
+
+        // 9. Enable TCON vblank (or trigger-finish) interrupt for wait_vblank/swap_buffers
+        Self::vsync_init(match config.interface {
+            LcdInterface::Rgb(_) => vsync::INT0_TCON0_VB_EN,
+            LcdInterface::Cpu(_) => i8080::INT0_TCON0_TRI_FINISH_EN,
+        });
+    }
+
+    /// Width in pixels.
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// Height in pixels.
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Panel interface the display was configured with, or `None` for TV output.
+    pub fn interface(&self) -> Option<LcdInterface> {
+        match self.mode {
+            Mode::Lcd(config) => Some(config.interface),
+            Mode::Tv(_) => None,
+        }
+    }
+
+    /// Clocking chosen for the LCD: PLL_VIDEO, divider, pixel clock and
+    /// refresh rate. `None` for TV output.
+    pub fn timing(&self) -> Option<&LcdTiming> {
+        self.timing.as_ref()
+    }
+
+    /// Get raw pointer to the buffer the drawing methods render into.
+    pub fn framebuffer(&self) -> *mut P::Raw {
+        self.fb
+    }
+
+    /// Select the buffer the drawing methods render into, without showing it.
+    ///
+    /// # Safety
+    /// `fb` must satisfy the same requirements as the framebuffer passed to [`Display::new`].
+    pub unsafe fn set_framebuffer(&mut self, fb: *mut P::Raw) {
+        self.fb = fb;
+    }
+
+    /// Size of one framebuffer in bytes.
+    pub fn framebuffer_size(&self) -> usize {
+        P::buffer_size(self.width, self.height)
+    }
+
+    /// Set a pixel at (x, y) to the given color (or palette index).
+    #[inline]
+    pub fn set_pixel(&self, x: u16, y: u16, color: P::Color) {
+        if x < self.width && y < self.height {
+            unsafe {
+                let offset = y as usize * self.width as usize + x as usize;
+                P::write(self.fb, offset, color);
+            }
+        }
+    }
+
+    /// Fill the entire screen with a color.
+    pub fn fill(&self, color: P::Color) {
+        let total = self.width as usize * self.height as usize;
+        unsafe { self.fill_span(0, total, color) };
+    }
+
+    /// Draw a horizontal line.
+    pub fn draw_hline(&self, x0: u16, x1: u16, y: u16, color: P::Color) {
+        Canvas::draw_hline(self, x0, x1, y, color);
+    }
+
+    /// Draw a vertical line.
+    pub fn draw_vline(&self, x: u16, y0: u16, y1: u16, color: P::Color) {
+        Canvas::draw_vline(self, x, y0, y1, color);
+    }
+
+    /// Draw a line using Bresenham's algorithm.
+    pub fn draw_line(&self, x0: i16, y0: i16, x1: i16, y1: i16, color: P::Color) {
+        Canvas::draw_line(self, x0, y0, x1, y1, color);
+    }
+
+    /// Draw a rectangle outline.
+    pub fn draw_rect(&self, x: u16, y: u16, w: u16, h: u16, color: P::Color) {
+        Canvas::draw_rect(self, x, y, w, h, color);
+    }
+
+    /// Fill a rectangle, clipped to the screen.
+    pub fn fill_rect(&self, x: u16, y: u16, w: u16, h: u16, color: P::Color) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let w = w.min(self.width - x) as usize;
+        let y_end = y.saturating_add(h).min(self.height);
+        for row in y..y_end {
+            let offset = row as usize * self.width as usize + x as usize;
+            unsafe { self.fill_span(offset, w, color) };
+        }
+    }
+
+    /// Fill `len` consecutive pixels starting at pixel `offset` of the draw buffer.
+    ///
+    /// # Safety
+    /// `offset + len` must not exceed `width * height`.
+    #[inline]
+    unsafe fn fill_span(&self, offset: usize, len: usize, color: P::Color) {
+        P::fill(self.fb, offset, len, color);
+    }
+
+    // --- Private hardware init helpers ---
+
+    /// Enable DEFE/DEBE/TCON module clocks, bus gating, resets and DRAM gating.
+    unsafe fn enable_clocks() {
+        let ccu = &*pac::Ccu::ptr();
+
+        ccu.fe_clk().write(|w| {
+            w.sclk_gating().set_bit();
+            w.clk_src_sel().bits(0); // PLL_VIDEO
+            w.clk_div_ratio_m().bits(0) // div 1
+        });
+        ccu.be_clk().write(|w| {
+            w.sclk_gating().set_bit();
+            w.clk_src_sel().bits(0); // PLL_VIDEO
+            w.clk_div_ratio_m().bits(0) // div 1
+        });
+        ccu.tcon_clk().write(|w| {
+            w.sclk_gating().set_bit();
+            w.clk_src_sel().bits(0) // PLL_VIDEO(1X)
+        });
+
+        // Bus clock gating
+        ccu.bus_clk_gating1().modify(|_, w| {
+            w.defe_gating().set_bit();
+            w.debe_gating().set_bit();
+            w.lcd_gating().set_bit()
+        });
+
+        // De-assert resets
+        ccu.bus_soft_rst1().modify(|_, w| {
+            w.defe_rst().set_bit();
+            w.debe_rst().set_bit();
+            w.lcd_rst().set_bit()
+        });
+
+        // DRAM gating for BE/FE
+        ccu.dram_gating().modify(|_, w| {
+            w.fe_dclk_gating().set_bit();
+            w.be_dclk_gating().set_bit()
+        });
+    }
+
+    /// Reverse of [`Self::enable_clocks`]: hold DEFE/DEBE/TCON in reset and gate all their clocks.
+    unsafe fn disable_clocks() {
+        let ccu = &*pac::Ccu::ptr();
+
+        ccu.dram_gating().modify(|_, w| {
+            w.fe_dclk_gating().clear_bit();
+            w.be_dclk_gating().clear_bit()
+        });
+        ccu.bus_soft_rst1().modify(|_, w| {
+            w.defe_rst().clear_bit();
+            w.debe_rst().clear_bit();
+            w.lcd_rst().clear_bit()
+        });
+        ccu.bus_clk_gating1().modify(|_, w| {
+            w.defe_gating().clear_bit();
+            w.debe_gating().clear_bit();
+            w.lcd_gating().clear_bit()
+        });
+        ccu.fe_clk().modify(|_, w| w.sclk_gating().clear_bit());
+        ccu.be_clk().modify(|_, w| w.sclk_gating().clear_bit());
+        ccu.tcon_clk().modify(|_, w| w.sclk_gating().clear_bit());
+    }
+
+    /// Configure the port D pins used by `interface` as LCD function
+    /// (function 2), drive level 3, no pull. Other PD pins are left untouched.
+    unsafe fn init_lcd_pins(interface: LcdInterface) {
+        let pins = lcd_pin_mask(interface);
+        for pin in 0..22u8 {
+            if pins & (1 << pin) != 0 {
+                let pd = AnyPin::steal((3 << 5) | pin);
+                pd.set_mode(PinMode::Func2);
+                pd.set_drive(DriveStrength::Level3);
+                pd.set_pull(Pull::None);
+            }
+        }
+    }
+
+    /// Return the LCD pins used by `interface` to the disabled state, so an
+    /// unpowered panel is not driven through its inputs.
+    unsafe fn release_lcd_pins(interface: LcdInterface) {
+        let pins = lcd_pin_mask(interface);
+        for pin in 0..22u8 {
+            if pins & (1 << pin) != 0 {
+                AnyPin::steal((3 << 5) | pin).set_mode(PinMode::Disabled);
+            }
+        }
+    }
+
+    /// Clear DEBE internal SRAM area (0x800..0x1000 relative to DEBE base).
+    /// The PAC maps 0x800 as debe_mode_ctrl_reg, 0x804 as debe_back_color_reg,
+    /// then various layer registers. We zero the mode_ctrl and back_color first,
+    /// then use raw pointer for the unmapped 0x808..0x810 gap, then zero
+    /// all layer config registers and SRAM blocks.
+    unsafe fn clear_debe_sram() {
+        // Zero the entire 0x800..0x1000 region via raw pointer.
+        // This is the DEBE register + SRAM init area that must be cleared
+        // before configuration. The PAC doesn't map all of it contiguously.
+        let debe_base = pac::Debe::ptr() as *mut u8;
+        let start = debe_base.add(0x800);
+        core::ptr::write_bytes(start, 0, 0x800); // 0x800 bytes = 2048 bytes
+    }
+
+    /// Disable TCON: clear control, interrupts, clock, tristate all outputs.
+    unsafe fn tcon_disable() {
+        let tcon = &*pac::Tcon::ptr();
+
+        // TCON_CTRL = 0 (module disable)
+        tcon.tcon_ctrl_reg().write(|w| w);
+
+        // TCON_INT0 = 0 (disable all interrupts)
+        tcon.tcon_int_reg0().write(|w| w);
+
+        // TCON0_DCLK: clear enable bits [31:28]
+        tcon.tcon_clk_ctrl_reg().modify(|_, w| w.lclk_en().bits(0));
+
+        // Tristate all TCON0 outputs (1 = tristate)
+        tcon.tcon0_io_ctrl_reg1().write(|w| {
+            w.d_output_tri_en().bits(0x00FF_FFFF);
+            w.io0_output_tri_en().set_bit();
+            w.io1_output_tri_en().set_bit();
+            w.io2_output_tri_en().set_bit();
+            w.io3_output_tri_en().set_bit()
+        });
+
+        // Tristate all TCON1 outputs
+        tcon.tcon1_io_ctrl_reg1().write(|w| {
+            w.d_output_tri_en().bits(0x00FF_FFFF);
+            w.io0_output_tri_en().set_bit();
+            w.io1_output_tri_en().set_bit();
+            w.io2_output_tri_en().set_bit();
+            w.io3_output_tri_en().set_bit()
+        });
+    }
+
+    /// Configure DEBE: layer 0 with the framebuffer.
+    unsafe fn debe_set_mode(w: u16, h: u16, fb_addr: *mut P::Raw) {
+        let debe = &*pac::Debe::ptr();
+
+        // Enable DEBE module
+        debe.debe_mode_ctrl_reg().modify(|_, wr| wr.en().set_bit());
+
+        // Display size register at offset 0x808 (not in PAC, use raw pointer)
+        let debe_base = pac::Debe::ptr() as *mut u32;
+        debe_base
+            .byte_add(0x808)
+            .write_volatile(((h as u32 - 1) << 16) | (w as u32 - 1));
+
+        // Layer 0: full-screen framebuffer, global alpha 255
+        Self::write_layer(0, &LayerConfig::framebuffer(fb_addr as u32, w, h, P::FORMAT));
+
+        // Register load control: trigger register update
+        Self::debe_load_regs();
+
+        // Enable output (start bit)
+        debe.debe_mode_ctrl_reg().modify(|_, wr| wr.start().set_bit());
+    }
+
+    /// Configure TCON0 for HV parallel RGB or i8080 CPU mode.
+    unsafe fn tcon_set_mode(config: &LcdConfig, timing: &LcdTiming) {
+        let tcon = &*pac::Tcon::ptr();
+        let w = config.width as u32;
+        let h = config.height as u32;
+
+        // TCON_CTRL: select TCON0 IO map (io_map_sel = 0)
+        tcon.tcon_ctrl_reg().modify(|_, wr| wr.io_map_sel().clear_bit());
+
+        // TCON0_CTRL: enable, interface (0 = HV, 1 = i8080), STA delay
+        let if_sel = match config.interface {
+            LcdInterface::Rgb(_) => 0,
+            LcdInterface::Cpu(_) => 1,
+        };
+        // STA delay is a 5-bit field; start as late as the vertical blanking allows
+        let v_blank = config.v_front_porch as u32 + config.v_back_porch as u32 + config.v_sync_len as u32;
+        let sta_dly = v_blank.min(30) as u8;
+        tcon.tcon0_ctrl_reg().write(|wr| {
+            wr.tcon0_en().set_bit();
+            wr.if_().bits(if_sel);
+            wr.tcon0_sta_dly().bits(sta_dly);
+            wr.tcon0_src_sel().bits(0) // DE CH1
+        });
+
+        // TCON0_DCLK: enable all dclk (0xF), set divider
+        tcon.tcon_clk_ctrl_reg().write(|wr| {
+            wr.lclk_en().bits(0xF);
+            wr.dclk_div().bits(timing.dclk_div)
+        });
+
+        // TCON0 active timing: width-1, height-1
+        tcon.tcon0_basic_timing_reg0().write(|wr| {
+            wr.tcon0_x().bits(w as u16 - 1);
+            wr.tcon0_y().bits(h as u16 - 1)
+        });
+
+        // TCON0 horizontal timing
+        let h_bp = config.h_sync_len as u32 + config.h_back_porch as u32;
+        let h_total = w + config.h_front_porch as u32 + h_bp;
+        tcon.tcon0_basic_timing_reg1().write(|wr| {
+            wr.ht().bits((h_total - 1) as u16);
+            wr.hbp().bits((h_bp - 1) as u16)
+        });
+
+        // TCON0 vertical timing
+        let v_bp = config.v_sync_len as u32 + config.v_back_porch as u32;
+        let v_total = h + config.v_front_porch as u32 + v_bp;
+        tcon.tcon0_basic_timing_reg2().write(|wr| {
+            wr.vt().bits((v_total * 2) as u16);
+            wr.vbp().bits((v_bp - 1) as u16)
+        });
+
+        // TCON0 sync timing
+        tcon.tcon0_basic_timing_reg3().write(|wr| {
+            wr.hspw().bits(config.h_sync_len - 1);
+            wr.vspw().bits((config.v_sync_len - 1) as u8)
+        });
+
+        // TCON0 HV interface: parallel RGB, output order RGB
+        tcon.tcon0_hv_timing_reg().write(|wr| wr);
+
+        // TCON0 CPU interface: bus mode and frame trigger, or unused (all 0)
+        match config.interface {
+            LcdInterface::Rgb(_) => tcon.tcon0_cpu_if_ctrl_reg().write(|wr| wr),
+            LcdInterface::Cpu(bus) => Self::cpu_if_init(config, bus),
+        }
+
+        // FRM (Frame Rate Modulator) for 18-bit panel dithering
+        let panel_bits = config.interface.bits_per_pixel();
+        if panel_bits == 18 || panel_bits == 16 {
+            // FRM seeds (all 0x11111111)
+            tcon.tcon_frm_seed0_r_reg()
+                .write(|wr| wr.seed_r_value0().bits(0x11111111));
+            tcon.tcon_frm_seed0_g_reg()
+                .write(|wr| wr.seed_g_value0().bits(0x11111111));
+            tcon.tcon_frm_seed0_b_reg()
+                .write(|wr| wr.seed_b_value0().bits(0x11111111));
+            tcon.tcon_frm_seed1_r_reg().write(|wr| wr.seed_r_value1().bits(0x1111));
+            tcon.tcon_frm_seed1_g_reg().write(|wr| wr.seed_g_value1().bits(0x1111));
+            tcon.tcon_frm_seed1_b_reg().write(|wr| wr.seed_b_value1().bits(0x1111));
+
+            // FRM table
+            tcon.tcon_frm_tbl_reg0()
+                .write(|wr| wr.frm_tbl_value0().bits(0x0101_0000));
+            tcon.tcon_frm_tbl_reg1()
+                .write(|wr| wr.frm_tbl_value1().bits(0x1515_1111));
+            tcon.tcon_frm_tbl_reg2()
+                .write(|wr| wr.frm_tbl_value2().bits(0x5757_5555));
+            tcon.tcon_frm_tbl_reg3()
+                .write(|wr| wr.frm_tbl_value3().bits(0x7F7F_7777));
+
+            // FRM control: enable, mode per channel (0 = 6bit, 1 = 5bit)
+            if panel_bits == 18 {
+                // 18-bit panel: 6-bit per channel (mode = 0)
+                tcon.tcon_frm_ctrl_reg().write(|wr| {
+                    wr.tcon0_frm_en().set_bit();
+                    wr.tcon0_frm_mode_r().clear_bit();
+                    wr.tcon0_frm_mode_g().clear_bit();
+                    wr.tcon0_frm_mode_b().clear_bit()
+                });
+            } else {
+                // 16-bit panel: 5-bit per channel (mode = 1)
+                tcon.tcon_frm_ctrl_reg().write(|wr| {
+                    wr.tcon0_frm_en().set_bit();
+                    wr.tcon0_frm_mode_r().set_bit();
+                    wr.tcon0_frm_mode_g().clear_bit(); // green is 6-bit in 565
+                    wr.tcon0_frm_mode_b().set_bit()
+                });
+            }
+        }
+
+        // IO polarity: DCLK phase select
+        // When dclk_invert is set, use DCLK1 (1/3 phase shift) via dclk_sel=1
+        // This matches the hardware behavior of the original raw-register driver.
+        tcon.tcon0_io_ctrl_reg0().write(|wr| {
+            if config.dclk_invert {
+                wr.dclk_sel().bits(1);
+            }
+            wr
+        });
+
+        // IO tristate: enable all outputs (write 0 = all outputs enabled)
+        // Note: reset value is 0x0FFF_FFFF (all tristate), must explicitly clear
+        tcon.tcon0_io_ctrl_reg1().write(|wr| {
+            wr.d_output_tri_en().bits(0);
+            wr.io0_output_tri_en().clear_bit();
+            wr.io1_output_tri_en().clear_bit();
+            wr.io2_output_tri_en().clear_bit();
+            wr.io3_output_tri_en().clear_bit()
+        });
+    }
+
+    /// Latch buffered DEBE register writes.
+    unsafe fn debe_load_regs() {
+        let debe = &*pac::Debe::ptr();
+        debe.debe_reg_buff_ctrl_reg().modify(|_, w| w.reg_load_ctrl().set_bit());
+    }
+
+    /// Enable TCON module.
+    unsafe fn tcon_enable() {
+        let tcon = &*pac::Tcon::ptr();
+        tcon.tcon_ctrl_reg().modify(|_, w| w.module_en().set_bit());
+    }
+
+    /// Enable or disable a DEBE layer.
+    unsafe fn layer_enable(layer: u8, enable: bool) {
+        let debe = &*pac::Debe::ptr();
+        debe.debe_mode_ctrl_reg().modify(|_, w| match layer {
+            0 => {
+                if enable {
+                    w.lay0_en().set_bit()
+                } else {
+                    w.lay0_en().clear_bit()
+                }
+            }
+            1 => {
+                if enable {
+                    w.lay1_en().set_bit()
+                } else {
+                    w.lay1_en().clear_bit()
+                }
+            }
+            2 => {
+                if enable {
+                    w.lay2_en().set_bit()
+                } else {
+                    w.lay2_en().clear_bit()
+                }
+            }
+            3 => {
+                if enable {
+                    w.lay3_en().set_bit()
+                } else {
+                    w.lay3_en().clear_bit()
+                }
+            }
+            _ => w,
+        });
+    }
+}
@@ -0,0 +1,220 @@
+//! Framebuffer pixel formats.
+//!
+//! A [`PixelFormat`] ties the DEBE layer format of the main framebuffer to
+//! the way [`Display`](super::Display) stores pixels in memory. Direct colour
+//! formats take the raw pixel value as colour; palette formats take an index
+//! into the DEBE palette (see [`Display::set_palette`](super::Display::set_palette)).
+//!
+//! Palette formats below 8 bits pack several pixels per byte, first pixel in
+//! the most significant bits. Pixels are numbered row by row without padding,
+//! matching the DEBE line width of `width * bpp` bits.
+
+use super::LayerFormat;
+
+/// Framebuffer pixel format of a [`Display`](super::Display).
+pub trait PixelFormat: 'static {
+    /// Storage unit the framebuffer pointer is typed with
+    type Raw: Copy + 'static;
+    /// Value of one pixel: a colour, or a palette index
+    type Color: Copy;
+    /// DEBE layer format
+    const FORMAT: LayerFormat;
+
+    /// Store pixel `index`, counted from `fb`.
+    ///
+    /// # Safety
+    /// The pixel must lie inside the framebuffer.
+    unsafe fn write(fb: *mut Self::Raw, index: usize, color: Self::Color);
+
+    /// Store `len` pixels starting at pixel `index`.
+    ///
+    /// # Safety
+    /// All pixels must lie inside the framebuffer.
+    unsafe fn fill(fb: *mut Self::Raw, index: usize, len: usize, color: Self::Color) {
+        for i in index..index + len {
+            Self::write(fb, i, color);
+        }
+    }
+
+    /// Framebuffer size in bytes for `width * height` pixels.
+    fn buffer_size(width: u16, height: u16) -> usize {
+        (width as usize * height as usize * Self::FORMAT.bits_per_pixel() as usize).div_ceil(8)
+    }
+}
+
+/// Direct colour format that can be built from 8-bit RGB.
+pub trait RgbFormat: PixelFormat {
+    /// Convert an opaque RGB888 colour.
+    fn rgb(r: u8, g: u8, b: u8) -> Self::Color;
+}
+
+macro_rules! direct_format {
+    ($(#[$meta:meta])* $name:ident, $raw:ty, $format:ident, $rgb:expr) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub struct $name;
+
+        impl PixelFormat for $name {
+            type Raw = $raw;
+            type Color = $raw;
+            const FORMAT: LayerFormat = LayerFormat::$format;
+
+            #[inline]
+            unsafe fn write(fb: *mut $raw, index: usize, color: $raw) {
+                fb.add(index).write_volatile(color);
+            }
+
+            /// Plain (non-volatile) stores so the compiler can emit multi-word `stm` bursts.
+            #[inline]
+            unsafe fn fill(fb: *mut $raw, index: usize, len: usize, color: $raw) {
+                core::slice::from_raw_parts_mut(fb.add(index), len).fill(color);
+            }
+        }
+
+        impl RgbFormat for $name {
+            #[inline]
+            fn rgb(r: u8, g: u8, b: u8) -> $raw {
+                $rgb(r, g, b)
+            }
+        }
+    };
+}
+
+direct_format!(
+    /// 16-bit RGB565.
+    Rgb565,
+    u16,
+    Rgb565,
+    rgb565
+);
+direct_format!(
+    /// 16-bit ARGB1555, alpha in bit 15.
+    Argb1555,
+    u16,
+    Argb1555,
+    |r, g, b| argb1555(true, r, g, b)
+);
+direct_format!(
+    /// 32-bit RGB, top byte ignored.
+    Xrgb8888,
+    u32,
+    Xrgb8888,
+    xrgb8888
+);
+direct_format!(
+    /// 32-bit ARGB with 8-bit alpha.
+    Argb8888,
+    u32,
+    Argb8888,
+    |r, g, b| argb8888(255, r, g, b)
+);
+
+macro_rules! palette_format {
+    ($(#[$meta:meta])* $name:ident, $bits:expr, $format:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        pub struct $name;
+
+        impl PixelFormat for $name {
+            type Raw = u8;
+            type Color = u8;
+            const FORMAT: LayerFormat = LayerFormat::$format;
+
+            #[inline]
+            unsafe fn write(fb: *mut u8, index: usize, color: u8) {
+                write_packed::<$bits>(fb, index, color);
+            }
+
+            unsafe fn fill(fb: *mut u8, index: usize, len: usize, color: u8) {
+                fill_packed::<$bits>(fb, index, len, color);
+            }
+        }
+    };
+}
+
+palette_format!(
+    /// 1 bit per pixel, 2-entry palette.
+    Palette1,
+    1,
+    Palette1
+);
+palette_format!(
+    /// 2 bits per pixel, 4-entry palette.
+    Palette2,
+    2,
+    Palette2
+);
+palette_format!(
+    /// 4 bits per pixel, 16-entry palette.
+    Palette4,
+    4,
+    Palette4
+);
+palette_format!(
+    /// 8 bits per pixel, 256-entry palette.
+    Palette8,
+    8,
+    Palette8
+);
+
+/// Store one `BITS`-wide pixel, first pixel in the most significant bits.
+#[inline]
+unsafe fn write_packed<const BITS: usize>(fb: *mut u8, index: usize, color: u8) {
+    let per_byte = 8 / BITS;
+    let mask = ((1u16 << BITS) - 1) as u8;
+    let byte = fb.add(index / per_byte);
+    let shift = (per_byte - 1 - index % per_byte) * BITS;
+    let val = byte.read_volatile() & !(mask << shift);
+    byte.write_volatile(val | ((color & mask) << shift));
+}
+
+/// Fill `BITS`-wide pixels: partial bytes pixel by pixel, whole bytes at once.
+unsafe fn fill_packed<const BITS: usize>(fb: *mut u8, index: usize, len: usize, color: u8) {
+    let per_byte = 8 / BITS;
+    let end = index + len;
+    let mut i = index;
+    while i < end && i % per_byte != 0 {
+        write_packed::<BITS>(fb, i, color);
+        i += 1;
+    }
+
+    let whole = (end - i) / per_byte;
+    if whole > 0 {
+        let mask = ((1u16 << BITS) - 1) as u8;
+        let mut pattern = 0u8;
+        for n in 0..per_byte {
+            pattern |= (color & mask) << (n * BITS);
+        }
+        core::slice::from_raw_parts_mut(fb.add(i / per_byte), whole).fill(pattern);
+        i += whole * per_byte;
+    }
+
+    while i < end {
+        write_packed::<BITS>(fb, i, color);
+        i += 1;
+    }
+}
+
+/// Convert RGB888 to RGB565.
+#[inline]
+pub const fn rgb565(r: u8, g: u8, b: u8) -> u16 {
+    ((r as u16 & 0xF8) << 8) | ((g as u16 & 0xFC) << 3) | ((b as u16) >> 3)
+}
+
+/// Convert RGB888 plus a 1-bit alpha to ARGB1555.
+#[inline]
+pub const fn argb1555(opaque: bool, r: u8, g: u8, b: u8) -> u16 {
+    ((opaque as u16) << 15) | ((r as u16 & 0xF8) << 7) | ((g as u16 & 0xF8) << 2) | ((b as u16) >> 3)
+}
+
+/// Pack RGB888 into XRGB8888.
+#[inline]
+pub const fn xrgb8888(r: u8, g: u8, b: u8) -> u32 {
+    ((r as u32) << 16) | ((g as u32) << 8) | b as u32
+}
+
+/// Pack 8-bit alpha and RGB into ARGB8888.
+#[inline]
+pub const fn argb8888(a: u8, r: u8, g: u8, b: u8) -> u32 {
+    ((a as u32) << 24) | xrgb8888(r, g, b)
+}
@@ -0,0 +1,182 @@
+//! Display power management and runtime mode switching.
+//!
+//! Power-on sequence:
+//! 1. Panel power enable GPIO on
+//! 2. Clocks, pins, DEBE and TCON (or TV encoder) programmed from the stored mode
+//! 3. Wait [`PanelPower::on_frames`] frames for the panel to settle
+//! 4. Backlight back to its brightness
+//!
+//! Power-off runs the same steps in reverse, ending with the DEFE, DEBE and
+//! TCON held in reset and their clocks gated in the CCU. Everything kept in
+//! DEBE/TCON registers and SRAM (layers 1..3, palette, hardware cursor, gamma
+//! table) is lost; layer 0 comes back showing the current front buffer.
+//!
+//! Settle frames are counted by vblank, or by triggered frames in i8080 mode.
+//! i8080 auto refresh is stopped by power-off and left off.
+
+use super::layer::debe_reg;
+use super::writeback::DEBE_WB_CTRL;
+use super::{Display, Error, LcdConfig, LcdInterface, Mode, PixelFormat};
+use crate::gpio::Output;
+use crate::interrupt::Interrupt;
+use crate::{intc, pac};
+
+/// Panel supply switch and its settle times.
+pub struct PanelPower {
+    /// Active-high panel power enable
+    enable: Output<'static>,
+    /// Frames between starting the signals and switching the backlight on
+    pub on_frames: u16,
+    /// Frames between switching the backlight off and stopping the signals
+    pub off_frames: u16,
+}
+
+impl PanelPower {
+    pub fn new(enable: Output<'static>, on_frames: u16, off_frames: u16) -> Self {
+        Self {
+            enable,
+            on_frames,
+            off_frames,
+        }
+    }
+}
+
+impl<P: PixelFormat> Display<P> {
+    /// Attach the panel power switch; returns the previously attached one.
+    ///
+    /// The pin is driven to match the current power state right away, so
+    /// create it high when the display is already running.
+    pub fn set_panel_power(&mut self, mut power: PanelPower) -> Option<PanelPower> {
+        power.enable.set_level(self.powered.into());
+        self.panel_power.replace(power)
+    }
+
+    /// Whether the display hardware is running.
+    pub fn is_powered(&self) -> bool {
+        self.powered
+    }
+
+    /// Run the power-off sequence. Nothing happens when already off.
+    pub async fn power_off(&mut self) {
+        if !self.powered {
+            return;
+        }
+        if let Some(backlight) = &mut self.backlight {
+            backlight.off();
+        }
+        let frames = self.panel_power.as_ref().map_or(0, |p| p.off_frames);
+        self.settle(frames).await;
+        unsafe { self.shutdown() };
+    }
+
+    /// Run the power-on sequence for the stored mode. Nothing happens when already on.
+    ///
+    /// Fails without touching the hardware if the LCD pixel clock can no
+    /// longer be reached (e.g. PLL_VIDEO was changed meanwhile).
+    pub async fn power_on(&mut self) -> Result<(), Error> {
+        if self.powered {
+            return Ok(());
+        }
+        let timing = match self.mode {
+            Mode::Lcd(config) => Some(config.timing()?),
+            Mode::Tv(_) => None,
+        };
+
+        if let Some(power) = &mut self.panel_power {
+            power.enable.set_high();
+        }
+        unsafe {
+            match (&self.mode, &timing) {
+                (Mode::Lcd(config), Some(timing)) => Self::lcd_init(config, timing, self.front),
+                (Mode::Tv(config), _) => Self::tv_init(config, self.front),
+                _ => unreachable!(),
+            }
+        }
+        self.timing = timing;
+        self.powered = true;
+
+        let frames = self.panel_power.as_ref().map_or(0, |p| p.on_frames);
+        self.settle(frames).await;
+        if let Some(backlight) = &mut self.backlight {
+            backlight.restore();
+        }
+        Ok(())
+    }
+
+    /// Switch to a new LCD resolution/timing (also from TV output).
+    ///
+    /// The new timing is checked first; on error the current mode keeps
+    /// running. Otherwise a running display goes through the power-off and
+    /// power-on sequences, a powered-off one only stores the mode.
+    ///
+    /// # Safety
+    /// The current framebuffers must hold [`PixelFormat::buffer_size`] bytes
+    /// for the new resolution.
+    pub async unsafe fn set_mode(&mut self, config: &LcdConfig) -> Result<(), Error> {
+        config.timing()?;
+
+        let powered = self.powered;
+        self.power_off().await;
+        self.mode = Mode::Lcd(*config);
+        self.width = config.width;
+        self.height = config.height;
+        self.cursor_pos = (0, 0);
+        if powered {
+            self.power_on().await?;
+        }
+        Ok(())
+    }
+
+    /// Wait `frames` frames of the running output.
+    async fn settle(&self, frames: u16) {
+        for _ in 0..frames {
+            match self.mode {
+                Mode::Lcd(LcdConfig {
+                    interface: LcdInterface::Cpu(_),
+                    ..
+                }) => self.cpu_refresh().await,
+                _ => self.wait_vblank().await,
+            }
+        }
+    }
+
+    /// Stop all display hardware immediately, backlight first and panel
+    /// power last.
+    unsafe fn shutdown(&mut self) {
+        if let Some(backlight) = &mut self.backlight {
+            backlight.off();
+        }
+        if let Mode::Lcd(config) = self.mode {
+            if let LcdInterface::Cpu(_) = config.interface {
+                self.cpu_set_auto_refresh(false);
+            }
+        }
+
+        intc::disable_irq(Interrupt::TCON.number());
+        debe_reg(DEBE_WB_CTRL).write_volatile(0);
+        self.capture_buf = core::ptr::null_mut();
+
+        Self::tcon_disable();
+        let debe = &*pac::Debe::ptr();
+        debe.debe_mode_ctrl_reg().write(|w| w);
+
+        match self.mode {
+            Mode::Lcd(config) => Self::release_lcd_pins(config.interface),
+            Mode::Tv(_) => Self::tv_shutdown(),
+        }
+        if let Some(power) = &mut self.panel_power {
+            power.enable.set_low();
+        }
+        Self::disable_clocks();
+        self.powered = false;
+    }
+}
+
+impl<P: PixelFormat> Drop for Display<P> {
+    /// Leave the display hardware off, without the settle delays.
+    fn drop(&mut self) {
+        if self.powered {
+            unsafe { self.shutdown() };
+        }
+    }
+}
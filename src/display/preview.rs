@@ -0,0 +1,160 @@
+//! Zero-copy camera preview: CSI -> DEFE -> DEBE layer.
+//!
+//! The CSI captures NV16/NV12 frames into four SDRAM buffers. Two of them
+//! sit in the CSI's hardware A/B slots, one is read by the DEFE, which
+//! scales and converts it straight into a DEBE layer, and one is spare. The
+//! CPU never touches pixel data.
+//!
+//! Rotation per camera frame ([`Preview::next_frame`]):
+//! 1. CSI frame-done: slot `s` holds a new frame, the CSI fills the other slot
+//! 2. Hand the spare buffer to slot `s` right away, so the CSI never comes
+//!    back to the new frame
+//! 3. Point the DEFE at the new frame with the register-ready latch
+//! 4. Wait for vblanks until the DEFE has latched it: the previously shown
+//!    buffer is no longer read and becomes the spare
+//!
+//! Clocks for the DEFE/DEBE are enabled by [`Display::new`].
+
+use super::defe::{Csc, Defe, InputFormat, InputFrame};
+use super::{Display, LayerConfig, PixelFormat, Rgb565};
+use crate::csi::{self, Buffer, Csi, Format, YuvLayout};
+
+/// Preview setup error.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PreviewError {
+    /// The CSI is not configured for NV16 or NV12 output
+    UnsupportedFormat,
+}
+
+/// Where and how the preview is shown.
+#[derive(Debug, Copy, Clone)]
+pub struct PreviewConfig {
+    /// DEBE layer showing the DEFE output (0..=3)
+    pub layer: u8,
+    /// Position and scaled size on screen
+    pub x: i16,
+    pub y: i16,
+    pub width: u16,
+    pub height: u16,
+    /// Priority within pipe 0 (3 = top)
+    pub priority: u8,
+    pub csc: Csc,
+}
+
+/// Live camera preview pipeline.
+pub struct Preview<'a, 'd, P: PixelFormat = Rgb565> {
+    display: &'a Display<P>,
+    csi: &'a mut Csi<'d>,
+    defe: Defe<'a>,
+    buffers: [Buffer; 4],
+    /// Buffer index in CSI slot A/B
+    slots: [usize; 2],
+    /// Buffer index currently read by the DEFE
+    shown: usize,
+    /// Buffer index neither captured into nor shown
+    spare: usize,
+    format: InputFormat,
+    width: u16,
+    height: u16,
+    layer: u8,
+}
+
+impl<'a, 'd, P: PixelFormat> Preview<'a, 'd, P> {
+    /// Start capturing into `buffers` and show the stream on a layer.
+    ///
+    /// The CSI must be configured for YUV422 with [`YuvLayout::Nv16`] or
+    /// [`YuvLayout::Nv12`]; packed YUV, RAW and JPEG cannot be read by the
+    /// DEFE and return [`PreviewError::UnsupportedFormat`].
+    ///
+    /// # Safety
+    /// Each buffer must satisfy the requirements of [`Csi::start`] and stay
+    /// valid, and untouched by the CPU, until the preview is dropped.
+    pub unsafe fn new(
+        display: &'a Display<P>,
+        csi: &'a mut Csi<'d>,
+        buffers: [Buffer; 4],
+        config: &PreviewConfig,
+    ) -> Result<Self, PreviewError> {
+        let format = match csi.config().format {
+            Format::Yuv422 {
+                layout: YuvLayout::Nv16,
+                ..
+            } => InputFormat::Nv16,
+            Format::Yuv422 {
+                layout: YuvLayout::Nv12,
+                ..
+            } => InputFormat::Nv12,
+            _ => return Err(PreviewError::UnsupportedFormat),
+        };
+        let window = csi.config().window;
+
+        // Nothing captured yet: show buffer 2, cleared to black, until the first frame
+        let black = buffers[2];
+        for (addr, value, len) in [(black.y, 0x10, csi.plane_size()), (black.c, 0x80, csi.chroma_size())] {
+            core::ptr::write_bytes(addr, value, len);
+            arm9::asm::clean_dcache_range(addr as u32, len as u32);
+        }
+        let mut defe = Defe::new(display);
+        let first = Self::frame(format, black, window.width, window.height);
+        defe.configure(&first, config.width, config.height, config.csc);
+
+        let mut layer = LayerConfig::defe(config.width, config.height);
+        layer.x = config.x;
+        layer.y = config.y;
+        layer.priority = config.priority;
+        display.configure_layer(config.layer, &layer);
+
+        csi.start([buffers[0], buffers[1]]);
+
+        Ok(Self {
+            display,
+            csi,
+            defe,
+            buffers,
+            slots: [0, 1],
+            shown: 2,
+            spare: 3,
+            format,
+            width: window.width,
+            height: window.height,
+            layer: config.layer,
+        })
+    }
+
+    /// Wait for the next camera frame and put it on screen at the next vblank.
+    ///
+    /// On CSI overflow the corrupt frame is skipped and the error returned;
+    /// the pipeline keeps running.
+    pub async fn next_frame(&mut self) -> Result<(), csi::Error> {
+        let slot = self.csi.wait_frame().await?;
+        let ready = self.slots[slot];
+
+        // The CSI is filling the other slot; this one gets the spare buffer
+        self.slots[slot] = self.spare;
+        unsafe { self.csi.set_buffer(slot, self.buffers[self.spare]) };
+
+        let frame = Self::frame(self.format, self.buffers[ready], self.width, self.height);
+        self.defe.set_input(&frame);
+        while self.defe.input_pending() {
+            self.display.wait_vblank().await;
+        }
+
+        // The previously shown buffer is no longer read
+        self.spare = core::mem::replace(&mut self.shown, ready);
+        Ok(())
+    }
+
+    /// DEFE input descriptor for one capture buffer.
+    fn frame(format: InputFormat, buf: Buffer, width: u16, height: u16) -> InputFrame {
+        InputFrame::semi_planar(format, buf.y as u32, buf.c as u32, width, height)
+    }
+}
+
+impl<'a, 'd, P: PixelFormat> Drop for Preview<'a, 'd, P> {
+    fn drop(&mut self) {
+        self.csi.stop();
+        self.display.set_layer_enabled(self.layer, false);
+        self.defe.stop();
+    }
+}
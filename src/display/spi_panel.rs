@@ -0,0 +1,484 @@
+//! SPI TFT panels (ST7789, ILI9341, GC9A01) driven from an SDRAM framebuffer.
+//!
+//! Drawing goes into an RGB565 framebuffer through the same [`Canvas`] API as
+//! [`Display`](super::Display). Changed pixels are tracked as one dirty
+//! rectangle, and [`SpiPanel::flush`] sends only that window to the panel:
+//! CASET/RASET select it, then RAMWR streams the pixels with
+//! [`Spi::dma_write`] while CS stays low.
+//!
+//! Wiring: SCK/MOSI/CS on the SPI pins (MISO is unused), D/C and the optional
+//! reset on any GPIO. Create the bus with [`Spi::new`] in SPI mode 0 (mode 3
+//! for some ST7789 modules without CS); the panel drives CS by hand.
+//!
+//! The framebuffer holds pixels byte-swapped (big-endian RGB565, as sent on
+//! the wire) so whole rows can be handed to the DMA. [`Canvas`] methods take
+//! and return normal `rgb565` values.
+//!
+//! Rotation uses the controller's MADCTL register, so it costs nothing at
+//! draw time. Panel offsets (e.g. a 240x240 ST7789 glass on the 240x320 RAM)
+//! are given for [`Rotation::Deg0`] and mirrored for the other rotations.
+
+use core::cell::Cell;
+
+use embedded_hal::delay::DelayNs;
+
+use super::Canvas;
+use crate::dma::{AnyNdmaChannel, NdmaChannel};
+use crate::gpio::Output;
+use crate::spi::{self, Spi};
+use crate::Peri;
+
+const SWRESET: u8 = 0x01;
+const SLPOUT: u8 = 0x11;
+const NORON: u8 = 0x13;
+const INVOFF: u8 = 0x20;
+const INVON: u8 = 0x21;
+const DISPON: u8 = 0x29;
+const CASET: u8 = 0x2A;
+const RASET: u8 = 0x2B;
+const RAMWR: u8 = 0x2C;
+const MADCTL: u8 = 0x36;
+const COLMOD: u8 = 0x3A;
+
+const MADCTL_MY: u8 = 0x80;
+const MADCTL_MX: u8 = 0x40;
+const MADCTL_MV: u8 = 0x20;
+const MADCTL_BGR: u8 = 0x08;
+
+/// 16 bits per pixel (RGB565) on the SPI interface
+const COLMOD_RGB565: u8 = 0x55;
+
+/// Largest DMA chunk; NDMA moves at most 128KB - 1 per transfer
+const DMA_CHUNK: usize = 0x1_0000;
+
+/// GC9A01 vendor init (inter-register enable, power, gamma), before the common tail
+#[rustfmt::skip]
+const GC9A01_INIT: &[(u8, &[u8])] = &[
+    (0xEF, &[]),
+    (0xEB, &[0x14]),
+    (0xFE, &[]),
+    (0xEF, &[]),
+    (0xEB, &[0x14]),
+    (0x84, &[0x40]),
+    (0x85, &[0xFF]),
+    (0x86, &[0xFF]),
+    (0x87, &[0xFF]),
+    (0x88, &[0x0A]),
+    (0x89, &[0x21]),
+    (0x8A, &[0x00]),
+    (0x8B, &[0x80]),
+    (0x8C, &[0x01]),
+    (0x8D, &[0x01]),
+    (0x8E, &[0xFF]),
+    (0x8F, &[0xFF]),
+    (0xB6, &[0x00, 0x20]),
+    (0x90, &[0x08, 0x08, 0x08, 0x08]),
+    (0xBD, &[0x06]),
+    (0xBC, &[0x00]),
+    (0xFF, &[0x60, 0x01, 0x04]),
+    (0xC3, &[0x13]),
+    (0xC4, &[0x13]),
+    (0xC9, &[0x22]),
+    (0xBE, &[0x11]),
+    (0xE1, &[0x10, 0x0E]),
+    (0xDF, &[0x21, 0x0C, 0x02]),
+    (0xF0, &[0x45, 0x09, 0x08, 0x08, 0x26, 0x2A]),
+    (0xF1, &[0x43, 0x70, 0x72, 0x36, 0x37, 0x6F]),
+    (0xF2, &[0x45, 0x09, 0x08, 0x08, 0x26, 0x2A]),
+    (0xF3, &[0x43, 0x70, 0x72, 0x36, 0x37, 0x6F]),
+    (0xED, &[0x1B, 0x0B]),
+    (0xAE, &[0x77]),
+    (0xCD, &[0x63]),
+    (0x70, &[0x07, 0x07, 0x04, 0x0E, 0x0F, 0x09, 0x07, 0x08, 0x03]),
+    (0xE8, &[0x34]),
+    (0x62, &[0x18, 0x0D, 0x71, 0xED, 0x70, 0x70, 0x18, 0x0F, 0x71, 0xEF, 0x70, 0x70]),
+    (0x63, &[0x18, 0x11, 0x71, 0xF1, 0x70, 0x70, 0x18, 0x13, 0x71, 0xF3, 0x70, 0x70]),
+    (0x64, &[0x28, 0x29, 0xF1, 0x01, 0xF1, 0x00, 0x07]),
+    (0x66, &[0x3C, 0x00, 0xCD, 0x67, 0x45, 0x45, 0x10, 0x00, 0x00, 0x00]),
+    (0x67, &[0x00, 0x3C, 0x00, 0x00, 0x00, 0x01, 0x54, 0x10, 0x32, 0x98]),
+    (0x74, &[0x10, 0x85, 0x80, 0x00, 0x00, 0x4E, 0x00]),
+    (0x98, &[0x3E, 0x07]),
+    (0x35, &[]),
+];
+
+/// Panel controller.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Controller {
+    /// 240x320 RAM, RGB order
+    St7789,
+    /// 240x320 RAM, BGR order
+    Ili9341,
+    /// 240x240 RAM (round panels), BGR order
+    Gc9a01,
+}
+
+impl Controller {
+    /// Frame memory size (columns, rows) in the native orientation.
+    pub const fn ram_size(self) -> (u16, u16) {
+        match self {
+            Controller::St7789 | Controller::Ili9341 => (240, 320),
+            Controller::Gc9a01 => (240, 240),
+        }
+    }
+
+    fn madctl(self, rotation: Rotation) -> u8 {
+        let base = match rotation {
+            Rotation::Deg0 => 0,
+            Rotation::Deg90 => MADCTL_MX | MADCTL_MV,
+            Rotation::Deg180 => MADCTL_MX | MADCTL_MY,
+            Rotation::Deg270 => MADCTL_MY | MADCTL_MV,
+        };
+        match self {
+            Controller::St7789 => base,
+            // Column order of these is mirrored relative to the ST7789
+            Controller::Ili9341 | Controller::Gc9a01 => (base ^ MADCTL_MX) | MADCTL_BGR,
+        }
+    }
+}
+
+/// Clockwise panel rotation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Rotation {
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+impl Rotation {
+    /// Whether width and height are swapped relative to [`Rotation::Deg0`].
+    pub const fn is_landscape(self) -> bool {
+        matches!(self, Rotation::Deg90 | Rotation::Deg270)
+    }
+}
+
+/// SPI panel configuration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub controller: Controller,
+    /// Visible size in the native orientation ([`Rotation::Deg0`])
+    pub width: u16,
+    pub height: u16,
+    /// Position of the visible area in controller RAM at [`Rotation::Deg0`]
+    pub x_offset: u16,
+    pub y_offset: u16,
+    pub rotation: Rotation,
+    /// Send INVON (most IPS panels need it)
+    pub invert_colors: bool,
+}
+
+impl Config {
+    /// 240x320 ST7789 IPS module.
+    pub const fn st7789_240x320() -> Self {
+        Self {
+            controller: Controller::St7789,
+            width: 240,
+            height: 320,
+            x_offset: 0,
+            y_offset: 0,
+            rotation: Rotation::Deg0,
+            invert_colors: true,
+        }
+    }
+
+    /// 240x240 ST7789 IPS module (glass at the bottom of the 240x320 RAM).
+    pub const fn st7789_240x240() -> Self {
+        Self {
+            height: 240,
+            y_offset: 80,
+            ..Self::st7789_240x320()
+        }
+    }
+
+    /// 240x320 ILI9341 TN module.
+    pub const fn ili9341_240x320() -> Self {
+        Self {
+            controller: Controller::Ili9341,
+            width: 240,
+            height: 320,
+            x_offset: 0,
+            y_offset: 0,
+            rotation: Rotation::Deg0,
+            invert_colors: false,
+        }
+    }
+
+    /// 240x240 round GC9A01 module.
+    pub const fn gc9a01_240x240() -> Self {
+        Self {
+            controller: Controller::Gc9a01,
+            width: 240,
+            height: 240,
+            x_offset: 0,
+            y_offset: 0,
+            rotation: Rotation::Deg0,
+            invert_colors: true,
+        }
+    }
+
+    /// Visible size (width, height) after rotation.
+    pub const fn size(&self) -> (u16, u16) {
+        if self.rotation.is_landscape() {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        }
+    }
+
+    /// RAM offset of the visible area (x, y) after rotation.
+    fn offset(&self) -> (u16, u16) {
+        let (ram_w, ram_h) = self.controller.ram_size();
+        let far_x = ram_w.saturating_sub(self.width + self.x_offset);
+        let far_y = ram_h.saturating_sub(self.height + self.y_offset);
+        match self.rotation {
+            Rotation::Deg0 => (self.x_offset, self.y_offset),
+            Rotation::Deg90 => (self.y_offset, far_x),
+            Rotation::Deg180 => (far_x, far_y),
+            Rotation::Deg270 => (far_y, self.x_offset),
+        }
+    }
+}
+
+/// Dirty window, end-exclusive
+#[derive(Copy, Clone)]
+struct Rect {
+    x0: u16,
+    y0: u16,
+    x1: u16,
+    y1: u16,
+}
+
+/// SPI TFT panel with a local framebuffer.
+pub struct SpiPanel<'d, T: spi::Instance> {
+    spi: Spi<'d, T>,
+    dc: Output<'d>,
+    reset: Option<Output<'d>>,
+    /// NDMA channel used for pixel data
+    dma: Peri<'d, AnyNdmaChannel>,
+    config: Config,
+    width: u16,
+    height: u16,
+    /// Big-endian RGB565 pixels, `width * height`
+    fb: *mut u16,
+    dirty: Cell<Option<Rect>>,
+}
+
+impl<'d, T: spi::Instance> SpiPanel<'d, T> {
+    /// Wrap an SPI bus and D/C pin. Call [`SpiPanel::init`] before drawing.
+    ///
+    /// `fb` must hold `width * height` pixels of the rotated size
+    /// ([`Config::size`]); with SDRAM, 4-byte alignment lets the DMA use
+    /// 32-bit bursts.
+    ///
+    /// # Safety
+    /// `fb` must be valid, writable and not used elsewhere for the panel's lifetime.
+    pub unsafe fn new(
+        spi: Spi<'d, T>,
+        dc: Output<'d>,
+        reset: Option<Output<'d>>,
+        dma: Peri<'d, impl NdmaChannel>,
+        config: &Config,
+        fb: *mut u16,
+    ) -> Self {
+        let (width, height) = config.size();
+        Self {
+            spi,
+            dc,
+            reset,
+            dma: dma.into(),
+            config: *config,
+            width,
+            height,
+            fb,
+            dirty: Cell::new(None),
+        }
+    }
+
+    /// Reset and initialize the controller, then clear the screen to black.
+    pub async fn init(&mut self, delay: &mut impl DelayNs) -> Result<(), spi::Error> {
+        if let Some(reset) = &mut self.reset {
+            reset.set_low();
+            delay.delay_ms(10);
+            reset.set_high();
+            delay.delay_ms(120);
+        } else {
+            self.command(SWRESET, &[])?;
+            delay.delay_ms(150);
+        }
+
+        if self.config.controller == Controller::Gc9a01 {
+            for &(cmd, params) in GC9A01_INIT {
+                self.command(cmd, params)?;
+            }
+        }
+        self.command(SLPOUT, &[])?;
+        delay.delay_ms(120);
+        self.command(COLMOD, &[COLMOD_RGB565])?;
+        self.command(MADCTL, &[self.config.controller.madctl(self.config.rotation)])?;
+        self.command(if self.config.invert_colors { INVON } else { INVOFF }, &[])?;
+        self.command(NORON, &[])?;
+
+        Canvas::fill(self, 0);
+        self.flush().await?;
+        self.command(DISPON, &[])?;
+        delay.delay_ms(20);
+        Ok(())
+    }
+
+    /// Change the rotation. The framebuffer is reinterpreted with the new
+    /// width and height and fully resent on the next flush.
+    pub fn set_rotation(&mut self, rotation: Rotation) -> Result<(), spi::Error> {
+        self.config.rotation = rotation;
+        (self.width, self.height) = self.config.size();
+        self.command(MADCTL, &[self.config.controller.madctl(rotation)])?;
+        self.invalidate();
+        Ok(())
+    }
+
+    pub fn rotation(&self) -> Rotation {
+        self.config.rotation
+    }
+
+    /// Get raw pointer to the framebuffer (big-endian RGB565).
+    pub fn framebuffer(&self) -> *mut u16 {
+        self.fb
+    }
+
+    /// Mark the whole screen for the next flush, e.g. after writing the
+    /// framebuffer directly.
+    pub fn invalidate(&self) {
+        self.dirty.set(Some(Rect {
+            x0: 0,
+            y0: 0,
+            x1: self.width,
+            y1: self.height,
+        }));
+    }
+
+    /// Send the area changed since the last flush. Does nothing if clean.
+    pub async fn flush(&mut self) -> Result<(), spi::Error> {
+        match self.dirty.take() {
+            Some(r) => self.write_window(r).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Send a region of the framebuffer, whether changed or not.
+    pub async fn flush_region(&mut self, x: u16, y: u16, w: u16, h: u16) -> Result<(), spi::Error> {
+        if x >= self.width || y >= self.height || w == 0 || h == 0 {
+            return Ok(());
+        }
+        let r = Rect {
+            x0: x,
+            y0: y,
+            x1: x.saturating_add(w).min(self.width),
+            y1: y.saturating_add(h).min(self.height),
+        };
+        self.write_window(r).await
+    }
+
+    /// Send a command byte with D/C low, then its parameters with D/C high.
+    pub fn command(&mut self, cmd: u8, params: &[u8]) -> Result<(), spi::Error> {
+        self.spi.cs_low();
+        self.dc.set_low();
+        let mut result = self.spi.blocking_write(&[cmd]);
+        if result.is_ok() && !params.is_empty() {
+            self.dc.set_high();
+            result = self.spi.blocking_write(params);
+        }
+        self.spi.cs_high();
+        result
+    }
+
+    async fn write_window(&mut self, r: Rect) -> Result<(), spi::Error> {
+        let (ox, oy) = self.config.offset();
+        let (x0, x1) = (r.x0 + ox, r.x1 - 1 + ox);
+        let (y0, y1) = (r.y0 + oy, r.y1 - 1 + oy);
+        self.command(CASET, &[(x0 >> 8) as u8, x0 as u8, (x1 >> 8) as u8, x1 as u8])?;
+        self.command(RASET, &[(y0 >> 8) as u8, y0 as u8, (y1 >> 8) as u8, y1 as u8])?;
+
+        self.spi.cs_low();
+        self.dc.set_low();
+        let mut result = self.spi.blocking_write(&[RAMWR]);
+        self.dc.set_high();
+        if result.is_ok() {
+            result = self.write_pixels(r).await;
+        }
+        self.spi.cs_high();
+        result
+    }
+
+    /// Stream the window rows; full-width windows are contiguous in memory.
+    async fn write_pixels(&mut self, r: Rect) -> Result<(), spi::Error> {
+        let stride = self.width as usize;
+        let row_len = (r.x1 - r.x0) as usize;
+        let rows = (r.y1 - r.y0) as usize;
+        let base = unsafe { self.fb.add(r.y0 as usize * stride + r.x0 as usize) } as *const u8;
+
+        if row_len == stride {
+            let data = unsafe { core::slice::from_raw_parts(base, rows * stride * 2) };
+            for chunk in data.chunks(DMA_CHUNK) {
+                self.spi.dma_write(self.dma.reborrow(), chunk).await?;
+            }
+        } else {
+            for row in 0..rows {
+                let data = unsafe { core::slice::from_raw_parts(base.add(row * stride * 2), row_len * 2) };
+                self.spi.dma_write(self.dma.reborrow(), data).await?;
+            }
+        }
+        Ok(())
+    }
+
+    fn mark_dirty(&self, x0: u16, y0: u16, x1: u16, y1: u16) {
+        let r = match self.dirty.get() {
+            Some(d) => Rect {
+                x0: d.x0.min(x0),
+                y0: d.y0.min(y0),
+                x1: d.x1.max(x1),
+                y1: d.y1.max(y1),
+            },
+            None => Rect { x0, y0, x1, y1 },
+        };
+        self.dirty.set(Some(r));
+    }
+}
+
+impl<'d, T: spi::Instance> Canvas for SpiPanel<'d, T> {
+    /// RGB565
+    type Color = u16;
+
+    fn width(&self) -> u16 {
+        self.width
+    }
+
+    fn height(&self) -> u16 {
+        self.height
+    }
+
+    #[inline]
+    fn set_pixel(&self, x: u16, y: u16, color: u16) {
+        if x < self.width && y < self.height {
+            unsafe {
+                let offset = y as usize * self.width as usize + x as usize;
+                self.fb.add(offset).write_volatile(color.swap_bytes());
+            }
+            self.mark_dirty(x, y, x + 1, y + 1);
+        }
+    }
+
+    fn fill_rect(&self, x: u16, y: u16, w: u16, h: u16, color: u16) {
+        if x >= self.width || y >= self.height || w == 0 || h == 0 {
+            return;
+        }
+        let x_end = x.saturating_add(w).min(self.width);
+        let y_end = y.saturating_add(h).min(self.height);
+        for row in y..y_end {
+            let offset = row as usize * self.width as usize + x as usize;
+            unsafe { core::slice::from_raw_parts_mut(self.fb.add(offset), (x_end - x) as usize) }
+                .fill(color.swap_bytes());
+        }
+        self.mark_dirty(x, y, x_end, y_end);
+    }
+}
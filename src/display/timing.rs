@@ -0,0 +1,158 @@
+//! LCD timing validation and pixel clock planning.
+//!
+//! DCLK is PLL_VIDEO divided by the TCON0 divider (6..=127). For a given
+//! [`LcdConfig`] every PLL_VIDEO setting (integer N/M and both fractional
+//! outputs) is tried with its best divider, and the closest DCLK wins. On a
+//! tie the currently running PLL_VIDEO is kept, so other users of the PLL
+//! (DEFE/DEBE, TVD, TVE) are left alone whenever possible.
+//!
+//! The DEFE and DEBE are clocked from PLL_VIDEO / 1, which caps the search
+//! at 300MHz.
+
+use super::LcdConfig;
+use crate::rcc::{self, PllVideo, PllVideoMode};
+
+/// TCON0 DCLK divider range
+const DCLK_DIV_MIN: u32 = 6;
+const DCLK_DIV_MAX: u32 = 127;
+
+/// PLL_VIDEO integer-mode output range, capped by the DE clock limit
+const PLL_VIDEO_MIN_HZ: u32 = 30_000_000;
+const PLL_VIDEO_MAX_HZ: u32 = 300_000_000;
+
+/// Largest accepted pixel clock deviation, in percent
+const MAX_CLOCK_ERROR_PERCENT: u32 = 5;
+
+// TCON0 timing register limits
+const MAX_ACTIVE: u32 = 2048;
+/// HT and HBP are 12-bit fields holding `value - 1`
+const MAX_H_TOTAL: u32 = 4096;
+/// VT holds twice the line count in 12 bits
+const MAX_V_TOTAL: u32 = 2047;
+const MAX_H_SYNC: u32 = 1024;
+const MAX_V_SYNC: u32 = 256;
+
+/// LCD configuration error.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// Width or height is 0 or larger than 2048
+    InvalidSize,
+    /// Horizontal sync is zero or the line is too long for TCON0
+    InvalidHorizontalTiming,
+    /// Vertical sync is zero or the frame is too tall for TCON0
+    InvalidVerticalTiming,
+    /// No PLL_VIDEO / divider pair gets within 5% of the pixel clock
+    PixelClockUnreachable,
+}
+
+/// Clock setup chosen for an [`LcdConfig`] and the resulting rates.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LcdTiming {
+    pub pll_video: PllVideo,
+    /// TCON0 DCLK divider
+    pub dclk_div: u8,
+    /// Achieved pixel clock
+    pub pixel_clock_hz: u32,
+    /// Achieved refresh rate in millihertz
+    pub refresh_millihz: u32,
+}
+
+impl LcdConfig {
+    /// Validate the timing and plan the pixel clock against the PLL_VIDEO
+    /// setting currently in the CCU.
+    pub fn timing(&self) -> Result<LcdTiming, Error> {
+        self.timing_with(rcc::pll_video())
+    }
+
+    /// Like [`LcdConfig::timing`], preferring `current` for PLL_VIDEO.
+    pub fn timing_with(&self, current: PllVideo) -> Result<LcdTiming, Error> {
+        let (h_total, v_total) = self.validate()?;
+
+        let target = self.pixel_clock_hz;
+        if target == 0 {
+            return Err(Error::PixelClockUnreachable);
+        }
+
+        let mut best: Option<(u32, PllVideo, u32)> = None;
+        let mut consider = |pll: PllVideo| {
+            let hz = pll.freq_hz();
+            if !(PLL_VIDEO_MIN_HZ..=PLL_VIDEO_MAX_HZ).contains(&hz) {
+                return;
+            }
+            let div = ((hz + target / 2) / target).clamp(DCLK_DIV_MIN, DCLK_DIV_MAX);
+            let err = (hz / div).abs_diff(target);
+            // Earlier candidates win ties; `current` is tried first
+            let better = match best {
+                None => true,
+                Some((best_err, best_pll, _)) => {
+                    err < best_err || (err == best_err && best_pll != current && hz > best_pll.freq_hz())
+                }
+            };
+            if better {
+                best = Some((err, pll, div));
+            }
+        };
+
+        consider(current);
+        for out_297mhz in [true, false] {
+            consider(PllVideo {
+                mode: PllVideoMode::Fractional { out_297mhz },
+            });
+        }
+        for m in 1..=16u8 {
+            for n in 1..=128u8 {
+                consider(PllVideo {
+                    mode: PllVideoMode::Integer { n, m },
+                });
+            }
+        }
+
+        let (err, pll_video, div) = best.ok_or(Error::PixelClockUnreachable)?;
+        if err as u64 * 100 > target as u64 * MAX_CLOCK_ERROR_PERCENT as u64 {
+            return Err(Error::PixelClockUnreachable);
+        }
+
+        let pixel_clock_hz = pll_video.freq_hz() / div;
+        let frame = h_total as u64 * v_total as u64;
+        Ok(LcdTiming {
+            pll_video,
+            dclk_div: div as u8,
+            pixel_clock_hz,
+            refresh_millihz: (pixel_clock_hz as u64 * 1000 / frame) as u32,
+        })
+    }
+
+    /// Check sizes against the TCON0 register fields. Returns the total
+    /// line length (pixels) and frame height (lines).
+    fn validate(&self) -> Result<(u32, u32), Error> {
+        let w = self.width as u32;
+        let h = self.height as u32;
+        if w == 0 || h == 0 || w > MAX_ACTIVE || h > MAX_ACTIVE {
+            return Err(Error::InvalidSize);
+        }
+
+        let h_sync = self.h_sync_len as u32;
+        let h_blank = self.h_front_porch as u32 + self.h_back_porch as u32 + h_sync;
+        let h_total = w + h_blank;
+        if h_sync == 0 || h_sync > MAX_H_SYNC || h_total > MAX_H_TOTAL {
+            return Err(Error::InvalidHorizontalTiming);
+        }
+
+        let v_sync = self.v_sync_len as u32;
+        let v_total = h + self.v_front_porch as u32 + self.v_back_porch as u32 + v_sync;
+        if v_sync == 0 || v_sync > MAX_V_SYNC || v_total > MAX_V_TOTAL {
+            return Err(Error::InvalidVerticalTiming);
+        }
+        Ok((h_total, v_total))
+    }
+}
+
+impl LcdTiming {
+    /// Apply the PLL_VIDEO setting unless it is already running.
+    pub(super) unsafe fn apply_pll(&self) {
+        if !rcc::pll_video_enabled() || rcc::pll_video() != self.pll_video {
+            rcc::set_pll_video(&self.pll_video);
+        }
+    }
+}
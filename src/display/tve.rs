@@ -0,0 +1,513 @@
+//! TV encoder (TVE): composite (CVBS) output for NTSC-M and PAL-B/D/G.
+//!
+//! Scan-out path: DEBE -> TCON1 (interlaced timing) -> TVE -> DAC0. The
+//! DEBE output color correction converts to YUV for the encoder.
+//! [`Display::new_tv`] sets this up with the same layer 0 framebuffer as the
+//! LCD path, so the drawing methods, layers, DEFE and `swap_buffers` work
+//! unchanged; vblank comes from TCON1.
+//!
+//! The TVE runs from TVE_SCLK2 = PLL_VIDEO / 11, which must be 27MHz, so
+//! bringing up TV output programs PLL_VIDEO for fractional 297MHz mode
+//! through [`rcc`](crate::rcc).
+//!
+//! DAC calibration is not supported: the output level is a manual trim
+//! ([`TvConfig::dac_trim`]) set per board.
+//!
+//! Cable detection uses the DAC load sense: the TVE interrupt fires when the
+//! detected state of DAC0 changes (see [`Display::tv_cable_state`]).
+//!
+//! The TVE, the TCON1 timing registers and the TVE clock are accessed by
+//! raw offset.
+
+use core::future::poll_fn;
+use core::task::Poll;
+
+use embassy_sync::waitqueue::AtomicWaker;
+
+use super::layer::debe_reg;
+use super::{vsync, Display, Mode, PixelFormat, Rgb565};
+use crate::interrupt::typelevel::Handler;
+use crate::interrupt::Interrupt;
+use crate::rcc::{self, ccu_clear, ccu_set, ccu_write, PllVideo, CCU_BUS_CLK_GATING1, CCU_BUS_SOFT_RST1, CCU_TVE_CLK};
+use crate::{intc, pac};
+
+// ============================================================================
+// TVE base and register offsets
+// ============================================================================
+const TVE_BASE: usize = 0x01C0_A000;
+
+const TVE_EN: usize = 0x000;
+const TVE_CFG0: usize = 0x004;
+const TVE_DAC0: usize = 0x008;
+const TVE_NOTCH: usize = 0x00C;
+const TVE_CHROMA_FREQ: usize = 0x010;
+const TVE_PORCH: usize = 0x014;
+const TVE_LINE: usize = 0x01C;
+const TVE_LEVEL: usize = 0x020;
+const TVE_DAC1: usize = 0x024;
+const TVE_DETECT_EN: usize = 0x030;
+const TVE_DETECT_INT_STA: usize = 0x034;
+const TVE_DETECT_STA: usize = 0x038;
+const TVE_DETECT_DEBOUNCE: usize = 0x03C;
+const TVE_CB_CR_LVL: usize = 0x10C;
+const TVE_BURST_WIDTH: usize = 0x114;
+const TVE_CB_CR_GAIN: usize = 0x118;
+const TVE_SYNC_VBI: usize = 0x11C;
+const TVE_ACTIVE_LINE: usize = 0x124;
+const TVE_CHROMA: usize = 0x128;
+const TVE_12C: usize = 0x12C;
+const TVE_RESYNC: usize = 0x130;
+const TVE_SLAVE: usize = 0x134;
+
+// EN register
+const EN_ENABLE: u32 = 1 << 0;
+/// DAC0 outputs encoder channel 1 (composite)
+const EN_DAC0_MAP_COMPOSITE: u32 = 1 << 4;
+
+// CFG0 register
+const CFG0_RES_480I: u32 = 0;
+const CFG0_RES_576I: u32 = 1;
+const CFG0_COMP_EN: u32 = 1 << 16;
+const CFG0_YC_EN: u32 = 1 << 17;
+const CFG0_DAC_CONTROL_54M: u32 = 1 << 26;
+const CFG0_CORE_DATAPATH_54M: u32 = 1 << 27;
+const CFG0_CORE_CONTROL_54M: u32 = 1 << 28;
+
+// DAC0 register
+const DAC0_DAC0_EN: u32 = 1 << 0;
+const DAC0_INTERNAL_DAC_37_5_OHMS: u32 = 3 << 16;
+const DAC0_CHROMA_0_75: u32 = 3 << 18;
+const DAC0_LUMA_0_4: u32 = 3 << 20;
+const DAC0_CLOCK_INVERT: u32 = 1 << 24;
+
+// DETECT registers (DAC0 only)
+const DETECT_EN_DAC0: u32 = 1 << 0;
+const DETECT_INT_EN_DAC0: u32 = 1 << 16;
+const DETECT_INT_STA_DAC0: u32 = 1 << 0;
+const DETECT_STA_DAC0_MASK: u32 = 0x3;
+
+/// Composite chroma gain 50%
+const CHROMA_COMP_GAIN_50: u32 = 2;
+const C12_COMP_YUV_EN: u32 = 1 << 0;
+const C12_NOTCH_WIDTH_WIDE: u32 = 1 << 8;
+const RESYNC_FIELD: u32 = 1 << 31;
+
+// ============================================================================
+// TCON1 and clock registers
+// ============================================================================
+const TCON1_CTRL: usize = 0x090;
+const TCON1_BASIC0: usize = 0x094;
+const TCON1_BASIC1: usize = 0x098;
+const TCON1_BASIC2: usize = 0x09C;
+const TCON1_BASIC3: usize = 0x0A0;
+const TCON1_BASIC4: usize = 0x0A4;
+const TCON1_BASIC5: usize = 0x0A8;
+const TCON1_IO_POL: usize = 0x0F0;
+
+const TCON1_CTRL_EN: u32 = 1 << 31;
+const TCON1_CTRL_INTERLACE_EN: u32 = 1 << 20;
+const TCON1_CTRL_STA_DLY_SHIFT: u32 = 4;
+
+/// DEBE_MODE_CTRL interlace mode enable
+const DEBE_MODCTL_ITLMOD_EN: u32 = 1 << 28;
+
+/// DEBE output color correction, converts the blended RGB for the TVE
+const DEBE_OCCTL: usize = 0x9C0;
+const DEBE_OCCTL_EN: u32 = 1 << 0;
+const DEBE_OCRCOEF: usize = 0x9D0;
+
+/// BT.601 RGB to YCbCr (limited range) output coefficients, as programmed
+/// by the Linux sun4i backend for the TV encoder
+const DEBE_RGB2YUV_COEF: [u32; 12] = [
+    0x0000_0107,
+    0x0000_0204,
+    0x0000_0064,
+    0x0000_0108,
+    0x0000_3F69,
+    0x0000_3ED6,
+    0x0000_01C1,
+    0x0000_0808,
+    0x0000_01C1,
+    0x0000_3E88,
+    0x0000_3FB8,
+    0x0000_0808,
+];
+
+const BUS1_TVE: u32 = 1 << 10;
+
+const TVE_CLK_SCLK2_GATING: u32 = 1 << 31;
+const TVE_CLK_SCLK1_GATING: u32 = 1 << 15;
+/// PLL_VIDEO(1X) / 11 = 27MHz
+const TVE_CLK_DIV_27M: u32 = 10;
+
+static CABLE_WAKER: AtomicWaker = AtomicWaker::new();
+
+#[inline]
+fn reg(offset: usize) -> *mut u32 {
+    (TVE_BASE + offset) as *mut u32
+}
+
+#[inline]
+unsafe fn write(offset: usize, val: u32) {
+    reg(offset).write_volatile(val);
+}
+
+#[inline]
+unsafe fn read(offset: usize) -> u32 {
+    reg(offset).read_volatile()
+}
+
+#[inline]
+unsafe fn tcon_write(offset: usize, val: u32) {
+    ((pac::Tcon::ptr() as usize + offset) as *mut u32).write_volatile(val);
+}
+
+/// Analog TV standard.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TvStandard {
+    /// NTSC-M, 720x480 interlaced, 525 lines, 59.94 fields/s
+    Ntsc,
+    /// PAL-B/D/G, 720x576 interlaced, 625 lines, 50 fields/s
+    Pal,
+}
+
+/// Per-standard encoder and timing parameters.
+struct TvTiming {
+    height: u16,
+    /// Total lines per frame
+    lines: u16,
+    chroma_freq: u32,
+    /// TVE porches in 27MHz samples
+    back_porch: u32,
+    front_porch: u32,
+    /// TCON1 per-field vertical back porch (including sync)
+    vbp: u16,
+    black_level: u32,
+    blank_level: u32,
+    vblank_level: u32,
+    burst_cb: u32,
+    burst_cr: u32,
+    gain_cb: u32,
+    gain_cr: u32,
+    resync_field: bool,
+    resync_line: u32,
+    resync_pixel: u32,
+    yc_en: bool,
+}
+
+impl TvStandard {
+    /// Active width in pixels.
+    pub const fn width(self) -> u16 {
+        720
+    }
+
+    /// Active height in pixels (both fields).
+    pub const fn height(self) -> u16 {
+        self.timing().height
+    }
+
+    const fn timing(self) -> TvTiming {
+        match self {
+            TvStandard::Ntsc => TvTiming {
+                height: 480,
+                lines: 525,
+                chroma_freq: 0x21F0_7C1F,
+                back_porch: 118,
+                front_porch: 32,
+                vbp: 18,
+                black_level: 282,
+                blank_level: 240,
+                vblank_level: 240,
+                burst_cb: 68,
+                burst_cr: 0,
+                gain_cb: 160,
+                gain_cr: 160,
+                resync_field: false,
+                resync_line: 14,
+                resync_pixel: 12,
+                yc_en: true,
+            },
+            TvStandard::Pal => TvTiming {
+                height: 576,
+                lines: 625,
+                chroma_freq: 0x2A09_8ACB,
+                back_porch: 138,
+                front_porch: 24,
+                vbp: 22,
+                black_level: 252,
+                blank_level: 252,
+                vblank_level: 252,
+                burst_cb: 40,
+                burst_cr: 40,
+                gain_cb: 224,
+                gain_cr: 224,
+                resync_field: true,
+                resync_line: 13,
+                resync_pixel: 12,
+                yc_en: false,
+            },
+        }
+    }
+}
+
+/// TV output configuration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TvConfig {
+    pub standard: TvStandard,
+    /// Manual DAC0 full-scale trim (TVE_DAC1 amplitude, 0..=0x3F).
+    ///
+    /// Adjust per board for 1 Vpp into a 75 Ω load, measured with a scope;
+    /// 0x18 suits the internal 37.5 Ω termination.
+    pub dac_trim: u8,
+}
+
+impl TvConfig {
+    /// NTSC-M with the default DAC trim.
+    pub const fn ntsc() -> Self {
+        Self {
+            standard: TvStandard::Ntsc,
+            dac_trim: 0x18,
+        }
+    }
+
+    /// PAL-B/D/G with the default DAC trim.
+    pub const fn pal() -> Self {
+        Self {
+            standard: TvStandard::Pal,
+            dac_trim: 0x18,
+        }
+    }
+}
+
+/// Load detected on the composite DAC.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CableState {
+    Disconnected,
+    Connected,
+    /// Output shorted to ground
+    Shorted,
+}
+
+/// TVE IRQ handler — acknowledges cable detect changes and wakes waiters.
+fn tve_irq_handler() {
+    unsafe {
+        let sta = read(TVE_DETECT_INT_STA);
+        write(TVE_DETECT_INT_STA, sta);
+    }
+    CABLE_WAKER.wake();
+}
+
+/// Interrupt handler for TVE cable detection.
+///
+/// Registered automatically by [`Display::new_tv`]; `bind_interrupts!` is
+/// optional:
+/// ```ignore
+/// bind_interrupts!(struct Irqs {
+///     TVE => display::tve::InterruptHandler;
+/// });
+/// ```
+pub struct InterruptHandler {
+    _private: (),
+}
+
+impl Handler<crate::interrupt::typelevel::TVE> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        tve_irq_handler();
+    }
+}
+
+impl Display<Rgb565> {
+    /// Initialize the display subsystem for composite TV output.
+    ///
+    /// `fb_addr` must point to a framebuffer of
+    /// `standard.width() * standard.height()` RGB565 pixels.
+    ///
+    /// # Safety
+    /// See [`Display::new_tv_with_format`].
+    pub unsafe fn new_tv(config: &TvConfig, fb_addr: *mut u16) -> Self {
+        Self::new_tv_with_format(config, fb_addr)
+    }
+}
+
+impl<P: PixelFormat> Display<P> {
+    /// Initialize composite TV output with a framebuffer of format `P`, of
+    /// [`PixelFormat::buffer_size`] bytes for the standard's resolution.
+    ///
+    /// # Safety
+    /// - Nothing else may depend on a PLL_VIDEO rate other than 297MHz.
+    /// - `fb_addr` must be a valid, writable SDRAM address, aligned to 4 bytes.
+    /// - Only one `Display` may exist at a time.
+    pub unsafe fn new_tv_with_format(config: &TvConfig, fb_addr: *mut P::Raw) -> Self {
+        let std = config.standard;
+        Self::tv_init(config, fb_addr);
+        core::ptr::write_bytes(fb_addr as *mut u8, 0, P::buffer_size(std.width(), std.height()));
+        Self::from_parts(Mode::Tv(*config), None, fb_addr)
+    }
+
+    /// Bring up clocks, DEBE, TCON1 and the TV encoder for `config`, scanning out `fb_addr`.
+    pub(super) unsafe fn tv_init(config: &TvConfig, fb_addr: *mut P::Raw) {
+        let std = config.standard;
+
+        // 1. Clocks: DE blocks plus the TVE (27MHz from PLL_VIDEO)
+        rcc::set_pll_video(&PllVideo::freq_297mhz());
+        Self::enable_clocks();
+        ccu_write(
+            CCU_TVE_CLK,
+            TVE_CLK_SCLK2_GATING | TVE_CLK_SCLK1_GATING | TVE_CLK_DIV_27M,
+        );
+        ccu_set(CCU_BUS_CLK_GATING1, BUS1_TVE);
+        ccu_set(CCU_BUS_SOFT_RST1, BUS1_TVE);
+
+        // 2. DEBE: same framebuffer layer as the LCD path, interlaced scan-out
+        Self::clear_debe_sram();
+        Self::tcon_disable();
+        Self::debe_set_mode(std.width(), std.height(), fb_addr);
+        let debe = &*pac::Debe::ptr();
+        debe.debe_mode_ctrl_reg()
+            .modify(|r, w| w.bits(r.bits() | DEBE_MODCTL_ITLMOD_EN));
+        // The TVE encodes YUV: convert the DEBE output
+        for (i, coef) in DEBE_RGB2YUV_COEF.iter().enumerate() {
+            debe_reg(DEBE_OCRCOEF + i * 4).write_volatile(*coef);
+        }
+        debe_reg(DEBE_OCCTL).write_volatile(DEBE_OCCTL_EN);
+
+        // 3. TCON1 timing, TCON outputs mapped to TCON1
+        Self::tcon1_set_mode(std);
+
+        // 4. TV encoder
+        Self::tve_set_mode(config);
+
+        // 5. Enable TCON, layer 0, TCON1 vblank interrupt
+        Self::tcon_enable();
+        Self::layer_enable(0, true);
+        Self::vsync_init(vsync::INT0_TCON1_VB_EN);
+
+        // 6. Cable detection on DAC0
+        write(TVE_DETECT_DEBOUNCE, 0x0F);
+        write(TVE_DETECT_INT_STA, DETECT_INT_STA_DAC0);
+        write(TVE_DETECT_EN, DETECT_EN_DAC0 | DETECT_INT_EN_DAC0);
+        intc::set_irq_handler(Interrupt::TVE.number(), tve_irq_handler);
+        intc::enable_irq(Interrupt::TVE.number());
+    }
+
+    /// Switch off the encoder and DAC, its interrupt and clocks.
+    pub(super) unsafe fn tv_shutdown() {
+        intc::disable_irq(Interrupt::TVE.number());
+        write(TVE_DETECT_EN, 0);
+        write(TVE_DAC0, 0);
+        write(TVE_EN, 0);
+        debe_reg(DEBE_OCCTL).write_volatile(0);
+        ccu_clear(CCU_BUS_SOFT_RST1, BUS1_TVE);
+        ccu_clear(CCU_BUS_CLK_GATING1, BUS1_TVE);
+        ccu_write(CCU_TVE_CLK, 0);
+    }
+
+    /// Current load state of the composite output.
+    pub fn tv_cable_state(&self) -> CableState {
+        match unsafe { read(TVE_DETECT_STA) } & DETECT_STA_DAC0_MASK {
+            0 => CableState::Disconnected,
+            1 => CableState::Connected,
+            _ => CableState::Shorted,
+        }
+    }
+
+    /// Wait until a cable is plugged in or removed, and return the new state.
+    pub async fn wait_tv_cable_change(&self) -> CableState {
+        let start = self.tv_cable_state();
+        poll_fn(|cx| {
+            CABLE_WAKER.register(cx.waker());
+            let state = self.tv_cable_state();
+            if state != start {
+                Poll::Ready(state)
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Change the manual composite DAC trim (0..=0x3F), see [`TvConfig::dac_trim`].
+    pub fn set_tv_dac_trim(&self, trim: u8) {
+        unsafe { write(TVE_DAC1, (trim & 0x3F) as u32) };
+    }
+
+    /// Configure TCON1 for interlaced TV timing (27MHz, two samples per pixel).
+    unsafe fn tcon1_set_mode(std: TvStandard) {
+        let tcon = &*pac::Tcon::ptr();
+        let t = std.timing();
+        let w = std.width() as u32;
+        let field_h = t.height as u32 / 2;
+        // 858 / 864 pixel clocks per line at 13.5MHz
+        let h_total = match std {
+            TvStandard::Ntsc => 858,
+            TvStandard::Pal => 864,
+        };
+        let h_bp = t.back_porch + 2;
+
+        tcon.tcon_ctrl_reg().modify(|_, wr| wr.io_map_sel().set_bit());
+
+        let sta_dly = (t.lines as u32 / 2 - field_h).min(0x1F);
+        tcon_write(
+            TCON1_CTRL,
+            TCON1_CTRL_EN | TCON1_CTRL_INTERLACE_EN | (sta_dly << TCON1_CTRL_STA_DLY_SHIFT),
+        );
+
+        // Source, scaled and output sizes are identical (per field)
+        let size = ((w - 1) << 16) | (field_h - 1);
+        tcon_write(TCON1_BASIC0, size);
+        tcon_write(TCON1_BASIC1, size);
+        tcon_write(TCON1_BASIC2, size);
+        tcon_write(TCON1_BASIC3, ((h_total - 1) << 16) | (h_bp - 1));
+        // Vertical total is counted in half lines: one frame of both fields
+        tcon_write(TCON1_BASIC4, ((t.lines as u32) << 16) | (t.vbp as u32 - 1));
+        // HSYNC / VSYNC widths
+        tcon_write(TCON1_BASIC5, (1 << 16) | 1);
+        tcon_write(TCON1_IO_POL, 0);
+    }
+
+    /// Program the encoder for `config.standard` and enable DAC0.
+    unsafe fn tve_set_mode(config: &TvConfig) {
+        let t = config.standard.timing();
+        let res = match config.standard {
+            TvStandard::Ntsc => CFG0_RES_480I,
+            TvStandard::Pal => CFG0_RES_576I,
+        };
+
+        write(TVE_EN, EN_DAC0_MAP_COMPOSITE);
+        write(
+            TVE_CFG0,
+            res | CFG0_COMP_EN
+                | if t.yc_en { CFG0_YC_EN } else { 0 }
+                | CFG0_DAC_CONTROL_54M
+                | CFG0_CORE_DATAPATH_54M
+                | CFG0_CORE_CONTROL_54M,
+        );
+        write(
+            TVE_DAC0,
+            DAC0_DAC0_EN | DAC0_INTERNAL_DAC_37_5_OHMS | DAC0_CHROMA_0_75 | DAC0_LUMA_0_4 | DAC0_CLOCK_INVERT,
+        );
+        write(TVE_NOTCH, 0);
+        write(TVE_CHROMA_FREQ, t.chroma_freq);
+        write(TVE_PORCH, (t.back_porch << 16) | t.front_porch);
+        write(TVE_LINE, (22 << 16) | t.lines as u32);
+        write(TVE_LEVEL, (t.blank_level << 16) | t.black_level);
+        write(TVE_DAC1, (config.dac_trim & 0x3F) as u32);
+        write(TVE_CB_CR_LVL, (t.burst_cr << 8) | t.burst_cb);
+        // HSYNC width 126, burst width 68, breezeway 22 (27MHz samples)
+        write(TVE_BURST_WIDTH, (22 << 16) | (68 << 8) | 126);
+        write(TVE_CB_CR_GAIN, (t.gain_cr << 8) | t.gain_cb);
+        write(TVE_SYNC_VBI, (t.vblank_level << 16) | 0x10);
+        write(TVE_ACTIVE_LINE, 1440);
+        write(TVE_CHROMA, CHROMA_COMP_GAIN_50);
+        write(TVE_12C, C12_COMP_YUV_EN | C12_NOTCH_WIDTH_WIDE);
+        write(
+            TVE_RESYNC,
+            (if t.resync_field { RESYNC_FIELD } else { 0 }) | (t.resync_line << 16) | t.resync_pixel,
+        );
+        write(TVE_SLAVE, 0);
+
+        write(TVE_EN, EN_DAC0_MAP_COMPOSITE | EN_ENABLE);
+    }
+}
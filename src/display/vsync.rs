@@ -0,0 +1,197 @@
+//! VSync-synchronized operation: TCON vblank/line interrupts and page flipping.
+//!
+//! TCON_INT_REG0 layout (TCON0 bits only):
+//! - [31] TCON0 vblank interrupt enable, [15] flag
+//! - [30] TCON1 vblank interrupt enable, [14] flag (TV output)
+//! - [29] TCON0 line interrupt enable, [13] flag
+//!
+//! - [26] TCON0 CPU trigger-finish interrupt enable, [10] flag
+//!
+//! Flags are cleared by writing 0. TCON_INT_REG1[27:16] holds the line number
+//! that triggers the TCON0 line interrupt.
+//!
+//! Page flipping uses the DEBE register buffer: new layer registers are written,
+//! then `reg_load_ctrl` is set and the hardware latches them at the next vblank,
+//! clearing the bit when done.
+//!
+//! In i8080 CPU mode there is no continuous scan-out; each finished trigger
+//! frame counts as a vblank instead.
+
+use core::future::poll_fn;
+use core::task::Poll;
+
+use embassy_sync::waitqueue::AtomicWaker;
+use portable_atomic::{AtomicU32, Ordering};
+
+use super::i8080::{self, INT0_TCON0_TRI_FINISH_FLAG};
+use super::{Display, PixelFormat};
+use crate::interrupt::typelevel::Handler;
+use crate::interrupt::Interrupt;
+use crate::{intc, pac};
+
+pub(super) const INT0_TCON0_VB_EN: u32 = 1 << 31;
+pub(super) const INT0_TCON1_VB_EN: u32 = 1 << 30;
+const INT0_TCON0_LINE_EN: u32 = 1 << 29;
+const INT0_TCON0_VB_FLAG: u32 = 1 << 15;
+const INT0_TCON1_VB_FLAG: u32 = 1 << 14;
+const INT0_TCON0_LINE_FLAG: u32 = 1 << 13;
+
+const INT1_TCON0_LINE_NUM_SHIFT: u32 = 16;
+const INT1_TCON0_LINE_NUM_MASK: u32 = 0xFFF << INT1_TCON0_LINE_NUM_SHIFT;
+
+static VBLANK_WAKER: AtomicWaker = AtomicWaker::new();
+static VBLANK_COUNT: AtomicU32 = AtomicU32::new(0);
+static LINE_WAKER: AtomicWaker = AtomicWaker::new();
+static LINE_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// TCON IRQ handler — counts vblank/line events and wakes waiters.
+fn tcon_irq_handler() {
+    let tcon = unsafe { &*pac::Tcon::ptr() };
+    let status = tcon.tcon_int_reg0().read().bits();
+
+    let flags = status & (INT0_TCON0_VB_FLAG | INT0_TCON1_VB_FLAG | INT0_TCON0_LINE_FLAG | INT0_TCON0_TRI_FINISH_FLAG);
+    if flags != 0 {
+        // Flags are write-0-to-clear
+        tcon.tcon_int_reg0().write(|w| unsafe { w.bits(status & !flags) });
+    }
+
+    if status & (INT0_TCON0_VB_FLAG | INT0_TCON1_VB_FLAG) != 0 {
+        VBLANK_COUNT.fetch_add(1, Ordering::Release);
+        VBLANK_WAKER.wake();
+    }
+    if status & INT0_TCON0_LINE_FLAG != 0 {
+        LINE_COUNT.fetch_add(1, Ordering::Release);
+        LINE_WAKER.wake();
+    }
+    if status & INT0_TCON0_TRI_FINISH_FLAG != 0 {
+        VBLANK_COUNT.fetch_add(1, Ordering::Release);
+        VBLANK_WAKER.wake();
+        i8080::on_tri_finish();
+    }
+}
+
+/// Interrupt handler for the TCON vblank/line interrupts.
+///
+/// The handler is registered automatically by [`Display::new`], so
+/// `bind_interrupts!` is optional. It's provided for consistency with
+/// the embassy pattern:
+/// ```ignore
+/// bind_interrupts!(struct Irqs {
+///     TCON => display::InterruptHandler;
+/// });
+/// ```
+pub struct InterruptHandler {
+    _private: (),
+}
+
+impl Handler<crate::interrupt::typelevel::TCON> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        tcon_irq_handler();
+    }
+}
+
+impl<P: PixelFormat> Display<P> {
+    /// Register the TCON IRQ handler and enable the frame interrupt `en`:
+    /// TCON0 or TCON1 vblank, or the trigger-finish interrupt in CPU mode.
+    pub(super) unsafe fn vsync_init(en: u32) {
+        let tcon = &*pac::Tcon::ptr();
+
+        // Clear any stale flags, enable the frame interrupt
+        tcon.tcon_int_reg0().write(|w| w.bits(en));
+
+        intc::set_irq_handler(Interrupt::TCON.number(), tcon_irq_handler);
+        intc::enable_irq(Interrupt::TCON.number());
+    }
+
+    /// Number of vblank periods since the display was initialized (wraps).
+    pub fn frame_count(&self) -> u32 {
+        VBLANK_COUNT.load(Ordering::Acquire)
+    }
+
+    /// Wait for the start of the next vertical blanking period.
+    pub async fn wait_vblank(&self) {
+        let start = VBLANK_COUNT.load(Ordering::Acquire);
+        poll_fn(|cx| {
+            VBLANK_WAKER.register(cx.waker());
+            if VBLANK_COUNT.load(Ordering::Acquire) != start {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Wait until the panel scan reaches `line` (counted from the start of the
+    /// vertical total, i.e. including the back porch).
+    pub async fn wait_line(&self, line: u16) {
+        let tcon = unsafe { &*pac::Tcon::ptr() };
+        let mut start = None;
+        poll_fn(|cx| {
+            // Register and sample before arming, so the first match can't be missed
+            LINE_WAKER.register(cx.waker());
+            let count = LINE_COUNT.load(Ordering::Acquire);
+            match start {
+                None => {
+                    start = Some(count);
+                    critical_section::with(|_| {
+                        tcon.tcon_int_reg1().modify(|r, w| unsafe {
+                            w.bits(
+                                (r.bits() & !INT1_TCON0_LINE_NUM_MASK)
+                                    | ((line as u32 & 0xFFF) << INT1_TCON0_LINE_NUM_SHIFT),
+                            )
+                        });
+                        tcon.tcon_int_reg0()
+                            .modify(|r, w| unsafe { w.bits((r.bits() & !INT0_TCON0_LINE_FLAG) | INT0_TCON0_LINE_EN) });
+                    });
+                    Poll::Pending
+                }
+                Some(start) if count != start => Poll::Ready(()),
+                Some(_) => Poll::Pending,
+            }
+        })
+        .await;
+
+        critical_section::with(|_| {
+            tcon.tcon_int_reg0()
+                .modify(|r, w| unsafe { w.bits(r.bits() & !INT0_TCON0_LINE_EN) });
+        });
+    }
+
+    /// Tear-free page flip: scan out `back_fb` from the next frame on.
+    ///
+    /// The D-cache is cleaned for the whole back buffer, the new layer 0
+    /// address is written to the DEBE shadow registers and latched by the
+    /// hardware at vblank. Returns the previous front buffer once it is no
+    /// longer being scanned out; the drawing methods render into it from
+    /// then on. A double-buffered loop looks like:
+    /// ```ignore
+    /// unsafe { display.set_framebuffer(fb_b) };
+    /// loop {
+    ///     display.fill_rect(...);
+    ///     unsafe { display.swap_buffers(display.framebuffer()) }.await;
+    /// }
+    /// ```
+    ///
+    /// # Safety
+    /// `back_fb` must satisfy the same requirements as the framebuffer passed
+    /// to [`Display::new`]: valid SDRAM of [`Display::framebuffer_size`] bytes,
+    /// not used elsewhere while it is scanned out or drawn into.
+    pub async unsafe fn swap_buffers(&mut self, back_fb: *mut P::Raw) -> *mut P::Raw {
+        let size = self.framebuffer_size() as u32;
+        arm9::asm::clean_dcache_range(back_fb as u32, size);
+
+        Self::write_layer_addr(0, back_fb as u32);
+        Self::debe_load_regs();
+
+        // reg_load_ctrl self-clears once the shadow registers were latched at vblank
+        let debe = unsafe { &*pac::Debe::ptr() };
+        while debe.debe_reg_buff_ctrl_reg().read().reg_load_ctrl().bit_is_set() {
+            self.wait_vblank().await;
+        }
+
+        let old_front = core::mem::replace(&mut self.front, back_fb);
+        self.fb = old_front;
+        old_front
+    }
+}
@@ -0,0 +1,99 @@
+//! DEBE write-back: capture the composited output into SDRAM.
+//!
+//! The write-back path stores every pixel the back-end sends to the TCON, i.e.
+//! all enabled layers after blending (the hardware cursor included), as
+//! ARGB8888 into a buffer of `width * height` words.
+//!
+//! - DEBE_WB_CTRL (0x8F0): [0] write-back enable; while set, each frame is written
+//! - DEBE_WB_ADDR (0x8F4): buffer byte address
+//! - DEBE_WB_LINE_WIDTH (0x8F8): line stride in bits
+//!
+//! Frames are delimited by vblank, so capture needs a running scan-out; in
+//! i8080 mode every triggered frame counts.
+
+use embassy_hal_internal::drop::OnDrop;
+
+use super::layer::debe_reg;
+use super::{Display, PixelFormat};
+
+pub(super) const DEBE_WB_CTRL: usize = 0x8F0;
+const DEBE_WB_ADDR: usize = 0x8F4;
+const DEBE_WB_LINE_WIDTH: usize = 0x8F8;
+
+const WB_CTRL_EN: u32 = 1 << 0;
+
+impl<P: PixelFormat> Display<P> {
+    /// Size of a capture buffer in bytes (ARGB8888).
+    pub fn capture_size(&self) -> usize {
+        self.width as usize * self.height as usize * 4
+    }
+
+    /// Capture the next complete frame into `buf` (`0xAARRGGBB` per pixel).
+    ///
+    /// `buf` must be 32-byte aligned and hold at least `width * height`
+    /// pixels rounded up to whole cache lines, since the cache lines covering
+    /// it are invalidated. Not available while continuous capture runs.
+    ///
+    /// Dropping the future stops the write-back.
+    pub async fn capture(&mut self, buf: &mut [u32]) {
+        let addr = buf.as_mut_ptr() as u32;
+        let size = (self.capture_size() as u32 + 31) & !31;
+        assert!(addr % 32 == 0 && buf.len() * 4 >= size as usize);
+        assert!(self.capture_buf.is_null());
+
+        // Dirty lines must not be evicted over the write-back data
+        arm9::asm::clean_dcache_range(addr, size);
+        arm9::asm::invalidate_dcache_range(addr, size);
+
+        // Arm at the start of vblank so exactly one whole frame is written
+        self.wait_vblank().await;
+        let wb_off = OnDrop::new(|| unsafe { debe_reg(DEBE_WB_CTRL).write_volatile(0) });
+        unsafe { self.wb_enable(addr) };
+        self.wait_vblank().await;
+        drop(wb_off);
+
+        arm9::asm::invalidate_dcache_range(addr, size);
+    }
+
+    /// Write every frame into `buf` until [`Display::stop_capture`].
+    ///
+    /// Use [`Display::wait_capture`] to read a frame; it is only stable until
+    /// the next frame starts overwriting it.
+    ///
+    /// # Safety
+    /// `buf` must be valid for [`Display::capture_size`] bytes, 32-byte
+    /// aligned, and stay valid until capture is stopped.
+    pub unsafe fn start_capture(&mut self, buf: *mut u32) {
+        self.capture_buf = buf;
+        arm9::asm::clean_dcache_range(buf as u32, self.capture_size() as u32);
+        arm9::asm::invalidate_dcache_range(buf as u32, self.capture_size() as u32);
+        self.wb_enable(buf as u32);
+    }
+
+    /// Stop continuous capture. The buffer is released after the current frame.
+    pub async fn stop_capture(&mut self) {
+        if self.capture_buf.is_null() {
+            return;
+        }
+        unsafe { debe_reg(DEBE_WB_CTRL).write_volatile(0) };
+        self.wait_vblank().await;
+        self.capture_buf = core::ptr::null_mut();
+    }
+
+    /// Wait until the continuous capture buffer holds a new complete frame
+    /// and drop stale cache lines of it. Returns `None` when not capturing.
+    pub async fn wait_capture(&self) -> Option<*const u32> {
+        if self.capture_buf.is_null() {
+            return None;
+        }
+        self.wait_vblank().await;
+        arm9::asm::invalidate_dcache_range(self.capture_buf as u32, self.capture_size() as u32);
+        Some(self.capture_buf)
+    }
+
+    unsafe fn wb_enable(&self, addr: u32) {
+        debe_reg(DEBE_WB_ADDR).write_volatile(addr);
+        debe_reg(DEBE_WB_LINE_WIDTH).write_volatile(self.width as u32 * 32);
+        debe_reg(DEBE_WB_CTRL).write_volatile(WB_CTRL_EN);
+    }
+}
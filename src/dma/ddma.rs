@@ -0,0 +1,333 @@
+//! Dedicated DMA (DDMA) channels.
+//!
+//! The four DDMA channels move up to 16MB per transfer and add a parameter
+//! register with per-side data block size and wait cycles, plus horizontal and
+//! vertical page addressing. They suit large memory copies and bulk
+//! peripherals (USB); the NDMA channels stay available for SPI/UART.
+//!
+//! Register layout (DMA + 0x300 + n * 0x20):
+//! - DDMA_CFG (+0x00): same fields as NDMA_CFG, address modes are 2 bits
+//!   (linear, IO, horizontal page, vertical page), [30] busy, [31] loading
+//! - DDMA_SRC_ADR (+0x04), DDMA_DES_ADR (+0x08), DDMA_BYTE_CNT (+0x0C)
+//! - DDMA_PAR (+0x18): [7:0] src wait cycles - 1, [15:8] src block size - 1,
+//!   [23:16] dst wait cycles - 1, [31:24] dst block size - 1
+//!
+//! Half/full interrupts are DMA_INT_CTRL/STA bits 16 + 2n / 17 + 2n.
+//! Completion is tracked through DMA_INT_STA like the NDMA channels.
+
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::sync::atomic::{compiler_fence, fence, Ordering};
+use core::task::{Context, Poll};
+
+use super::{AnyDdmaChannel, BurstLen, ChannelState, DataWidth, DdmaChannel, SealedChannel, DMA_BASE};
+use crate::{pac, Peri};
+
+/// DDMA channel count
+pub const DDMA_COUNT: usize = 4;
+
+/// Largest DDMA byte count
+pub const DDMA_MAX_BYTES: u32 = 0x100_0000;
+
+/// First DDMA bit in DMA_INT_CTRL/DMA_INT_STA
+const INT_SHIFT: usize = 16;
+
+/// DRQ type for DDMA source/destination
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DdmaDrqType {
+    Sram = 0x00,
+    Sdram = 0x01,
+    Usb = 0x04,
+}
+
+impl DdmaDrqType {
+    /// Auto-detect the correct memory DRQ type based on address.
+    /// Addresses >= 0x8000_0000 are SDRAM, otherwise SRAM.
+    #[inline]
+    pub fn for_addr(addr: u32) -> Self {
+        if addr >= 0x8000_0000 {
+            DdmaDrqType::Sdram
+        } else {
+            DdmaDrqType::Sram
+        }
+    }
+}
+
+/// DDMA address mode
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DdmaAddrType {
+    /// Linear (auto-increment)
+    Linear = 0,
+    /// IO (fixed address)
+    Io = 1,
+    /// Horizontal page: blocks of `block_size` units along a page row
+    HorizontalPage = 2,
+    /// Vertical page: blocks of `block_size` units down a page column
+    VerticalPage = 3,
+}
+
+/// DDMA transfer configuration
+#[derive(Debug, Copy, Clone)]
+pub struct DdmaConfig {
+    pub src_drq: DdmaDrqType,
+    pub src_addr_type: DdmaAddrType,
+    pub src_burst: BurstLen,
+    pub src_width: DataWidth,
+    pub dst_drq: DdmaDrqType,
+    pub dst_addr_type: DdmaAddrType,
+    pub dst_burst: BurstLen,
+    pub dst_width: DataWidth,
+    /// Data block size for page modes (1..=256 units)
+    pub src_block_size: u16,
+    /// Cycles between source accesses (1..=256)
+    pub src_wait_cycles: u16,
+    pub dst_block_size: u16,
+    pub dst_wait_cycles: u16,
+    /// Continuous mode (auto-reload)
+    pub continuous: bool,
+}
+
+impl Default for DdmaConfig {
+    fn default() -> Self {
+        Self {
+            src_drq: DdmaDrqType::Sdram,
+            src_addr_type: DdmaAddrType::Linear,
+            src_burst: BurstLen::Single,
+            src_width: DataWidth::Bit8,
+            dst_drq: DdmaDrqType::Sdram,
+            dst_addr_type: DdmaAddrType::Linear,
+            dst_burst: BurstLen::Single,
+            dst_width: DataWidth::Bit8,
+            src_block_size: 1,
+            src_wait_cycles: 1,
+            dst_block_size: 1,
+            dst_wait_cycles: 1,
+            continuous: false,
+        }
+    }
+}
+
+impl DdmaConfig {
+    /// Memory to memory copy, 32-bit bursts when `src`, `dst` and `len`
+    /// are word aligned.
+    pub fn memcpy(src: u32, dst: u32, len: u32) -> Self {
+        let (width, burst) = if (src | dst | len) % 4 == 0 {
+            (DataWidth::Bit32, BurstLen::Burst4)
+        } else {
+            (DataWidth::Bit8, BurstLen::Single)
+        };
+        Self {
+            src_drq: DdmaDrqType::for_addr(src),
+            src_burst: burst,
+            src_width: width,
+            dst_drq: DdmaDrqType::for_addr(dst),
+            dst_burst: burst,
+            dst_width: width,
+            ..Default::default()
+        }
+    }
+
+    fn cfg(&self) -> u32 {
+        (self.src_drq as u32)
+            | ((self.src_addr_type as u32) << 5)
+            | ((self.src_burst as u32) << 7)
+            | ((self.src_width as u32) << 8)
+            | (1u32 << 15)
+            | ((self.dst_drq as u32) << 16)
+            | ((self.dst_addr_type as u32) << 21)
+            | ((self.dst_burst as u32) << 23)
+            | ((self.dst_width as u32) << 24)
+            | ((self.continuous as u32) << 29)
+    }
+
+    fn par(&self) -> u32 {
+        let field = |n: u16| (n.clamp(1, 256) - 1) as u32;
+        field(self.src_wait_cycles)
+            | (field(self.src_block_size) << 8)
+            | (field(self.dst_wait_cycles) << 16)
+            | (field(self.dst_block_size) << 24)
+    }
+}
+
+pub(super) static CHANNEL_STATE: [ChannelState; DDMA_COUNT] = [
+    ChannelState::NEW,
+    ChannelState::NEW,
+    ChannelState::NEW,
+    ChannelState::NEW,
+];
+
+/// Dispatch DDMA half/full events from a DMA_INT_STA snapshot; only full
+/// completes a transfer.
+pub(super) fn on_interrupt(status: u32) {
+    let dma = unsafe { &*pac::Dma::ptr() };
+    for ch in 0..DDMA_COUNT {
+        let bits = status & (0x3u32 << (INT_SHIFT + ch * 2));
+        if bits != 0 {
+            dma.dma_int_sta().write(|w| unsafe { w.bits(bits) });
+
+            if bits & full_bit(ch) != 0 {
+                CHANNEL_STATE[ch].laps.fetch_add(1, Ordering::Release);
+                CHANNEL_STATE[ch].complete.store(true, Ordering::Release);
+            }
+            CHANNEL_STATE[ch].waker.wake();
+        }
+    }
+}
+
+// ============================================================================
+// Low-level DDMA channel register access
+// ============================================================================
+
+/// DDMA0=0x300, DDMA1=0x320, DDMA2=0x340, DDMA3=0x360
+#[inline]
+fn ddma_reg(ch: usize, offset: usize) -> *mut u32 {
+    (DMA_BASE + 0x300 + ch * 0x20 + offset) as *mut u32
+}
+
+const DDMA_CFG: usize = 0x00;
+const DDMA_SRC_ADR: usize = 0x04;
+const DDMA_DES_ADR: usize = 0x08;
+const DDMA_BYTE_CNT: usize = 0x0C;
+const DDMA_PAR: usize = 0x18;
+
+#[inline]
+fn full_bit(ch: usize) -> u32 {
+    1u32 << (INT_SHIFT + ch * 2 + 1)
+}
+
+/// Configure and start a DDMA transfer.
+///
+/// # Safety
+/// Caller must ensure addresses and lengths are valid.
+unsafe fn ddma_start(ch: usize, src: u32, dst: u32, byte_count: u32, config: &DdmaConfig) {
+    assert!(ch < DDMA_COUNT);
+    assert!(byte_count > 0 && byte_count <= DDMA_MAX_BYTES);
+
+    // Same cache maintenance as NDMA: DMA works on physical memory
+    if config.src_addr_type != DdmaAddrType::Io && super::is_cached_addr(src) {
+        arm9::asm::clean_dcache_range(src, byte_count);
+    }
+    if config.dst_addr_type != DdmaAddrType::Io && super::is_cached_addr(dst) {
+        arm9::asm::invalidate_dcache_range(dst, byte_count);
+    }
+
+    let dma = &*pac::Dma::ptr();
+    CHANNEL_STATE[ch].complete.store(false, Ordering::Release);
+    CHANNEL_STATE[ch].laps.store(0, Ordering::Release);
+    // Stale full bit from a previous run would read as "done"
+    dma.dma_int_sta().write(|w| w.bits(0x3u32 << (INT_SHIFT + ch * 2)));
+
+    core::ptr::write_volatile(ddma_reg(ch, DDMA_SRC_ADR), src);
+    core::ptr::write_volatile(ddma_reg(ch, DDMA_DES_ADR), dst);
+    core::ptr::write_volatile(ddma_reg(ch, DDMA_BYTE_CNT), byte_count);
+    core::ptr::write_volatile(ddma_reg(ch, DDMA_PAR), config.par());
+
+    let cfg = config.cfg();
+    core::ptr::write_volatile(ddma_reg(ch, DDMA_CFG), cfg);
+
+    dma.dma_int_ctrl().modify(|r, w| w.bits(r.bits() | full_bit(ch)));
+
+    compiler_fence(Ordering::SeqCst);
+
+    core::ptr::write_volatile(ddma_reg(ch, DDMA_CFG), cfg | (1u32 << 31));
+}
+
+/// Check if DDMA channel is busy (via DMA_INT_STA, not CFG register)
+#[inline]
+fn ddma_is_busy(ch: usize) -> bool {
+    let dma = unsafe { &*pac::Dma::ptr() };
+    dma.dma_int_sta().read().bits() & full_bit(ch) == 0
+}
+
+/// Stop a DDMA channel
+unsafe fn ddma_stop(ch: usize) {
+    core::ptr::write_volatile(ddma_reg(ch, DDMA_CFG), 0);
+
+    let dma = &*pac::Dma::ptr();
+    let bits = 0x3u32 << (INT_SHIFT + ch * 2);
+    dma.dma_int_ctrl().modify(|r, w| w.bits(r.bits() & !bits));
+    dma.dma_int_sta().write(|w| w.bits(bits));
+}
+
+// ============================================================================
+// Async DMA Transfer
+// ============================================================================
+
+/// An async DDMA transfer. Completes when the DMA finishes.
+///
+/// Dropping a transfer waits until the channel is idle.
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Transfer<'d> {
+    ch: usize,
+    _ch: PhantomData<&'d mut AnyDdmaChannel>,
+}
+
+impl<'d> Transfer<'d> {
+    /// Start a new DDMA transfer.
+    ///
+    /// # Safety
+    /// `src` and `dst` must be valid for `byte_count` bytes.
+    /// The channel stays borrowed until the transfer is dropped.
+    pub unsafe fn new(
+        ch: Peri<'d, impl DdmaChannel>,
+        src: u32,
+        dst: u32,
+        byte_count: u32,
+        config: &DdmaConfig,
+    ) -> Self {
+        let ch = ch.number();
+        ddma_start(ch, src, dst, byte_count, config);
+        Self { ch, _ch: PhantomData }
+    }
+
+    /// Start a memory to memory copy of `len` bytes (see [`DdmaConfig::memcpy`]).
+    ///
+    /// # Safety
+    /// `src` and `dst` must be valid for `len` bytes and must not overlap.
+    pub unsafe fn memcpy(ch: Peri<'d, impl DdmaChannel>, src: *const u8, dst: *mut u8, len: usize) -> Self {
+        let (src, dst) = (src as u32, dst as u32);
+        Self::new(ch, src, dst, len as u32, &DdmaConfig::memcpy(src, dst, len as u32))
+    }
+
+    /// Check if the transfer is still running.
+    pub fn is_running(&self) -> bool {
+        !CHANNEL_STATE[self.ch].complete.load(Ordering::Acquire) && ddma_is_busy(self.ch)
+    }
+
+    /// Blocking wait until transfer completes.
+    pub fn blocking_wait(self) {
+        while self.is_running() {}
+        fence(Ordering::SeqCst);
+        core::mem::forget(self);
+    }
+}
+
+impl<'d> Drop for Transfer<'d> {
+    fn drop(&mut self) {
+        // Same as the NDMA transfer: stopping clears the full bit that
+        // shows the channel idle, so wait before stopping
+        while self.is_running() {}
+        unsafe { ddma_stop(self.ch) };
+        fence(Ordering::SeqCst);
+    }
+}
+
+impl<'d> Unpin for Transfer<'d> {}
+
+impl<'d> Future for Transfer<'d> {
+    type Output = ();
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let state = &CHANNEL_STATE[self.ch];
+        state.waker.register(cx.waker());
+
+        if state.complete.load(Ordering::Acquire) || !ddma_is_busy(self.ch) {
+            fence(Ordering::SeqCst);
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
@@ -2,6 +2,16 @@
 //!
 //! The F1C100S has 4 Normal DMA (NDMA) channels suitable for SPI, UART, etc.
 //! Each NDMA channel supports up to 128KB per transfer.
+//! The 4 Dedicated DMA (DDMA) channels in [`ddma`] handle up to 16MB with
+//! page addressing and wait-cycle control.
+//!
+//! Channels are the `NDMA0..3` and `DDMA0..3` peripheral singletons. Drivers
+//! take a `Peri<'d, impl NdmaChannel>` (or [`DdmaChannel`]) by value and keep
+//! it for as long as a transfer runs, so two users of one channel fail to
+//! compile. Pass `ch.reborrow()` to use a channel again afterwards.
+//!
+//! [`RingBuffer`] runs an NDMA channel in continuous mode for streaming
+//! peripherals (audio codec, UART RX, touch-panel ADC).
 //!
 //! DRQ types for NDMA:
 //! - SPI0_RX=0x04, SPI0_TX=0x04
@@ -10,10 +20,16 @@
 
 #![macro_use]
 
-use crate::pac;
+use core::marker::PhantomData;
 
+use crate::{impl_peripheral, pac, peripherals, Peri, PeripheralType};
+
+pub mod ddma;
+mod ringbuffer;
 pub mod word;
 
+pub use ringbuffer::{OverrunError, RingBuffer};
+
 /// NDMA channel count
 pub const NDMA_COUNT: usize = 4;
 
@@ -58,6 +74,16 @@ pub enum DataWidth {
     Bit32 = 2,
 }
 
+impl From<word::WordSize> for DataWidth {
+    fn from(size: word::WordSize) -> Self {
+        match size {
+            word::WordSize::OneByte => DataWidth::Bit8,
+            word::WordSize::TwoBytes => DataWidth::Bit16,
+            word::WordSize::FourBytes => DataWidth::Bit32,
+        }
+    }
+}
+
 /// DMA address type
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u8)]
@@ -110,23 +136,103 @@ impl Default for NdmaConfig {
     }
 }
 
+// ============================================================================
+// Channel singletons
+// ============================================================================
+
+pub(crate) trait SealedChannel {
+    /// Channel index within its kind (0..=3)
+    fn number(&self) -> usize;
+}
+
+/// DMA channel. Owning its singleton is what entitles a driver to use it.
+#[allow(private_bounds)]
+pub trait Channel: SealedChannel + PeripheralType + 'static {}
+
+/// Normal DMA channel (`NDMA0`..`NDMA3`).
+pub trait NdmaChannel: Channel + Into<AnyNdmaChannel> {}
+
+/// Dedicated DMA channel (`DDMA0`..`DDMA3`).
+pub trait DdmaChannel: Channel + Into<AnyDdmaChannel> {}
+
+/// Type-erased NDMA channel.
+pub struct AnyNdmaChannel {
+    number: u8,
+}
+
+impl_peripheral!(AnyNdmaChannel);
+
+impl SealedChannel for AnyNdmaChannel {
+    fn number(&self) -> usize {
+        self.number as usize
+    }
+}
+impl Channel for AnyNdmaChannel {}
+impl NdmaChannel for AnyNdmaChannel {}
+
+/// Type-erased DDMA channel.
+pub struct AnyDdmaChannel {
+    number: u8,
+}
+
+impl_peripheral!(AnyDdmaChannel);
+
+impl SealedChannel for AnyDdmaChannel {
+    fn number(&self) -> usize {
+        self.number as usize
+    }
+}
+impl Channel for AnyDdmaChannel {}
+impl DdmaChannel for AnyDdmaChannel {}
+
+macro_rules! impl_channel {
+    ($kind:ident, $any:ident, $name:ident, $number:expr) => {
+        impl SealedChannel for peripherals::$name {
+            fn number(&self) -> usize {
+                $number
+            }
+        }
+        impl Channel for peripherals::$name {}
+        impl $kind for peripherals::$name {}
+
+        impl From<peripherals::$name> for $any {
+            fn from(_: peripherals::$name) -> Self {
+                Self { number: $number }
+            }
+        }
+    };
+}
+
+impl_channel!(NdmaChannel, AnyNdmaChannel, NDMA0, 0);
+impl_channel!(NdmaChannel, AnyNdmaChannel, NDMA1, 1);
+impl_channel!(NdmaChannel, AnyNdmaChannel, NDMA2, 2);
+impl_channel!(NdmaChannel, AnyNdmaChannel, NDMA3, 3);
+impl_channel!(DdmaChannel, AnyDdmaChannel, DDMA0, 0);
+impl_channel!(DdmaChannel, AnyDdmaChannel, DDMA1, 1);
+impl_channel!(DdmaChannel, AnyDdmaChannel, DDMA2, 2);
+impl_channel!(DdmaChannel, AnyDdmaChannel, DDMA3, 3);
+
 use core::future::Future;
 use core::pin::Pin;
 use core::sync::atomic::{compiler_fence, fence, AtomicBool, Ordering};
 use core::task::{Context, Poll};
 
 use embassy_sync::waitqueue::AtomicWaker;
+use portable_atomic::AtomicU32;
 
 /// Per-channel state for async wakeup
 struct ChannelState {
     waker: AtomicWaker,
     complete: AtomicBool,
+    /// Full events seen, i.e. passes completed in continuous mode
+    laps: AtomicU32,
 }
 
 impl ChannelState {
     const NEW: Self = Self {
         waker: AtomicWaker::new(),
         complete: AtomicBool::new(false),
+        laps: AtomicU32::new(0),
     };
 }
 
@@ -137,6 +243,17 @@ static CHANNEL_STATE: [ChannelState; NDMA_COUNT] = [
     ChannelState::NEW,
 ];
 
+/// NDMA channels running a [`RingBuffer`], one bit per channel
+static RING_CHANNELS: AtomicU32 = AtomicU32::new(0);
+
+/// Whether the DMA interrupt can be masked for a while.
+///
+/// A [`RingBuffer`] counts its passes in the interrupt handler and loses one
+/// when two complete while it is masked, so not while a ring is running.
+pub(crate) fn irq_maskable() -> bool {
+    RING_CHANNELS.load(Ordering::Acquire) == 0
+}
+
 /// Initialize the DMA controller. Called once from hal::init().
 pub(crate) unsafe fn init() {
     let ccu = &*pac::Ccu::ptr();
@@ -167,22 +284,31 @@ pub(crate) unsafe fn init() {
     crate::intc::enable_irq(crate::interrupt::Interrupt::DMA.number());
 }
 
-/// DMA IRQ handler — dispatches half/full transfer events for all NDMA channels
+/// DMA IRQ handler — dispatches half/full transfer events for all NDMA and DDMA channels
 fn dma_irq_handler() {
     let dma = unsafe { &*pac::Dma::ptr() };
-    let status = dma.dma_int_sta().read().bits();
+    // Channels with their interrupts off are polled; leave their bits alone
+    let status = dma.dma_int_sta().read().bits() & dma.dma_int_ctrl().read().bits();
 
     for ch in 0..NDMA_COUNT {
-        let full_bit = 1u32 << (ch * 2 + 1);
         let half_bit = 1u32 << (ch * 2);
-
-        if status & (full_bit | half_bit) != 0 {
-            dma.dma_int_sta().write(|w| unsafe { w.bits(full_bit | half_bit) });
-
-            CHANNEL_STATE[ch].complete.store(true, Ordering::Release);
+        let full_bit = half_bit << 1;
+        let bits = status & (half_bit | full_bit);
+
+        if bits != 0 {
+            // Clear only what was seen, a later event stays pending
+            dma.dma_int_sta().write(|w| unsafe { w.bits(bits) });
+
+            // Half events only wake; the transfer is done on full
+            if bits & full_bit != 0 {
+                CHANNEL_STATE[ch].laps.fetch_add(1, Ordering::Release);
+                CHANNEL_STATE[ch].complete.store(true, Ordering::Release);
+            }
             CHANNEL_STATE[ch].waker.wake();
         }
     }
+
+    ddma::on_interrupt(status);
 }
 
 // ============================================================================
@@ -211,6 +337,9 @@ fn ndma_byte_cnt_addr(ch: usize) -> *mut u32 {
     (DMA_BASE + 0x10C + ch * 0x20) as *mut u32
 }
 
+/// Valid bits of NDMA_BYTE_CNT; reads back the bytes left in the current pass
+const NDMA_BYTE_CNT_MASK: u32 = 0x3_FFFF;
+
 /// Check if an address is in SDRAM (cached) region.
 #[inline]
 fn is_cached_addr(addr: u32) -> bool {
@@ -237,6 +366,7 @@ unsafe fn ndma_start(ch: usize, src: u32, dst: u32, byte_count: u32, config: &Nd
 
     let state = &CHANNEL_STATE[ch];
     state.complete.store(false, Ordering::Release);
+    state.laps.store(0, Ordering::Release);
 
     core::ptr::write_volatile(ndma_src_addr(ch), src);
     core::ptr::write_volatile(ndma_dst_addr(ch), dst);
@@ -256,11 +386,11 @@ unsafe fn ndma_start(ch: usize, src: u32, dst: u32, byte_count: u32, config: &Nd
 
     core::ptr::write_volatile(ndma_cfg_addr(ch), cfg);
 
+    // Continuous transfers also report the half-way point
+    let irq_en = if config.continuous { 0x3u32 } else { 0x2u32 } << (ch * 2);
     let dma = &*pac::Dma::ptr();
-    dma.dma_int_ctrl().modify(|r, w| {
-        let full_en_bit = 1u32 << (ch * 2 + 1);
-        w.bits(r.bits() | full_en_bit)
-    });
+    dma.dma_int_sta().write(|w| w.bits(0x3u32 << (ch * 2)));
+    dma.dma_int_ctrl().modify(|r, w| w.bits(r.bits() | irq_en));
 
     compiler_fence(Ordering::SeqCst);
 
@@ -270,6 +400,9 @@ unsafe fn ndma_start(ch: usize, src: u32, dst: u32, byte_count: u32, config: &Nd
 
 /// Start an NDMA transfer for polling via DMA_INT_STA.
 ///
+/// Wait for completion with [`ndma_poll_wait`] on the same channel; keep the
+/// singleton and pass `ch.reborrow()` to both.
+///
 /// # Safety
 /// Caller must ensure addresses and lengths are valid.
 ///
@@ -278,7 +411,14 @@ unsafe fn ndma_start(ch: usize, src: u32, dst: u32, byte_count: u32, config: &Nd
 /// extra bus activity (stack operations in SDRAM, UART polling) between
 /// SPI setup and DMA CFG write can cause AHB bus lockups.
 #[inline(always)]
-pub unsafe fn ndma_start_poll(ch: usize, src: u32, dst: u32, byte_count: u32, config: &NdmaConfig) {
+pub unsafe fn ndma_start_poll(
+    ch: Peri<'_, impl NdmaChannel>,
+    src: u32,
+    dst: u32,
+    byte_count: u32,
+    config: &NdmaConfig,
+) {
+    let ch = ch.number();
     // Cache maintenance: flush src data from D-cache to physical memory
     if config.src_addr_type == AddrType::Linear && is_cached_addr(src) {
         arm9::asm::clean_dcache_range(src, byte_count);
@@ -286,17 +426,20 @@ pub unsafe fn ndma_start_poll(ch: usize, src: u32, dst: u32, byte_count: u32, co
     // NOTE: dst cache invalidation is done by the caller AFTER DMA completes,
     // to avoid invalidating cache lines that overlap with stack/local variables.
 
-    // Exactly match the working raw test sequence:
+    // Only this channel's bits are touched; other channels keep running
+    let dma = &*pac::Dma::ptr();
+    let bits = 0x3u32 << (ch * 2);
     // 1. Stop channel
     core::ptr::write_volatile(ndma_cfg_addr(ch), 0);
-    // 2. Clear ALL pending interrupt flags
-    core::ptr::write_volatile((0x01C0_2004) as *mut u32, 0x00FF_00FF);
+    // 2. Clear this channel's pending flags
+    dma.dma_int_sta().write(|w| w.bits(bits));
     // 3. Write src/dst/cnt
     core::ptr::write_volatile(ndma_src_addr(ch), src);
     core::ptr::write_volatile(ndma_dst_addr(ch), dst);
     core::ptr::write_volatile(ndma_byte_cnt_addr(ch), byte_count);
-    // 4. Disable DMA interrupts (polling mode — don't let IRQ handler steal the pending bit)
-    core::ptr::write_volatile((0x01C0_2000) as *mut u32, 0);
+    // 4. Disable this channel's interrupts (polling mode — the IRQ handler
+    //    skips disabled channels, so it can't steal the pending bit)
+    dma.dma_int_ctrl().modify(|r, w| w.bits(r.bits() & !bits));
 
     let cfg: u32 = (config.src_drq as u32)
         | ((config.src_addr_type as u32) << 5)
@@ -322,7 +465,8 @@ pub unsafe fn ndma_start_poll(ch: usize, src: u32, dst: u32, byte_count: u32, co
 ///
 /// # Safety
 /// Channel must have been started with ndma_start_poll.
-pub unsafe fn ndma_poll_wait(ch: usize) {
+pub unsafe fn ndma_poll_wait(ch: Peri<'_, impl NdmaChannel>) {
+    let ch = ch.number();
     let dma = &*pac::Dma::ptr();
     let full_bit = 1u32 << (ch * 2 + 1);
 
@@ -374,19 +518,30 @@ unsafe fn ndma_stop(ch: usize) {
 // ============================================================================
 
 /// An async NDMA transfer. Completes when the DMA finishes.
+///
+/// Dropping a transfer waits until the channel is idle.
 #[must_use = "futures do nothing unless you `.await` or poll them"]
-pub struct Transfer {
+pub struct Transfer<'d> {
     ch: usize,
+    _ch: PhantomData<&'d mut AnyNdmaChannel>,
 }
 
-impl Transfer {
+impl<'d> Transfer<'d> {
     /// Start a new NDMA transfer.
     ///
     /// # Safety
     /// `src` and `dst` must be valid for `byte_count` bytes.
-    pub unsafe fn new(ch: usize, src: u32, dst: u32, byte_count: u32, config: &NdmaConfig) -> Self {
+    /// The channel stays borrowed until the transfer is dropped.
+    pub unsafe fn new(
+        ch: Peri<'d, impl NdmaChannel>,
+        src: u32,
+        dst: u32,
+        byte_count: u32,
+        config: &NdmaConfig,
+    ) -> Self {
+        let ch = ch.number();
         ndma_start(ch, src, dst, byte_count, config);
-        Self { ch }
+        Self { ch, _ch: PhantomData }
     }
 
     /// Check if the transfer is still running.
@@ -402,17 +557,19 @@ impl Transfer {
     }
 }
 
-impl Drop for Transfer {
+impl<'d> Drop for Transfer<'d> {
     fn drop(&mut self) {
+        // Idle is only visible as the full bit (NDMA_CFG must not be read
+        // while busy) and stopping clears it, so wait before stopping
+        while self.is_running() {}
         unsafe { ndma_stop(self.ch) };
-        while ndma_is_busy(self.ch) {}
         fence(Ordering::SeqCst);
     }
 }
 
-impl Unpin for Transfer {}
+impl<'d> Unpin for Transfer<'d> {}
 
-impl Future for Transfer {
+impl<'d> Future for Transfer<'d> {
     type Output = ();
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
         let state = &CHANNEL_STATE[self.ch];
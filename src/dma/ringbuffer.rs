@@ -0,0 +1,333 @@
+//! Circular NDMA transfers.
+//!
+//! A [`RingBuffer`] keeps one NDMA channel running in continuous mode over a
+//! memory buffer, between the buffer and a peripheral data register. The
+//! hardware position is the number of completed passes (counted by the full
+//! interrupt) plus the bytes done in the current pass (from NDMA_BYTE_CNT).
+//! The half and full interrupts wake a waiting [`RingBuffer::read`] or
+//! [`RingBuffer::write`], so data arrives in half-buffer steps at the latest.
+//!
+//! Lap counting needs the DMA interrupt. The polling helpers
+//! ([`ndma_start_poll`](super::ndma_start_poll),
+//! [`Spi::dma_transfer_blocking`](crate::spi::Spi::dma_transfer_blocking))
+//! only touch their own channel's bits, and a full event that is still
+//! pending is counted. Two passes completed with the interrupt masked would
+//! count as one, so the SPI path leaves it enabled while a ring is running.
+//!
+//! The buffer should be 32-byte aligned and a multiple of 32 bytes long:
+//! cache lines covering it are invalidated (read) or cleaned (write) as data
+//! is consumed or queued.
+
+use core::cell::Cell;
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::sync::atomic::{fence, Ordering};
+use core::task::Poll;
+
+use super::word::Word;
+use super::{
+    is_cached_addr, ndma_byte_cnt_addr, ndma_start, ndma_stop, AddrType, AnyNdmaChannel, NdmaChannel, NdmaConfig,
+    NdmaDrqType, SealedChannel, CHANNEL_STATE, NDMA_BYTE_CNT_MASK, RING_CHANNELS,
+};
+use crate::{pac, Peri};
+
+/// The DMA overwrote data that was not read yet, or ran out of data to send.
+///
+/// Call [`RingBuffer::clear`] to continue.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OverrunError;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Dir {
+    /// Peripheral to memory
+    Read,
+    /// Memory to peripheral
+    Write,
+}
+
+/// Continuous NDMA transfer over a circular buffer.
+pub struct RingBuffer<'d, W: Word> {
+    ch: usize,
+    dir: Dir,
+    buf: *mut W,
+    /// Capacity in words
+    cap: usize,
+    /// Words read (or queued) by the CPU since start
+    pos: u64,
+    /// Last value returned by `hw_position`, which never goes backwards
+    hw_last: Cell<u64>,
+    _p: PhantomData<(&'d mut AnyNdmaChannel, &'d mut [W])>,
+}
+
+impl<'d, W: Word> RingBuffer<'d, W> {
+    /// Start receiving from the peripheral register `peri_addr` into `buffer`.
+    ///
+    /// The buffer holds at most 0x1FFFF bytes and at least two words.
+    ///
+    /// # Safety
+    /// `peri_addr` must be the data register of the peripheral behind `drq`,
+    /// with DMA requests enabled on that peripheral.
+    pub unsafe fn new_read(
+        ch: Peri<'d, impl NdmaChannel>,
+        drq: NdmaDrqType,
+        peri_addr: u32,
+        buffer: &'d mut [W],
+    ) -> Self {
+        let config = NdmaConfig {
+            src_drq: drq,
+            src_addr_type: AddrType::Io,
+            src_width: W::size().into(),
+            dst_drq: NdmaDrqType::for_addr(buffer.as_ptr() as u32),
+            dst_width: W::size().into(),
+            continuous: true,
+            ..Default::default()
+        };
+        Self::start(ch.number(), Dir::Read, peri_addr, buffer, &config)
+    }
+
+    /// Start sending `buffer` to the peripheral register `peri_addr`.
+    ///
+    /// The current contents of `buffer` go out first (fill it with silence or
+    /// idle words beforehand); [`RingBuffer::write`] queues data after them.
+    ///
+    /// # Safety
+    /// Same as [`RingBuffer::new_read`].
+    pub unsafe fn new_write(
+        ch: Peri<'d, impl NdmaChannel>,
+        drq: NdmaDrqType,
+        peri_addr: u32,
+        buffer: &'d mut [W],
+    ) -> Self {
+        let config = NdmaConfig {
+            src_drq: NdmaDrqType::for_addr(buffer.as_ptr() as u32),
+            src_width: W::size().into(),
+            dst_drq: drq,
+            dst_addr_type: AddrType::Io,
+            dst_width: W::size().into(),
+            continuous: true,
+            ..Default::default()
+        };
+        Self::start(ch.number(), Dir::Write, peri_addr, buffer, &config)
+    }
+
+    unsafe fn start(ch: usize, dir: Dir, peri_addr: u32, buffer: &'d mut [W], config: &NdmaConfig) -> Self {
+        let cap = buffer.len();
+        let byte_count = cap * W::size().bytes();
+        assert!(cap >= 2, "ring buffer needs at least two words");
+        assert!(
+            byte_count <= 0x1_FFFF,
+            "ring buffer exceeds the 0x1FFFF-byte NDMA limit"
+        );
+        let byte_count = byte_count as u32;
+        let mem_addr = buffer.as_mut_ptr() as u32;
+
+        let (src, dst) = match dir {
+            Dir::Read => (peri_addr, mem_addr),
+            Dir::Write => (mem_addr, peri_addr),
+        };
+        RING_CHANNELS.fetch_or(1 << ch, Ordering::AcqRel);
+        ndma_start(ch, src, dst, byte_count, config);
+
+        Self {
+            ch,
+            dir,
+            buf: buffer.as_mut_ptr(),
+            cap,
+            // A write ring starts full with the initial contents
+            pos: if dir == Dir::Write { cap as u64 } else { 0 },
+            hw_last: Cell::new(0),
+            _p: PhantomData,
+        }
+    }
+
+    /// Buffer size in words.
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Words that can be read (read ring) or queued (write ring) right now.
+    pub fn available(&self) -> Result<usize, OverrunError> {
+        let hw = self.hw_position();
+        match self.dir {
+            Dir::Read => {
+                let n = hw - self.pos;
+                if n > self.cap as u64 {
+                    return Err(OverrunError);
+                }
+                Ok(n as usize)
+            }
+            Dir::Write => {
+                if hw > self.pos {
+                    return Err(OverrunError);
+                }
+                Ok((hw + self.cap as u64 - self.pos) as usize)
+            }
+        }
+    }
+
+    /// Drop everything after an overrun and continue from the current DMA position.
+    ///
+    /// A read ring then returns only new data; a write ring treats the whole
+    /// buffer as queued again, so stale data plays once before new writes.
+    pub fn clear(&mut self) {
+        let hw = self.hw_position();
+        self.pos = match self.dir {
+            Dir::Read => hw,
+            Dir::Write => hw + self.cap as u64,
+        };
+    }
+
+    /// Wait for data and copy up to `buf.len()` words, returning the count.
+    ///
+    /// Panics on a ring created with [`RingBuffer::new_write`].
+    pub async fn read(&mut self, buf: &mut [W]) -> Result<usize, OverrunError> {
+        assert!(self.dir == Dir::Read);
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let n = self.wait_available().await?.min(buf.len());
+
+        let start = self.index();
+        let first = n.min(self.cap - start);
+        self.copy_out(start, &mut buf[..first]);
+        self.copy_out(0, &mut buf[first..n]);
+
+        // The DMA may have lapped the data while it was copied
+        if self.hw_position() - self.pos > self.cap as u64 {
+            return Err(OverrunError);
+        }
+        self.pos += n as u64;
+        Ok(n)
+    }
+
+    /// Fill all of `buf`, waiting as needed.
+    pub async fn read_exact(&mut self, buf: &mut [W]) -> Result<(), OverrunError> {
+        let mut done = 0;
+        while done < buf.len() {
+            done += self.read(&mut buf[done..]).await?;
+        }
+        Ok(())
+    }
+
+    /// Wait for free space and queue up to `buf.len()` words, returning the count.
+    ///
+    /// Panics on a ring created with [`RingBuffer::new_read`].
+    pub async fn write(&mut self, buf: &[W]) -> Result<usize, OverrunError> {
+        assert!(self.dir == Dir::Write);
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let n = self.wait_available().await?.min(buf.len());
+
+        let start = self.index();
+        let first = n.min(self.cap - start);
+        self.copy_in(start, &buf[..first]);
+        self.copy_in(0, &buf[first..n]);
+
+        self.pos += n as u64;
+        // The DMA ran past the queued data while it was copied
+        if self.hw_position() > self.pos {
+            return Err(OverrunError);
+        }
+        Ok(n)
+    }
+
+    /// Queue all of `buf`, waiting as needed.
+    pub async fn write_all(&mut self, buf: &[W]) -> Result<(), OverrunError> {
+        let mut done = 0;
+        while done < buf.len() {
+            done += self.write(&buf[done..]).await?;
+        }
+        Ok(())
+    }
+
+    /// Wait until [`RingBuffer::available`] is non-zero.
+    async fn wait_available(&self) -> Result<usize, OverrunError> {
+        poll_fn(|cx| {
+            CHANNEL_STATE[self.ch].waker.register(cx.waker());
+            match self.available() {
+                Ok(0) => Poll::Pending,
+                r => Poll::Ready(r),
+            }
+        })
+        .await
+    }
+
+    /// Buffer index of the next word read or written by the CPU.
+    fn index(&self) -> usize {
+        (self.pos % self.cap as u64) as usize
+    }
+
+    fn copy_out(&self, start: usize, dst: &mut [W]) {
+        if dst.is_empty() {
+            return;
+        }
+        let src = unsafe { self.buf.add(start) };
+        if is_cached_addr(src as u32) {
+            arm9::asm::invalidate_dcache_range(src as u32, (dst.len() * W::size().bytes()) as u32);
+        }
+        fence(Ordering::SeqCst);
+        for (i, w) in dst.iter_mut().enumerate() {
+            *w = unsafe { src.add(i).read_volatile() };
+        }
+    }
+
+    fn copy_in(&self, start: usize, src: &[W]) {
+        if src.is_empty() {
+            return;
+        }
+        let dst = unsafe { self.buf.add(start) };
+        for (i, w) in src.iter().enumerate() {
+            unsafe { dst.add(i).write_volatile(*w) };
+        }
+        if is_cached_addr(dst as u32) {
+            arm9::asm::clean_dcache_range(dst as u32, (src.len() * W::size().bytes()) as u32);
+        }
+        fence(Ordering::SeqCst);
+    }
+
+    /// Words moved by the DMA since start.
+    ///
+    /// A pass that wrapped before its full interrupt was handled is still
+    /// pending in DMA_INT_STA; the snapshot is retried until the lap count
+    /// and that bit are stable around the NDMA_BYTE_CNT read.
+    ///
+    /// NDMA_BYTE_CNT reads 0 between the full event and the reload. That
+    /// pass is already in the lap count (or the pending bit), so 0 counts as
+    /// the start of the next pass rather than adding a whole extra buffer.
+    /// The result is kept monotonic in case the count reads 0 just before
+    /// the full bit is set.
+    fn hw_position(&self) -> u64 {
+        let dma = unsafe { &*pac::Dma::ptr() };
+        let state = &CHANNEL_STATE[self.ch];
+        let full_bit = 1u32 << (self.ch * 2 + 1);
+        let len = (self.cap * W::size().bytes()) as u64;
+
+        loop {
+            let laps = state.laps.load(Ordering::Acquire);
+            let pending = dma.dma_int_sta().read().bits() & full_bit;
+            let left = unsafe { core::ptr::read_volatile(ndma_byte_cnt_addr(self.ch)) } & NDMA_BYTE_CNT_MASK;
+            let pending_after = dma.dma_int_sta().read().bits() & full_bit;
+
+            if laps == state.laps.load(Ordering::Acquire) && pending == pending_after {
+                let laps = laps as u64 + (pending != 0) as u64;
+                let done = match left as u64 {
+                    0 => 0,
+                    left => len - left.min(len),
+                };
+                let pos = ((laps * len + done) / W::size().bytes() as u64).max(self.hw_last.get());
+                self.hw_last.set(pos);
+                return pos;
+            }
+        }
+    }
+}
+
+impl<'d, W: Word> Drop for RingBuffer<'d, W> {
+    fn drop(&mut self) {
+        unsafe { ndma_stop(self.ch) };
+        RING_CHANNELS.fetch_and(!(1 << self.ch), Ordering::AcqRel);
+        fence(Ordering::SeqCst);
+    }
+}
@@ -61,8 +61,20 @@ pub mod dma;
 
 pub mod spi;
 
+pub mod pwm;
+
 pub mod display;
 
+pub mod usb_otg;
+
+pub mod tvd;
+
+pub mod csi;
+
+pub mod ve;
+
+pub mod deinterlace;
+
 // This must go last, so that it sees all the impl_foo! macros defined earlier.
 pub(crate) mod _generated {
     #![allow(dead_code)]
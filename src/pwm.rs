@@ -0,0 +1,257 @@
+//! PWM driver for the two F1C100S PWM channels.
+//!
+//! Both channels are clocked from the 24MHz oscillator through a prescaler and
+//! share one control register:
+//! - PWM_CTRL_REG: channel 0 fields at bit 0, channel 1 at bit 15
+//!   ([3:0] prescaler, [4] enable, [5] active state, [6] clock gating),
+//!   [28]/[29] channel 0/1 period register busy
+//! - PWM_CHn_PERIOD: [31:16] entire cycles - 1, [15:0] active cycles
+//!
+//! Pins: PWM0 on PE12 (function 4), PWM1 on PE6 (function 5).
+
+use core::marker::PhantomData;
+
+use crate::gpio::{self, Pull, SealedPin};
+use crate::time::Hertz;
+use crate::Peri;
+
+const PWM_BASE: usize = 0x01C2_1000;
+
+// Register offsets
+const PWM_CTRL: usize = 0x00;
+
+// Per-channel PWM_CTRL fields, before shifting by `SealedInstance::SHIFT`
+const CTRL_EN: u32 = 1 << 4;
+const CTRL_ACT_STA: u32 = 1 << 5;
+const CTRL_SCLK_GATING: u32 = 1 << 6;
+const CTRL_FIELD_MASK: u32 = 0x3FF;
+
+/// Oscillator feeding the prescalers
+const PWM_CLK_HZ: u32 = 24_000_000;
+
+/// (PWM_CTRL prescaler code, divider), smallest divider first
+const PRESCALERS: [(u32, u32); 11] = [
+    (0xF, 1),
+    (0x0, 120),
+    (0x1, 180),
+    (0x2, 240),
+    (0x3, 360),
+    (0x4, 480),
+    (0x8, 12_000),
+    (0x9, 24_000),
+    (0xA, 36_000),
+    (0xB, 48_000),
+    (0xC, 72_000),
+];
+
+#[inline(always)]
+fn reg(offset: usize) -> *mut u32 {
+    (PWM_BASE + offset) as *mut u32
+}
+
+#[inline(always)]
+unsafe fn write(offset: usize, val: u32) {
+    reg(offset).write_volatile(val);
+}
+
+#[inline(always)]
+unsafe fn read(offset: usize) -> u32 {
+    reg(offset).read_volatile()
+}
+
+/// PWM configuration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Config {
+    /// Output frequency
+    pub frequency: Hertz,
+    /// Output is low during the active part of the period
+    pub inverted: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            frequency: Hertz(20_000),
+            inverted: false,
+        }
+    }
+}
+
+/// Single PWM channel output.
+///
+/// The channel is type-erased so the output can be stored next to other
+/// drivers, e.g. as a [`crate::display::Backlight`].
+pub struct SimplePwm<'d> {
+    /// Bit position of the channel fields in PWM_CTRL
+    shift: u32,
+    /// PWM_CTRL period register busy flag
+    period_busy: u32,
+    /// Period register offset
+    period_reg: usize,
+    /// Entire cycles per period
+    period: u16,
+    duty: u16,
+    _peri: PhantomData<&'d mut ()>,
+}
+
+impl<'d> SimplePwm<'d> {
+    /// Configure the channel and its pin. The output starts disabled with 0% duty.
+    pub fn new<T: Instance>(_peri: Peri<'d, T>, pin: Peri<'d, impl OutPin<T>>, config: Config) -> Self {
+        pin.set_mode(T::PIN_MODE);
+        pin.set_pull(Pull::None);
+
+        let mut this = Self {
+            shift: T::SHIFT,
+            period_busy: T::PERIOD_BUSY,
+            period_reg: T::PERIOD,
+            period: 1,
+            duty: 0,
+            _peri: PhantomData,
+        };
+        this.set_config(&config);
+        this
+    }
+
+    /// Change frequency and polarity. The duty cycle ratio is kept.
+    pub fn set_config(&mut self, config: &Config) {
+        let ticks = PWM_CLK_HZ / config.frequency.0.max(1);
+        let (code, div) = PRESCALERS
+            .iter()
+            .copied()
+            .find(|&(_, div)| ticks / div <= 0x1_0000)
+            .unwrap_or(PRESCALERS[PRESCALERS.len() - 1]);
+        let period = (ticks / div).clamp(1, 0x1_0000);
+
+        let ratio = self.duty as u32 * 0xFFFF / self.period as u32;
+        // 0x1_0000 entire cycles can't be represented as a u16 duty maximum
+        self.period = period.min(0xFFFF) as u16;
+        self.duty = (ratio * self.period as u32 / 0xFFFF) as u16;
+
+        let mut field = code | CTRL_SCLK_GATING;
+        if !config.inverted {
+            field |= CTRL_ACT_STA;
+        }
+        unsafe {
+            let ctrl = read(PWM_CTRL);
+            let enabled = ctrl & (CTRL_EN << self.shift);
+            // Prescaler may only change with the clock gated
+            write(PWM_CTRL, ctrl & !(CTRL_SCLK_GATING << self.shift));
+            let ctrl = read(PWM_CTRL) & !(CTRL_FIELD_MASK << self.shift);
+            write(PWM_CTRL, ctrl | (field << self.shift) | enabled);
+        }
+        self.write_period();
+    }
+
+    /// Start driving the output.
+    pub fn enable(&mut self) {
+        unsafe { write(PWM_CTRL, read(PWM_CTRL) | (CTRL_EN << self.shift)) };
+    }
+
+    /// Stop the output; the pin goes to its inactive level.
+    pub fn disable(&mut self) {
+        unsafe { write(PWM_CTRL, read(PWM_CTRL) & !(CTRL_EN << self.shift)) };
+    }
+
+    /// Whether the output is enabled.
+    pub fn is_enabled(&self) -> bool {
+        unsafe { read(PWM_CTRL) & (CTRL_EN << self.shift) != 0 }
+    }
+
+    /// Duty value meaning 100%.
+    pub fn max_duty(&self) -> u16 {
+        self.period
+    }
+
+    /// Current duty value.
+    pub fn duty(&self) -> u16 {
+        self.duty
+    }
+
+    /// Set the active part of the period, `0..=max_duty()`.
+    pub fn set_duty(&mut self, duty: u16) {
+        self.duty = duty.min(self.period);
+        self.write_period();
+    }
+
+    fn write_period(&self) {
+        unsafe {
+            // Wait for the previous period write to be taken over
+            while read(PWM_CTRL) & self.period_busy != 0 {}
+            write(self.period_reg, ((self.period as u32 - 1) << 16) | self.duty as u32);
+        }
+    }
+}
+
+impl<'d> Drop for SimplePwm<'d> {
+    fn drop(&mut self) {
+        unsafe { write(PWM_CTRL, read(PWM_CTRL) & !((CTRL_EN | CTRL_SCLK_GATING) << self.shift)) };
+    }
+}
+
+impl<'d> embedded_hal::pwm::ErrorType for SimplePwm<'d> {
+    type Error = core::convert::Infallible;
+}
+
+impl<'d> embedded_hal::pwm::SetDutyCycle for SimplePwm<'d> {
+    fn max_duty_cycle(&self) -> u16 {
+        self.max_duty()
+    }
+
+    fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
+        self.set_duty(duty);
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Instance trait
+// ============================================================================
+
+trait SealedInstance {
+    /// Bit position of the channel fields in PWM_CTRL
+    const SHIFT: u32;
+    /// PWM_CTRL period register busy flag
+    const PERIOD_BUSY: u32;
+    /// Period register offset
+    const PERIOD: usize;
+    /// Pin function of the channel output
+    const PIN_MODE: gpio::PinMode;
+}
+
+/// PWM channel instance.
+#[allow(private_bounds)]
+pub trait Instance: SealedInstance + embassy_hal_internal::PeripheralType + 'static {}
+
+impl SealedInstance for crate::peripherals::PWM0 {
+    const SHIFT: u32 = 0;
+    const PERIOD_BUSY: u32 = 1 << 28;
+    const PERIOD: usize = 0x04;
+    const PIN_MODE: gpio::PinMode = gpio::PinMode::Func4;
+}
+impl Instance for crate::peripherals::PWM0 {}
+
+impl SealedInstance for crate::peripherals::PWM1 {
+    const SHIFT: u32 = 15;
+    const PERIOD_BUSY: u32 = 1 << 29;
+    const PERIOD: usize = 0x08;
+    const PIN_MODE: gpio::PinMode = gpio::PinMode::Func5;
+}
+impl Instance for crate::peripherals::PWM1 {}
+
+// ============================================================================
+// Pin traits
+// ============================================================================
+
+mod sealed {
+    pub trait OutPin<T> {}
+}
+
+/// Output pin of a PWM channel.
+#[allow(private_bounds)]
+pub trait OutPin<T: Instance>: sealed::OutPin<T> + gpio::Pin {}
+
+impl sealed::OutPin<crate::peripherals::PWM0> for crate::peripherals::PE12 {}
+impl OutPin<crate::peripherals::PWM0> for crate::peripherals::PE12 {}
+impl sealed::OutPin<crate::peripherals::PWM1> for crate::peripherals::PE6 {}
+impl OutPin<crate::peripherals::PWM1> for crate::peripherals::PE6 {}
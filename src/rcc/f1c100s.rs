@@ -118,7 +118,7 @@ pub enum PllVideoMode {
 ///
 /// Integer mode: output = 24MHz * N / M (30~600MHz)
 /// Fractional mode: 270MHz or 297MHz
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct PllVideo {
     pub mode: PllVideoMode,
 }
@@ -142,6 +142,14 @@ impl PllVideo {
             mode: PllVideoMode::Fractional { out_297mhz: false },
         }
     }
+
+    pub const fn freq_hz(&self) -> u32 {
+        match self.mode {
+            PllVideoMode::Integer { n, m } => (24_000_000u64 * n as u64 / m as u64) as u32,
+            PllVideoMode::Fractional { out_297mhz: true } => 297_000_000,
+            PllVideoMode::Fractional { out_297mhz: false } => 270_000_000,
+        }
+    }
 }
 
 /// AHB clock source
@@ -280,6 +288,54 @@ fn wait_pll_video_stable(ccu: &pac::ccu::RegisterBlock) {
     }
 }
 
+/// Program PLL_VIDEO and wait for it to lock.
+pub(crate) unsafe fn set_pll_video(pll_video: &PllVideo) {
+    let ccu = &*pac::Ccu::ptr();
+    match pll_video.mode {
+        PllVideoMode::Integer { n, m } => {
+            ccu.pll_video_ctrl().write(|w| {
+                w.pll_en().set_bit();
+                w.pll_mode_sel().set_bit(); // integer mode
+                w.pll_factor_n().bits(n - 1);
+                w.pll_prediv_m().bits(m - 1)
+            });
+        }
+        PllVideoMode::Fractional { out_297mhz } => {
+            ccu.pll_video_ctrl().write(|w| {
+                w.pll_en().set_bit();
+                w.pll_mode_sel().clear_bit(); // fractional mode
+                w.frac_clk_out().bit(out_297mhz);
+                w.pll_prediv_m().bits(0) // M must be 0 in fractional mode
+            });
+        }
+    }
+    sdelay(100);
+    wait_pll_video_stable(ccu);
+}
+
+/// Read the current PLL_VIDEO setting back from the CCU.
+pub fn pll_video() -> PllVideo {
+    let ccu = unsafe { &*pac::Ccu::ptr() };
+    let pll = ccu.pll_video_ctrl().read();
+    let mode = if pll.pll_mode_sel().bit_is_set() {
+        PllVideoMode::Integer {
+            n: pll.pll_factor_n().bits() + 1,
+            m: pll.pll_prediv_m().bits() + 1,
+        }
+    } else {
+        PllVideoMode::Fractional {
+            out_297mhz: pll.frac_clk_out().bit_is_set(),
+        }
+    };
+    PllVideo { mode }
+}
+
+/// Whether PLL_VIDEO is enabled; [`pll_video`] reads the factors either way.
+pub fn pll_video_enabled() -> bool {
+    let ccu = unsafe { &*pac::Ccu::ptr() };
+    ccu.pll_video_ctrl().read().pll_en().bit_is_set()
+}
+
 /// Initialize the F1C100S clock tree.
 pub(crate) unsafe fn init(config: &Config) {
     let ccu = &*pac::Ccu::ptr();
@@ -294,26 +350,7 @@ pub(crate) unsafe fn init(config: &Config) {
 
     // 3. Configure PLL_VIDEO
     if let Some(pll_video) = &config.pll_video {
-        match pll_video.mode {
-            PllVideoMode::Integer { n, m } => {
-                ccu.pll_video_ctrl().write(|w| {
-                    w.pll_en().set_bit();
-                    w.pll_mode_sel().set_bit(); // integer mode
-                    w.pll_factor_n().bits(n - 1);
-                    w.pll_prediv_m().bits(m - 1)
-                });
-            }
-            PllVideoMode::Fractional { out_297mhz } => {
-                ccu.pll_video_ctrl().write(|w| {
-                    w.pll_en().set_bit();
-                    w.pll_mode_sel().clear_bit(); // fractional mode
-                    w.frac_clk_out().bit(out_297mhz);
-                    w.pll_prediv_m().bits(0) // M must be 0 in fractional mode
-                });
-            }
-        }
-        sdelay(100);
-        wait_pll_video_stable(ccu);
+        set_pll_video(pll_video);
     }
 
     // 4. Configure PLL_PERIPH
@@ -380,3 +417,46 @@ pub(crate) unsafe fn init(config: &Config) {
     // Update global clock tracking
     super::update_clocks(config);
 }
+
+// ============================================================================
+// Raw CCU access for the multimedia blocks (TVE, TVD, CSI, VE, de-interlacer), whose
+// clock, gating and reset bits share registers with the typed accessors.
+// ============================================================================
+pub(crate) const CCU_PLL_VE: usize = 0x018;
+pub(crate) const CCU_BUS_CLK_GATING1: usize = 0x064;
+pub(crate) const CCU_DRAM_GATING: usize = 0x100;
+pub(crate) const CCU_DEINTERLACE_CLK: usize = 0x11C;
+pub(crate) const CCU_TVE_CLK: usize = 0x120;
+pub(crate) const CCU_TVD_CLK: usize = 0x124;
+pub(crate) const CCU_VE_CLK: usize = 0x13C;
+pub(crate) const CCU_BUS_SOFT_RST1: usize = 0x2C4;
+
+#[inline]
+fn ccu_reg(offset: usize) -> *mut u32 {
+    (pac::Ccu::ptr() as usize + offset) as *mut u32
+}
+
+#[inline]
+pub(crate) unsafe fn ccu_read(offset: usize) -> u32 {
+    ccu_reg(offset).read_volatile()
+}
+
+/// Write a whole CCU register (module clock registers).
+#[inline]
+pub(crate) unsafe fn ccu_write(offset: usize, val: u32) {
+    ccu_reg(offset).write_volatile(val);
+}
+
+/// Set `bits` in a CCU register.
+#[inline]
+pub(crate) unsafe fn ccu_set(offset: usize, bits: u32) {
+    let r = ccu_reg(offset);
+    r.write_volatile(r.read_volatile() | bits);
+}
+
+/// Clear `bits` in a CCU register.
+#[inline]
+pub(crate) unsafe fn ccu_clear(offset: usize, bits: u32) {
+    let r = ccu_reg(offset);
+    r.write_volatile(r.read_volatile() & !bits);
+}
@@ -14,8 +14,10 @@
 
 use core::marker::PhantomData;
 
+use embassy_hal_internal::drop::OnDrop;
 use embedded_hal::spi::{Mode, Phase, Polarity, MODE_0};
 
+use crate::dma::{NdmaChannel, SealedChannel};
 use crate::gpio::{self, PinMode, Pull};
 use crate::{pac, rcc, Peri};
 
@@ -461,8 +463,8 @@ impl<'d, T: Instance> Spi<'d, T> {
 
     /// Async DMA write: send `data` via DMA, discard received bytes.
     ///
-    /// Uses NDMA channel `tx_ch` (0..3). Caller must ensure the channel is free.
-    pub async fn dma_write(&mut self, tx_ch: usize, data: &[u8]) -> Result<(), Error> {
+    /// Uses NDMA channel `tx_ch`, borrowed for the duration of the transfer.
+    pub async fn dma_write(&mut self, tx_ch: Peri<'_, impl NdmaChannel>, data: &[u8]) -> Result<(), Error> {
         use crate::dma::{self, AddrType, BurstLen, DataWidth, NdmaConfig, NdmaDrqType};
 
         let regs = Self::regs();
@@ -524,8 +526,8 @@ impl<'d, T: Instance> Spi<'d, T> {
 
     /// Async DMA read: receive `data.len()` bytes via DMA, sending dummy 0x00.
     ///
-    /// Uses NDMA channel `rx_ch` (0..3). Caller must ensure the channel is free.
-    pub async fn dma_read(&mut self, rx_ch: usize, data: &mut [u8]) -> Result<(), Error> {
+    /// Uses NDMA channel `rx_ch`, borrowed for the duration of the transfer.
+    pub async fn dma_read(&mut self, rx_ch: Peri<'_, impl NdmaChannel>, data: &mut [u8]) -> Result<(), Error> {
         use crate::dma::{self, AddrType, BurstLen, DataWidth, NdmaConfig, NdmaDrqType};
 
         let regs = Self::regs();
@@ -589,16 +591,33 @@ impl<'d, T: Instance> Spi<'d, T> {
     ///
     /// TX phase is blocking (command bytes are small).
     /// RX phase uses NDMA with polling (no interrupt/async dependency).
-    pub fn dma_transfer_blocking(&mut self, rx_ch: usize, tx_buf: &[u8], rx_buf: &mut [u8]) -> Result<(), Error> {
+    pub fn dma_transfer_blocking(
+        &mut self,
+        rx_ch: Peri<'_, impl NdmaChannel>,
+        tx_buf: &[u8],
+        rx_buf: &mut [u8],
+    ) -> Result<(), Error> {
         let regs = Self::regs();
 
         if rx_buf.is_empty() {
             return self.blocking_write(tx_buf);
         }
 
-        // Disable DMA IRQ in INTC — we're polling, don't want the IRQ handler
-        // interfering with DMA_INT_STA reads or causing unexpected bus activity.
-        crate::intc::disable_irq(crate::interrupt::Interrupt::DMA.number());
+        // Disable DMA IRQ in INTC — we're polling, don't want unexpected bus
+        // activity from the IRQ handler. Other channels' events stay pending
+        // and are handled once it is re-enabled, on every return path. A
+        // running RingBuffer needs the IRQ to count its passes, so then it
+        // stays enabled (the handler leaves our channel's bits alone).
+        let irq = crate::interrupt::Interrupt::DMA.number();
+        let mask_irq = crate::dma::irq_maskable();
+        if mask_irq {
+            crate::intc::disable_irq(irq);
+        }
+        let _irq = OnDrop::new(|| {
+            if mask_irq {
+                crate::intc::enable_irq(irq);
+            }
+        });
 
         // === Phase 1: blocking TX (command bytes) ===
         self.reset_fifos();
@@ -633,10 +652,10 @@ impl<'d, T: Instance> Spi<'d, T> {
         let dst_addr = rx_buf.as_mut_ptr() as u32;
         let rxd_addr = T::regs() as u32 + 0x300;
         let len = rx_buf.len() as u32;
+        let ch = rx_ch.number();
 
         // === Critical section: no UART, no function calls, just raw register writes ===
         unsafe {
-            let ch = rx_ch;
             let dma_base: usize = 0x01C0_2000;
             let cfg_reg = (dma_base + 0x100 + ch * 0x20) as *mut u32;
             let src_reg = (dma_base + 0x104 + ch * 0x20) as *mut u32;
@@ -645,16 +664,18 @@ impl<'d, T: Instance> Spi<'d, T> {
             let int_sta = (dma_base + 0x04) as *mut u32;
             let int_ctrl = (dma_base + 0x00) as *mut u32;
 
+            let ch_bits = 0x3u32 << (ch * 2);
+
             // 1. Stop channel
             core::ptr::write_volatile(cfg_reg, 0);
-            // 2. Clear all pending
-            core::ptr::write_volatile(int_sta, 0x00FF_00FF);
+            // 2. Clear this channel's pending flags
+            core::ptr::write_volatile(int_sta, ch_bits);
             // 3. Write src/dst/cnt
             core::ptr::write_volatile(src_reg, rxd_addr);
             core::ptr::write_volatile(dst_reg, dst_addr);
             core::ptr::write_volatile(cnt_reg, len);
-            // 4. Disable DMA interrupts
-            core::ptr::write_volatile(int_ctrl, 0);
+            // 4. Disable this channel's interrupts
+            core::ptr::write_volatile(int_ctrl, core::ptr::read_volatile(int_ctrl) & !ch_bits);
 
             // CFG value — SPI RX is 8-bit, so both src and dst use 8-bit width.
             // Using 32-bit dst width causes byte replication/misalignment because
@@ -699,7 +720,7 @@ impl<'d, T: Instance> Spi<'d, T> {
         // Poll DMA completion via raw register read
         unsafe {
             let int_sta_reg = 0x01C0_2004 as *const u32;
-            let full_bit = 1u32 << (rx_ch * 2 + 1);
+            let full_bit = 1u32 << (ch * 2 + 1);
             let mut loops = 0u32;
             loop {
                 let sta = core::ptr::read_volatile(int_sta_reg);
@@ -741,9 +762,6 @@ impl<'d, T: Instance> Spi<'d, T> {
         // Disable SPI DMA requests
         self.disable_dma();
 
-        // Re-enable DMA IRQ
-        crate::intc::enable_irq(crate::interrupt::Interrupt::DMA.number());
-
         Ok(())
     }
 }
@@ -0,0 +1,456 @@
+//! TV decoder (TVD): composite video capture for F1C100S/F1C200S.
+//!
+//! The TVD digitizes a CVBS input (TVIN0), detects NTSC or PAL, and writes
+//! decoded frames to SDRAM as NV12 (Y plane + interleaved CbCr plane). Both
+//! fields are woven line-by-line into one progressive frame buffer.
+//!
+//! Layout:
+//! - TVD_TOP (base + 0x000): ADC/AFE power and TVD0 mapping
+//! - TVD0 (base + 0x1000): decoder core, write-back DMA and interrupts
+//!
+//! Frames are captured into a pair of buffers given to [`Tvd::start`]. The
+//! interrupt handler re-queues each buffer as soon as its frame completes, so
+//! capture alternates between them without waiting for the task.
+//! [`Tvd::wait_frame`] returns the index of the buffer that was just
+//! completed; the hardware continues into the other one. Losing the input
+//! signal is reported as [`Error::NoSignal`]; short H/V lock drops on a noisy
+//! input are not, use [`Tvd::status`] for those.
+//!
+//! The TVD registers are not in the PAC, so we use raw pointer access.
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::Poll;
+
+use embassy_sync::waitqueue::AtomicWaker;
+use portable_atomic::{AtomicU32, Ordering};
+
+use crate::interrupt::typelevel::Handler;
+use crate::interrupt::Interrupt;
+use crate::rcc::{ccu_clear, ccu_set, ccu_write, CCU_BUS_CLK_GATING1, CCU_BUS_SOFT_RST1, CCU_DRAM_GATING, CCU_TVD_CLK};
+use crate::{intc, peripherals, Peri};
+
+// ============================================================================
+// TVD base and register offsets (not in PAC)
+// ============================================================================
+const TVD_BASE: usize = 0x01C0_B000;
+
+// TVD_TOP
+const TVD_TOP_MAP: usize = 0x000;
+const TVD_TOP_CTL: usize = 0x008;
+const TVD_ADC_CTL: usize = 0x020;
+const TVD_ADC_CFG: usize = 0x024;
+
+// TVD0
+const TVD0: usize = 0x1000;
+const TVD_EN: usize = TVD0 + 0x000;
+const TVD_MODE: usize = TVD0 + 0x004;
+const TVD_WB1: usize = TVD0 + 0x080;
+const TVD_WB2: usize = TVD0 + 0x084;
+const TVD_WB3: usize = TVD0 + 0x088;
+const TVD_WB4: usize = TVD0 + 0x08C;
+const TVD_IRQ_CTL: usize = TVD0 + 0x0E0;
+const TVD_IRQ_STATUS: usize = TVD0 + 0x0F0;
+const TVD_STATUS4: usize = TVD0 + 0x18C;
+
+// TOP bits
+const TOP_MAP_TVD0: u32 = 1 << 0;
+const TOP_CTL_CLK_EN: u32 = 1 << 0;
+const ADC_CTL_ADC_EN: u32 = 1 << 0;
+const ADC_CTL_AFE_EN: u32 = 1 << 1;
+/// Low-pass filter and gain defaults for the CVBS ADC
+const ADC_CFG_DEFAULT: u32 = 0x0000_0A0E;
+
+// EN / MODE bits
+const EN_ENABLE: u32 = 1 << 0;
+const MODE_NTSC: u32 = 0;
+const MODE_PAL: u32 = 1;
+
+// WB1 bits. The format fields are left at their reset value of 0: both
+// fields woven into one frame, semi-planar NV12 output with Cb first.
+const WB1_EN: u32 = 1 << 0;
+/// Latch WB3/WB4 at the next frame boundary (immediately while stopped)
+const WB1_ADDR_UPDATE: u32 = 1 << 28;
+
+// IRQ bits
+const IRQ_FRAME_END: u32 = 1 << 24;
+const IRQ_FIFO_OVERFLOW: u32 = 1 << 4;
+const IRQ_LOCK_CHANGE: u32 = 1 << 16;
+
+// STATUS4 bits
+const STATUS4_NO_SIGNAL: u32 = 1 << 0;
+const STATUS4_H_LOCKED: u32 = 1 << 1;
+const STATUS4_V_LOCKED: u32 = 1 << 2;
+const STATUS4_625_LINES: u32 = 1 << 18;
+
+// CCU bits (raw, TVD bits are shared registers)
+const BUS1_TVD: u32 = 1 << 9;
+const DRAM_GATING_TVD: u32 = 1 << 3;
+/// SCLK gating, PLL_VIDEO(1X) / 11 = 27MHz
+const TVD_CLK_27M: u32 = (1 << 31) | 10;
+
+#[inline]
+fn reg(offset: usize) -> *mut u32 {
+    (TVD_BASE + offset) as *mut u32
+}
+
+#[inline]
+unsafe fn write(offset: usize, val: u32) {
+    reg(offset).write_volatile(val);
+}
+
+#[inline]
+unsafe fn read(offset: usize) -> u32 {
+    reg(offset).read_volatile()
+}
+
+static WAKER: AtomicWaker = AtomicWaker::new();
+static FRAME_COUNT: AtomicU32 = AtomicU32::new(0);
+static OVERFLOW_COUNT: AtomicU32 = AtomicU32::new(0);
+static SIGNAL_LOST_COUNT: AtomicU32 = AtomicU32::new(0);
+/// Y and CbCr addresses of the two capture buffers
+static BUFFERS: [[AtomicU32; 2]; 2] = [
+    [AtomicU32::new(0), AtomicU32::new(0)],
+    [AtomicU32::new(0), AtomicU32::new(0)],
+];
+/// Buffer the hardware is currently writing
+static WRITING: AtomicU32 = AtomicU32::new(0);
+/// Buffer holding the most recently completed frame
+static LAST_DONE: AtomicU32 = AtomicU32::new(0);
+
+/// TVD IRQ handler — re-queues the completed buffer, counts frames,
+/// overflows and signal loss, wakes the waiter.
+fn tvd_irq_handler() {
+    let status = unsafe { read(TVD_IRQ_STATUS) };
+    unsafe { write(TVD_IRQ_STATUS, status) };
+
+    if status & IRQ_FRAME_END != 0 {
+        // The other buffer was latched for the frame now starting; queue
+        // the completed one for the frame after it.
+        let done = WRITING.load(Ordering::Relaxed);
+        WRITING.store(done ^ 1, Ordering::Relaxed);
+        unsafe { queue_buffer(done as usize) };
+        LAST_DONE.store(done, Ordering::Relaxed);
+        FRAME_COUNT.fetch_add(1, Ordering::Release);
+    }
+    if status & IRQ_FIFO_OVERFLOW != 0 {
+        OVERFLOW_COUNT.fetch_add(1, Ordering::Release);
+    }
+    if status & IRQ_LOCK_CHANGE != 0 && unsafe { read(TVD_STATUS4) } & STATUS4_NO_SIGNAL != 0 {
+        SIGNAL_LOST_COUNT.fetch_add(1, Ordering::Release);
+    }
+    if status & (IRQ_FRAME_END | IRQ_FIFO_OVERFLOW | IRQ_LOCK_CHANGE) != 0 {
+        WAKER.wake();
+    }
+}
+
+/// Point the write-back at buffer `idx`, latched at the next frame boundary.
+unsafe fn queue_buffer(idx: usize) {
+    write(TVD_WB3, BUFFERS[idx][0].load(Ordering::Relaxed));
+    write(TVD_WB4, BUFFERS[idx][1].load(Ordering::Relaxed));
+    write(TVD_WB1, read(TVD_WB1) | WB1_ADDR_UPDATE);
+}
+
+/// Interrupt handler for TVD frame and lock events.
+///
+/// The handler is registered automatically by [`Tvd::new`], so
+/// `bind_interrupts!` is optional. It's provided for consistency with
+/// the embassy pattern:
+/// ```ignore
+/// bind_interrupts!(struct Irqs {
+///     TVD => tvd::InterruptHandler;
+/// });
+/// ```
+pub struct InterruptHandler {
+    _private: (),
+}
+
+impl Handler<crate::interrupt::typelevel::TVD> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        tvd_irq_handler();
+    }
+}
+
+/// Analog video standard.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Standard {
+    /// 525 lines, 720x480
+    Ntsc,
+    /// 625 lines, 720x576
+    Pal,
+}
+
+impl Standard {
+    /// Captured frame width in pixels.
+    pub const fn width(self) -> u16 {
+        720
+    }
+
+    /// Captured frame height in pixels (both fields).
+    pub const fn height(self) -> u16 {
+        match self {
+            Standard::Ntsc => 480,
+            Standard::Pal => 576,
+        }
+    }
+
+    /// Size in bytes of one NV12 frame (Y plane + CbCr plane).
+    pub const fn frame_size(self) -> usize {
+        self.width() as usize * self.height() as usize * 3 / 2
+    }
+}
+
+/// Decoder lock status.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LockStatus {
+    /// A video signal is present on the input
+    pub signal: bool,
+    pub h_locked: bool,
+    pub v_locked: bool,
+    /// Standard detected from the line count, valid once locked
+    pub standard: Option<Standard>,
+}
+
+impl LockStatus {
+    /// Horizontally and vertically locked to a signal.
+    pub fn locked(&self) -> bool {
+        self.signal && self.h_locked && self.v_locked
+    }
+}
+
+/// TVD capture error
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The input signal was lost while waiting for a frame
+    NoSignal,
+    /// Write-back FIFO overflowed (SDRAM bandwidth); the frame is corrupt
+    Overflow,
+}
+
+/// TVD configuration
+#[non_exhaustive]
+#[derive(Copy, Clone)]
+pub struct Config {
+    /// Fixed standard, or `None` to follow the detected line count
+    pub standard: Option<Standard>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { standard: None }
+    }
+}
+
+/// Composite video capture driver.
+pub struct Tvd<'d> {
+    config: Config,
+    standard: Standard,
+    frames_seen: u32,
+    overflows_seen: u32,
+    signal_lost_seen: u32,
+    _peri: PhantomData<&'d mut peripherals::TVD>,
+}
+
+impl<'d> Tvd<'d> {
+    /// Power up the TVD and its ADC.
+    ///
+    /// PLL_VIDEO must run at 297MHz (fractional mode) for the 27MHz TVD clock.
+    pub fn new(_peri: Peri<'d, peripherals::TVD>, config: Config) -> Self {
+        unsafe {
+            // Clock, bus gating, DRAM gating and reset de-assert
+            ccu_write(CCU_TVD_CLK, TVD_CLK_27M);
+            ccu_set(CCU_BUS_CLK_GATING1, BUS1_TVD);
+            ccu_set(CCU_DRAM_GATING, DRAM_GATING_TVD);
+            ccu_set(CCU_BUS_SOFT_RST1, BUS1_TVD);
+
+            write(TVD_TOP_MAP, TOP_MAP_TVD0);
+            write(TVD_TOP_CTL, TOP_CTL_CLK_EN);
+            write(TVD_ADC_CFG, ADC_CFG_DEFAULT);
+            write(TVD_ADC_CTL, ADC_CTL_ADC_EN | ADC_CTL_AFE_EN);
+
+            write(TVD_IRQ_CTL, 0);
+            write(TVD_IRQ_STATUS, 0xFFFF_FFFF);
+        }
+
+        let standard = config.standard.unwrap_or(Standard::Ntsc);
+        unsafe { Self::set_mode(standard) };
+
+        intc::set_irq_handler(Interrupt::TVD.number(), tvd_irq_handler);
+        intc::enable_irq(Interrupt::TVD.number());
+
+        Self {
+            config,
+            standard,
+            frames_seen: 0,
+            overflows_seen: 0,
+            signal_lost_seen: 0,
+            _peri: PhantomData,
+        }
+    }
+
+    /// Current lock status of the input.
+    pub fn status(&self) -> LockStatus {
+        let sta = unsafe { read(TVD_STATUS4) };
+        let signal = sta & STATUS4_NO_SIGNAL == 0;
+        let h_locked = sta & STATUS4_H_LOCKED != 0;
+        let v_locked = sta & STATUS4_V_LOCKED != 0;
+        let standard = if signal && v_locked {
+            Some(if sta & STATUS4_625_LINES != 0 {
+                Standard::Pal
+            } else {
+                Standard::Ntsc
+            })
+        } else {
+            None
+        };
+        LockStatus {
+            signal,
+            h_locked,
+            v_locked,
+            standard,
+        }
+    }
+
+    /// Standard the decoder is currently configured for.
+    pub fn standard(&self) -> Standard {
+        self.standard
+    }
+
+    /// Wait until the decoder locks, and switch to the detected standard
+    /// when auto-detection is enabled. Returns the standard in use.
+    pub async fn wait_lock(&mut self) -> Standard {
+        unsafe { write(TVD_IRQ_CTL, read(TVD_IRQ_CTL) | IRQ_LOCK_CHANGE) };
+        let status = poll_fn(|cx| {
+            WAKER.register(cx.waker());
+            let status = self.status();
+            if status.locked() {
+                Poll::Ready(status)
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+
+        if self.config.standard.is_none() {
+            if let Some(std) = status.standard {
+                if std != self.standard {
+                    self.standard = std;
+                    unsafe { Self::set_mode(std) };
+                }
+            }
+        }
+        self.standard
+    }
+
+    /// Start continuous capture into two NV12 buffers.
+    ///
+    /// Each buffer holds a Y plane of `width * height` bytes followed (at the
+    /// CbCr address) by `width * height / 2` bytes of interleaved CbCr.
+    ///
+    /// # Safety
+    /// Both buffers must be valid SDRAM, 4-byte aligned, sized for
+    /// [`Standard::frame_size`], and not accessed by the CPU except for the
+    /// buffer most recently returned by [`Tvd::wait_frame`]. That buffer is
+    /// written again once the following frame completes, so it must be
+    /// consumed within one frame period.
+    pub unsafe fn start(&mut self, buffers: [(*mut u8, *mut u8); 2]) {
+        for (slot, (y, c)) in BUFFERS.iter().zip(buffers) {
+            slot[0].store(y as u32, Ordering::Relaxed);
+            slot[1].store(c as u32, Ordering::Relaxed);
+        }
+        WRITING.store(0, Ordering::Relaxed);
+        self.frames_seen = FRAME_COUNT.load(Ordering::Acquire);
+        self.overflows_seen = OVERFLOW_COUNT.load(Ordering::Acquire);
+        self.signal_lost_seen = SIGNAL_LOST_COUNT.load(Ordering::Acquire);
+
+        let w = self.standard.width() as u32;
+        let h = self.standard.height() as u32;
+        write(TVD_WB2, (h << 16) | w);
+
+        // Buffer 0 latches at once while the write-back is off; buffer 1
+        // waits in WB3/WB4 for the first frame boundary.
+        queue_buffer(0);
+        write(TVD_IRQ_STATUS, 0xFFFF_FFFF);
+        write(TVD_IRQ_CTL, IRQ_FRAME_END | IRQ_FIFO_OVERFLOW | IRQ_LOCK_CHANGE);
+        write(TVD_WB1, WB1_EN);
+        queue_buffer(1);
+        write(TVD_EN, EN_ENABLE);
+    }
+
+    /// Wait for the next complete frame. Returns the index (0 or 1) of the
+    /// buffer that now holds it; capture continues into the other buffer.
+    ///
+    /// If several frames completed since the last call, the latest one is
+    /// returned. The signal is only reported lost while it is still absent.
+    pub async fn wait_frame(&mut self) -> Result<usize, Error> {
+        poll_fn(|cx| {
+            WAKER.register(cx.waker());
+            let overflows = OVERFLOW_COUNT.load(Ordering::Acquire);
+            if overflows != self.overflows_seen {
+                self.overflows_seen = overflows;
+                self.frames_seen = FRAME_COUNT.load(Ordering::Acquire);
+                return Poll::Ready(Err(Error::Overflow));
+            }
+            let frames = FRAME_COUNT.load(Ordering::Acquire);
+            if frames != self.frames_seen {
+                self.frames_seen = frames;
+                return Poll::Ready(Ok(()));
+            }
+            let lost = SIGNAL_LOST_COUNT.load(Ordering::Acquire);
+            if lost != self.signal_lost_seen {
+                self.signal_lost_seen = lost;
+                if !self.status().signal {
+                    return Poll::Ready(Err(Error::NoSignal));
+                }
+            }
+            Poll::Pending
+        })
+        .await?;
+
+        let done = LAST_DONE.load(Ordering::Relaxed) as usize;
+        let y = BUFFERS[done][0].load(Ordering::Relaxed);
+        let c = BUFFERS[done][1].load(Ordering::Relaxed);
+        let luma = self.standard.width() as u32 * self.standard.height() as u32;
+        arm9::asm::invalidate_dcache_range(y, luma);
+        arm9::asm::invalidate_dcache_range(c, luma / 2);
+        Ok(done)
+    }
+
+    /// Stop capture. The decoder stays powered and locked.
+    pub fn stop(&mut self) {
+        unsafe {
+            write(TVD_WB1, 0);
+            write(TVD_IRQ_CTL, 0);
+            write(TVD_IRQ_STATUS, 0xFFFF_FFFF);
+        }
+    }
+
+    /// Configure the decoder core for `std`.
+    unsafe fn set_mode(std: Standard) {
+        write(
+            TVD_MODE,
+            match std {
+                Standard::Ntsc => MODE_NTSC,
+                Standard::Pal => MODE_PAL,
+            },
+        );
+    }
+}
+
+impl<'d> Drop for Tvd<'d> {
+    fn drop(&mut self) {
+        self.stop();
+        intc::disable_irq(Interrupt::TVD.number());
+        unsafe {
+            write(TVD_EN, 0);
+            write(TVD_ADC_CTL, 0);
+            ccu_clear(CCU_BUS_SOFT_RST1, BUS1_TVD);
+            ccu_clear(CCU_DRAM_GATING, DRAM_GATING_TVD);
+            ccu_clear(CCU_BUS_CLK_GATING1, BUS1_TVD);
+            ccu_write(CCU_TVD_CLK, 0);
+        }
+    }
+}
@@ -0,0 +1,396 @@
+//! USB OTG role detection and switching for F1C100S/F1C200S.
+//!
+//! The F1C100S has a single MUSB-based USB 2.0 OTG controller (USBC0).
+//! Its PHY interface status register (ISCR, USBC0 + 0x400) reports the
+//! external ID pin and VBUS-valid state and can raise the USB_OTG interrupt
+//! when either changes.
+//!
+//! This driver watches those signals and switches the controller between
+//! peripheral (B-device) and host (A-device) operation:
+//! - ID high + VBUS valid  -> device role attached
+//! - ID low (grounded)     -> host role attached, VBUS enable GPIO driven high
+//! - otherwise             -> detached
+//!
+//! The device and host stacks run on top of this and are started/stopped
+//! according to the events returned by [`UsbOtg::wait_event`].
+//!
+//! The USBC0 registers are not in the PAC, so we use raw pointer access.
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::Poll;
+
+use embassy_sync::waitqueue::AtomicWaker;
+
+use crate::gpio::Output;
+use crate::interrupt::typelevel::Handler;
+use crate::interrupt::Interrupt;
+use crate::{intc, pac, peripherals, Peri};
+
+// ============================================================================
+// USBC0 base and register offsets (not in PAC)
+// ============================================================================
+const USBC_BASE: usize = 0x01C1_3000;
+
+const USBC_POWER: usize = 0x40;
+const USBC_DEVCTL: usize = 0x41;
+const USBC_VEND0: usize = 0x43;
+const USBC_INTUSB: usize = 0x4C;
+const USBC_INTUSBE: usize = 0x50;
+const USBC_ISCR: usize = 0x400;
+
+// POWER register bits
+const POWER_SOFT_CONN: u8 = 1 << 6;
+
+// DEVCTL register bits
+const DEVCTL_SESSION: u8 = 1 << 0;
+
+// ISCR register bits
+const ISCR_VBUS_VALID_FROM_VBUS: u32 = 1 << 29;
+const ISCR_EXT_ID_STATUS: u32 = 1 << 28;
+const ISCR_ID_PULLUP_EN: u32 = 1 << 17;
+const ISCR_DPDM_PULLUP_EN: u32 = 1 << 16;
+const ISCR_FORCE_ID_MASK: u32 = 0x3 << 14;
+const ISCR_FORCE_ID_LOW: u32 = 0x2 << 14;
+const ISCR_FORCE_ID_HIGH: u32 = 0x3 << 14;
+const ISCR_FORCE_VBUS_MASK: u32 = 0x3 << 12;
+const ISCR_FORCE_VBUS_LOW: u32 = 0x2 << 12;
+const ISCR_FORCE_VBUS_HIGH: u32 = 0x3 << 12;
+const ISCR_VBUS_CHANGE_DETECT: u32 = 1 << 6;
+const ISCR_ID_CHANGE_DETECT: u32 = 1 << 5;
+const ISCR_DPDM_CHANGE_DETECT: u32 = 1 << 4;
+const ISCR_IRQ_EN: u32 = 1 << 3;
+const ISCR_VBUS_CHANGE_DETECT_EN: u32 = 1 << 2;
+const ISCR_ID_CHANGE_DETECT_EN: u32 = 1 << 1;
+
+/// Write-1-to-clear status bits in ISCR. Must be masked out on read-modify-write.
+const ISCR_W1C_MASK: u32 = ISCR_VBUS_CHANGE_DETECT | ISCR_ID_CHANGE_DETECT | ISCR_DPDM_CHANGE_DETECT;
+
+#[inline]
+fn reg8(offset: usize) -> *mut u8 {
+    (USBC_BASE + offset) as *mut u8
+}
+
+#[inline]
+fn reg32(offset: usize) -> *mut u32 {
+    (USBC_BASE + offset) as *mut u32
+}
+
+#[inline]
+fn iscr_read() -> u32 {
+    unsafe { reg32(USBC_ISCR).read_volatile() }
+}
+
+/// Read-modify-write ISCR without accidentally clearing pending change bits.
+#[inline]
+fn iscr_modify(clear: u32, set: u32) {
+    unsafe {
+        let val = reg32(USBC_ISCR).read_volatile() & !ISCR_W1C_MASK;
+        reg32(USBC_ISCR).write_volatile((val & !clear) | set);
+    }
+}
+
+static WAKER: AtomicWaker = AtomicWaker::new();
+
+/// USB OTG IRQ handler — acknowledges ID/VBUS change detection and wakes the waiter.
+fn usb_otg_irq_handler() {
+    let iscr = iscr_read();
+    let pending = iscr & (ISCR_VBUS_CHANGE_DETECT | ISCR_ID_CHANGE_DETECT);
+    if pending != 0 {
+        // Write back with only the pending change bits set to clear them
+        unsafe { reg32(USBC_ISCR).write_volatile((iscr & !ISCR_W1C_MASK) | pending) };
+        WAKER.wake();
+    }
+}
+
+/// Interrupt handler for USB OTG role detection.
+///
+/// The handler is registered automatically by [`UsbOtg::new`], so
+/// `bind_interrupts!` is optional. It's provided for consistency with
+/// the embassy pattern:
+/// ```ignore
+/// bind_interrupts!(struct Irqs {
+///     USB_OTG => usb_otg::InterruptHandler;
+/// });
+/// ```
+pub struct InterruptHandler {
+    _private: (),
+}
+
+impl Handler<crate::interrupt::typelevel::USB_OTG> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        usb_otg_irq_handler();
+    }
+}
+
+/// Controller role.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Role {
+    /// Peripheral (B-device): ID pin floating/high, host supplies VBUS.
+    Device,
+    /// Host (A-device): ID pin grounded, we supply VBUS.
+    Host,
+}
+
+/// Attach/detach event reported by [`UsbOtg::wait_event`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Event {
+    /// A cable was attached and the controller switched to `Role`.
+    Attached(Role),
+    /// The cable was removed. The controller is idle and VBUS is off.
+    Detached,
+}
+
+/// USB OTG configuration
+#[non_exhaustive]
+#[derive(Copy, Clone)]
+pub struct Config {
+    /// Enable the PHY-internal ID pin pull-up (needed when the board has no external pull-up).
+    pub id_pullup: bool,
+    /// Enable the PHY-internal D+/D- pull-up/pull-down resistors.
+    pub dpdm_pullup: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            id_pullup: true,
+            dpdm_pullup: true,
+        }
+    }
+}
+
+/// USB OTG role switch driver.
+pub struct UsbOtg<'d> {
+    vbus_en: Option<Output<'d>>,
+    state: Option<Role>,
+    /// Role set by `force_host`/`force_device`, with the ID level at that time
+    forced: Option<(Role, bool)>,
+    /// Our own VBUS is still decaying after the host role ended
+    vbus_draining: bool,
+    _peri: PhantomData<&'d mut peripherals::USB_OTG>,
+}
+
+impl<'d> UsbOtg<'d> {
+    /// Create the role switch driver.
+    ///
+    /// `vbus_en` is an optional GPIO that enables the board's 5V VBUS switch
+    /// when acting as host. It is driven low until a host role is entered.
+    pub fn new(_peri: Peri<'d, peripherals::USB_OTG>, mut vbus_en: Option<Output<'d>>, config: Config) -> Self {
+        let ccu = unsafe { &*pac::Ccu::ptr() };
+
+        // USB PHY clock gating and reset de-assert
+        ccu.usbphy_cfg().modify(|_, w| {
+            w.sclk_gating_usbphy().set_bit();
+            w.usbphy_rst().set_bit()
+        });
+
+        // USB OTG bus clock gating and reset de-assert
+        ccu.bus_clk_gating0().modify(|_, w| w.usb_otg_gating().set_bit());
+        ccu.bus_soft_rst0().modify(|_, w| w.usb_otg_rst().clear_bit());
+        for _ in 0..100 {
+            core::hint::spin_loop();
+        }
+        ccu.bus_soft_rst0().modify(|_, w| w.usb_otg_rst().set_bit());
+
+        unsafe {
+            // Select PIO bus interface (VEND0 = 0)
+            reg8(USBC_VEND0).write_volatile(0);
+            // Disable and clear all controller-level USB interrupts
+            reg8(USBC_INTUSBE).write_volatile(0);
+            reg8(USBC_INTUSB).write_volatile(0xFF);
+        }
+
+        // Start in the idle (detached) state: force ID high, VBUS low.
+        let mut set = ISCR_FORCE_ID_HIGH | ISCR_FORCE_VBUS_LOW;
+        if config.id_pullup {
+            set |= ISCR_ID_PULLUP_EN;
+        }
+        if config.dpdm_pullup {
+            set |= ISCR_DPDM_PULLUP_EN;
+        }
+        iscr_modify(
+            ISCR_FORCE_ID_MASK | ISCR_FORCE_VBUS_MASK | ISCR_ID_PULLUP_EN | ISCR_DPDM_PULLUP_EN,
+            set,
+        );
+
+        // Clear stale change flags, then enable ID/VBUS change interrupts
+        iscr_modify(0, ISCR_W1C_MASK);
+        iscr_modify(0, ISCR_IRQ_EN | ISCR_ID_CHANGE_DETECT_EN | ISCR_VBUS_CHANGE_DETECT_EN);
+
+        if let Some(pin) = vbus_en.as_mut() {
+            pin.set_low();
+        }
+
+        intc::set_irq_handler(Interrupt::USB_OTG.number(), usb_otg_irq_handler);
+        intc::enable_irq(Interrupt::USB_OTG.number());
+
+        Self {
+            vbus_en,
+            state: None,
+            forced: None,
+            vbus_draining: false,
+            _peri: PhantomData,
+        }
+    }
+
+    /// Current ID pin level as seen by the PHY. `false` = grounded (A-device).
+    pub fn id_high(&self) -> bool {
+        iscr_read() & ISCR_EXT_ID_STATUS != 0
+    }
+
+    /// Whether VBUS is currently above the session-valid threshold.
+    pub fn vbus_valid(&self) -> bool {
+        iscr_read() & ISCR_VBUS_VALID_FROM_VBUS != 0
+    }
+
+    /// The role the controller is currently operating in, if attached.
+    pub fn role(&self) -> Option<Role> {
+        self.state
+    }
+
+    /// Role implied by the current ID/VBUS state, or `None` if nothing is attached.
+    ///
+    /// A forced role wins until the ID pin changes level. After the host
+    /// role, VBUS must drop below the valid threshold once before it counts
+    /// as an attached host again; the VBUS change interrupt wakes us then.
+    fn detect(&mut self) -> Option<Role> {
+        if let Some((role, id_high)) = self.forced {
+            if self.id_high() == id_high {
+                return Some(role);
+            }
+            self.forced = None;
+        }
+        let vbus = self.vbus_valid();
+        if !vbus {
+            self.vbus_draining = false;
+        }
+        if !self.id_high() {
+            Some(Role::Host)
+        } else if vbus && !self.vbus_draining {
+            Some(Role::Device)
+        } else {
+            None
+        }
+    }
+
+    /// Wait for the next attach/detach event and switch the controller accordingly.
+    ///
+    /// On the first call the current state is reported immediately if a cable
+    /// is already attached. A direct host <-> device change is reported as
+    /// `Detached` followed by `Attached(new_role)` on the next call.
+    pub async fn wait_event(&mut self) -> Event {
+        poll_fn(|cx| {
+            WAKER.register(cx.waker());
+
+            let detected = self.detect();
+            if detected == self.state {
+                return Poll::Pending;
+            }
+
+            if self.state.is_some() {
+                self.enter_idle();
+                self.state = None;
+                return Poll::Ready(Event::Detached);
+            }
+
+            // state is None and detected is Some
+            let role = detected.unwrap();
+            match role {
+                Role::Host => self.enter_host(),
+                Role::Device => self.enter_device(),
+            }
+            self.state = Some(role);
+            Poll::Ready(Event::Attached(role))
+        })
+        .await
+    }
+
+    /// Force the controller into host role regardless of the ID pin.
+    ///
+    /// Useful for boards with a type-A receptacle wired to the OTG port.
+    /// The role holds until [`UsbOtg::release_force`] or an ID pin change.
+    pub fn force_host(&mut self) {
+        self.enter_host();
+        self.state = Some(Role::Host);
+        self.forced = Some((Role::Host, self.id_high()));
+    }
+
+    /// Force the controller into device role regardless of the ID pin.
+    ///
+    /// The role holds until [`UsbOtg::release_force`] or an ID pin change.
+    pub fn force_device(&mut self) {
+        self.enter_device();
+        self.state = Some(Role::Device);
+        self.forced = Some((Role::Device, self.id_high()));
+    }
+
+    /// Go back to ID/VBUS detection. If the detected role differs, the next
+    /// [`UsbOtg::wait_event`] reports the change.
+    pub fn release_force(&mut self) {
+        self.forced = None;
+    }
+
+    fn enter_host(&mut self) {
+        if let Some(pin) = self.vbus_en.as_mut() {
+            pin.set_high();
+        }
+        iscr_modify(
+            ISCR_FORCE_ID_MASK | ISCR_FORCE_VBUS_MASK,
+            ISCR_FORCE_ID_LOW | ISCR_FORCE_VBUS_HIGH,
+        );
+        unsafe {
+            // Disconnect from the bus as a peripheral, then start a host session
+            let power = reg8(USBC_POWER).read_volatile();
+            reg8(USBC_POWER).write_volatile(power & !POWER_SOFT_CONN);
+            let devctl = reg8(USBC_DEVCTL).read_volatile();
+            reg8(USBC_DEVCTL).write_volatile(devctl | DEVCTL_SESSION);
+        }
+    }
+
+    fn enter_device(&mut self) {
+        if let Some(pin) = self.vbus_en.as_mut() {
+            pin.set_low();
+        }
+        iscr_modify(
+            ISCR_FORCE_ID_MASK | ISCR_FORCE_VBUS_MASK,
+            ISCR_FORCE_ID_HIGH | ISCR_FORCE_VBUS_HIGH,
+        );
+        unsafe {
+            let devctl = reg8(USBC_DEVCTL).read_volatile();
+            reg8(USBC_DEVCTL).write_volatile(devctl & !DEVCTL_SESSION);
+            // Enable D+ pull-up so the host sees us
+            let power = reg8(USBC_POWER).read_volatile();
+            reg8(USBC_POWER).write_volatile(power | POWER_SOFT_CONN);
+        }
+    }
+
+    fn enter_idle(&mut self) {
+        if self.state == Some(Role::Host) {
+            self.vbus_draining = true;
+        }
+        if let Some(pin) = self.vbus_en.as_mut() {
+            pin.set_low();
+        }
+        unsafe {
+            let power = reg8(USBC_POWER).read_volatile();
+            reg8(USBC_POWER).write_volatile(power & !POWER_SOFT_CONN);
+            let devctl = reg8(USBC_DEVCTL).read_volatile();
+            reg8(USBC_DEVCTL).write_volatile(devctl & !DEVCTL_SESSION);
+        }
+        iscr_modify(
+            ISCR_FORCE_ID_MASK | ISCR_FORCE_VBUS_MASK,
+            ISCR_FORCE_ID_HIGH | ISCR_FORCE_VBUS_LOW,
+        );
+    }
+}
+
+impl<'d> Drop for UsbOtg<'d> {
+    fn drop(&mut self) {
+        intc::disable_irq(Interrupt::USB_OTG.number());
+        iscr_modify(ISCR_IRQ_EN | ISCR_ID_CHANGE_DETECT_EN | ISCR_VBUS_CHANGE_DETECT_EN, 0);
+        self.enter_idle();
+    }
+}
@@ -0,0 +1,462 @@
+//! Baseline JPEG headers for the VE: parsing for the decoder, writing for
+//! the encoder.
+//!
+//! Only what the hardware needs is extracted: frame size, sampling,
+//! quantization and Huffman tables, restart interval and the location of
+//! the entropy-coded scan. Motion-JPEG frames without a DHT segment get
+//! the standard tables from ITU T.81 Annex K, which are also the ones the
+//! encoder uses.
+
+use super::{Buffer, Error};
+use crate::display::defe::{InputFormat, InputFrame};
+
+// Markers
+const APP0: u8 = 0xE0;
+const SOI: u8 = 0xD8;
+const EOI: u8 = 0xD9;
+const SOF0: u8 = 0xC0;
+const SOF1: u8 = 0xC1;
+const DHT: u8 = 0xC4;
+const DQT: u8 = 0xDB;
+const DRI: u8 = 0xDD;
+const SOS: u8 = 0xDA;
+const RST0: u8 = 0xD0;
+const RST7: u8 = 0xD7;
+
+/// Chroma subsampling of a JPEG image.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Subsampling {
+    /// Chroma halved horizontally (typical for MJPEG cameras)
+    Yuv422,
+    /// Chroma halved in both directions (typical for stills)
+    Yuv420,
+}
+
+impl Subsampling {
+    /// Chroma subsampling as (horizontal, vertical) divisors.
+    pub const fn divisors(self) -> (u32, u32) {
+        match self {
+            Subsampling::Yuv422 => (2, 1),
+            Subsampling::Yuv420 => (2, 2),
+        }
+    }
+
+    /// Luma sampling factors byte of the SOF component spec.
+    const fn sof_sampling(self) -> u8 {
+        match self {
+            Subsampling::Yuv422 => 0x21,
+            Subsampling::Yuv420 => 0x22,
+        }
+    }
+}
+
+/// Encoder quantization.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Quantization {
+    /// Annex K tables scaled by an IJG-style quality factor (1..=100)
+    Quality(u8),
+    /// Explicit luma and chroma tables in natural (row-major) order
+    Tables { luma: [u8; 64], chroma: [u8; 64] },
+}
+
+impl Quantization {
+    /// Luma and chroma tables in natural order, no zero entries.
+    pub fn tables(&self) -> ([u8; 64], [u8; 64]) {
+        match *self {
+            Quantization::Quality(quality) => {
+                let q = quality.clamp(1, 100) as u32;
+                let scale = if q < 50 { 5000 / q } else { 200 - q * 2 };
+                let scaled = |base: &[u8; 64]| base.map(|b| ((b as u32 * scale + 50) / 100).clamp(1, 255) as u8);
+                (scaled(&STD_QUANT_LUMA), scaled(&STD_QUANT_CHROMA))
+            }
+            Quantization::Tables { luma, chroma } => (luma.map(|q| q.max(1)), chroma.map(|q| q.max(1))),
+        }
+    }
+}
+
+/// Size and layout of a JPEG image, available before decoding.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct JpegInfo {
+    pub width: u16,
+    pub height: u16,
+    pub subsampling: Subsampling,
+}
+
+impl JpegInfo {
+    /// Parse the headers of a baseline JPEG.
+    pub fn parse(data: &[u8]) -> Result<Self, Error> {
+        Ok(Header::parse(data)?.info)
+    }
+
+    /// Bytes needed for the tiled Y plane.
+    pub const fn luma_size(&self) -> usize {
+        align32(self.width as u32) as usize * align32(self.height as u32) as usize
+    }
+
+    /// Bytes needed for the tiled CbCr plane.
+    pub const fn chroma_size(&self) -> usize {
+        let (_, vsub) = self.subsampling.divisors();
+        align32(self.width as u32) as usize * align32((self.height as u32).div_ceil(vsub)) as usize
+    }
+
+    /// DEFE input format matching the decoder output.
+    pub const fn defe_format(&self) -> InputFormat {
+        match self.subsampling {
+            Subsampling::Yuv422 => InputFormat::Nv16Tiled,
+            Subsampling::Yuv420 => InputFormat::Nv12Tiled,
+        }
+    }
+
+    /// DEFE input frame describing a buffer this image was decoded into.
+    ///
+    /// JPEG uses full-range BT.601, i.e. [`Csc`](crate::display::defe::Csc)
+    /// with [`ColorRange::Full`](crate::display::defe::ColorRange::Full).
+    pub fn defe_frame(&self, buf: Buffer) -> InputFrame {
+        InputFrame::tiled(self.defe_format(), buf.y as u32, buf.c as u32, self.width, self.height)
+    }
+}
+
+const fn align32(x: u32) -> u32 {
+    (x + 31) & !31
+}
+
+/// One Huffman table as stored in the DHT segment.
+#[derive(Copy, Clone)]
+pub(super) struct Huffman<'a> {
+    /// Number of codes of each length 1..=16
+    pub counts: &'a [u8],
+    /// Symbols in code order
+    pub symbols: &'a [u8],
+}
+
+/// Everything the decoder needs from the headers.
+pub(super) struct Header<'a> {
+    pub info: JpegInfo,
+    /// Luma sampling factors (h, v)
+    pub sampling: (u8, u8),
+    /// Luma and chroma quantization tables, zig-zag order
+    pub quant: [&'a [u8]; 2],
+    /// DC0, AC0, DC1, AC1: set 0 for luma, set 1 for chroma
+    pub huffman: [Huffman<'a>; 4],
+    pub restart_interval: u16,
+    /// Entropy-coded data, up to but excluding EOI
+    pub scan: core::ops::Range<usize>,
+}
+
+impl<'a> Header<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self, Error> {
+        if data.len() < 4 || data[0] != 0xFF || data[1] != SOI {
+            return Err(Error::InvalidJpeg);
+        }
+
+        let mut quant: [Option<&[u8]>; 4] = [None; 4];
+        // Indexed [class][id], class 0 = DC, 1 = AC
+        let mut tables: [[Option<Huffman>; 4]; 2] = [[None; 4]; 2];
+        let mut restart_interval = 0;
+        // (id, h, v, quant table) per component
+        let mut comps = [(0u8, 0u8, 0u8, 0u8); 3];
+        let mut size = None;
+
+        let mut pos = 2;
+        loop {
+            // Skip fill bytes before the marker
+            if data.get(pos) != Some(&0xFF) {
+                return Err(Error::InvalidJpeg);
+            }
+            while pos < data.len() && data[pos] == 0xFF {
+                pos += 1;
+            }
+            if pos + 2 >= data.len() {
+                return Err(Error::InvalidJpeg);
+            }
+            let marker = data[pos];
+            let len = u16::from_be_bytes([data[pos + 1], data[pos + 2]]) as usize;
+            let body = data.get(pos + 3..pos + 1 + len).ok_or(Error::InvalidJpeg)?;
+            pos += 1 + len;
+
+            match marker {
+                SOF0 | SOF1 => {
+                    if body.len() < 15 || body[0] != 8 {
+                        return Err(Error::Unsupported);
+                    }
+                    let height = u16::from_be_bytes([body[1], body[2]]);
+                    let width = u16::from_be_bytes([body[3], body[4]]);
+                    if body[5] != 3 || width == 0 || height == 0 {
+                        return Err(Error::Unsupported);
+                    }
+                    for (i, c) in comps.iter_mut().enumerate() {
+                        let b = &body[6 + i * 3..9 + i * 3];
+                        *c = (b[0], b[1] >> 4, b[1] & 0x0F, b[2] & 0x03);
+                    }
+                    size = Some((width, height));
+                }
+                0xC2..=0xCF if marker != DHT => return Err(Error::Unsupported),
+                DHT => {
+                    let mut rest = body;
+                    while !rest.is_empty() {
+                        let (class, id) = ((rest[0] >> 4) as usize, (rest[0] & 0x0F) as usize);
+                        let counts = rest.get(1..17).ok_or(Error::InvalidJpeg)?;
+                        let n: usize = counts.iter().map(|&c| c as usize).sum();
+                        let symbols = rest.get(17..17 + n).ok_or(Error::InvalidJpeg)?;
+                        if class > 1 || id > 3 || n > 256 {
+                            return Err(Error::InvalidJpeg);
+                        }
+                        tables[class][id] = Some(Huffman { counts, symbols });
+                        rest = &rest[17 + n..];
+                    }
+                }
+                DQT => {
+                    let mut rest = body;
+                    while !rest.is_empty() {
+                        // 16-bit tables are not supported by the hardware
+                        if rest[0] >> 4 != 0 {
+                            return Err(Error::Unsupported);
+                        }
+                        let table = rest.get(1..65).ok_or(Error::InvalidJpeg)?;
+                        quant[(rest[0] & 0x03) as usize] = Some(table);
+                        rest = &rest[65..];
+                    }
+                }
+                DRI => {
+                    let b = body.get(..2).ok_or(Error::InvalidJpeg)?;
+                    restart_interval = u16::from_be_bytes([b[0], b[1]]);
+                }
+                SOS => {
+                    let (width, height) = size.ok_or(Error::InvalidJpeg)?;
+                    if body.first() != Some(&3) || body.len() < 7 {
+                        return Err(Error::Unsupported);
+                    }
+                    // (dc, ac) table ids in component order
+                    let mut sel = [(0usize, 0usize); 3];
+                    for (i, s) in sel.iter_mut().enumerate() {
+                        let b = body[2 + i * 2];
+                        *s = ((b >> 4) as usize & 0x03, b as usize & 0x03);
+                    }
+
+                    // The hardware has one table set for luma and one shared by Cb/Cr
+                    let (_, h, v, q_luma) = comps[0];
+                    let (_, _, _, q_chroma) = comps[1];
+                    if comps[1..].iter().any(|c| c.1 != 1 || c.2 != 1 || c.3 != q_chroma) || sel[1] != sel[2] {
+                        return Err(Error::Unsupported);
+                    }
+                    let subsampling = match (h, v) {
+                        (2, 1) => Subsampling::Yuv422,
+                        (2, 2) => Subsampling::Yuv420,
+                        _ => return Err(Error::Unsupported),
+                    };
+
+                    let q = |id: u8| quant[id as usize].ok_or(Error::InvalidJpeg);
+                    let t = |class: usize, id: usize, default: Huffman<'static>| match tables[class][id] {
+                        Some(t) => Ok(t),
+                        // MJPEG frames omit DHT and rely on the standard tables
+                        None if tables.iter().flatten().all(Option::is_none) => Ok(default),
+                        None => Err(Error::InvalidJpeg),
+                    };
+
+                    return Ok(Self {
+                        info: JpegInfo {
+                            width,
+                            height,
+                            subsampling,
+                        },
+                        sampling: (h, v),
+                        quant: [q(q_luma)?, q(q_chroma)?],
+                        huffman: [
+                            t(0, sel[0].0, STD_DC_LUMA)?,
+                            t(1, sel[0].1, STD_AC_LUMA)?,
+                            t(0, sel[1].0, STD_DC_CHROMA)?,
+                            t(1, sel[1].1, STD_AC_CHROMA)?,
+                        ],
+                        restart_interval,
+                        scan: pos..scan_end(data, pos),
+                    });
+                }
+                EOI => return Err(Error::InvalidJpeg),
+                // APPn, COM and friends
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Write SOI, APP0 (JFIF), DQT, SOF0, DHT and SOS for a 3-component image.
+///
+/// Quantization tables are given in natural order. Returns the header length,
+/// or `None` if `out` is too small.
+pub(super) fn write_header(
+    out: &mut [u8],
+    width: u16,
+    height: u16,
+    subsampling: Subsampling,
+    luma_q: &[u8; 64],
+    chroma_q: &[u8; 64],
+) -> Option<usize> {
+    let mut w = Writer { out, pos: 0 };
+    w.put(&[0xFF, SOI])?;
+
+    // JFIF 1.01, no density, no thumbnail
+    w.segment(APP0, &[b'J', b'F', b'I', b'F', 0, 1, 1, 0, 0, 1, 0, 1, 0, 0])?;
+
+    let mut dqt = [0u8; 130];
+    for (t, table) in [luma_q, chroma_q].into_iter().enumerate() {
+        dqt[t * 65] = t as u8;
+        for (i, &z) in ZIGZAG.iter().enumerate() {
+            dqt[t * 65 + 1 + i] = table[z as usize];
+        }
+    }
+    w.segment(DQT, &dqt)?;
+
+    let [h1, h0] = height.to_be_bytes();
+    let [w1, w0] = width.to_be_bytes();
+    // Y uses table 0, Cb/Cr share table 1
+    w.segment(
+        SOF0,
+        &[
+            8,
+            h1,
+            h0,
+            w1,
+            w0,
+            3,
+            1,
+            subsampling.sof_sampling(),
+            0,
+            2,
+            0x11,
+            1,
+            3,
+            0x11,
+            1,
+        ],
+    )?;
+
+    let tables = [
+        (0x00, STD_DC_LUMA),
+        (0x10, STD_AC_LUMA),
+        (0x01, STD_DC_CHROMA),
+        (0x11, STD_AC_CHROMA),
+    ];
+    let len: usize = tables.iter().map(|(_, t)| 17 + t.symbols.len()).sum();
+    w.put(&[0xFF, DHT])?;
+    w.put(&(len as u16 + 2).to_be_bytes())?;
+    for (class_id, t) in tables {
+        w.put(&[class_id])?;
+        w.put(t.counts)?;
+        w.put(t.symbols)?;
+    }
+
+    w.segment(SOS, &[3, 1, 0x00, 2, 0x11, 3, 0x11, 0, 63, 0])?;
+    Some(w.pos)
+}
+
+/// Bounds-checked byte writer for headers.
+struct Writer<'a> {
+    out: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> Writer<'a> {
+    fn put(&mut self, bytes: &[u8]) -> Option<()> {
+        self.out
+            .get_mut(self.pos..self.pos + bytes.len())?
+            .copy_from_slice(bytes);
+        self.pos += bytes.len();
+        Some(())
+    }
+
+    /// Marker, length and body.
+    fn segment(&mut self, marker: u8, body: &[u8]) -> Option<()> {
+        self.put(&[0xFF, marker])?;
+        self.put(&(body.len() as u16 + 2).to_be_bytes())?;
+        self.put(body)
+    }
+}
+
+/// Find the EOI marker after `start`, skipping stuffed bytes and restart markers.
+fn scan_end(data: &[u8], start: usize) -> usize {
+    let mut i = start;
+    while i + 1 < data.len() {
+        if data[i] == 0xFF {
+            match data[i + 1] {
+                0x00 | 0xFF | RST0..=RST7 => {}
+                _ => return i,
+            }
+        }
+        i += 1;
+    }
+    data.len()
+}
+
+/// Natural-order index of each zig-zag position.
+const ZIGZAG: [u8; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20, 13, 6, 7, 14, 21,
+    28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59, 52, 45, 38, 31, 39, 46, 53, 60, 61, 54,
+    47, 55, 62, 63,
+];
+
+// Standard quantization tables (ITU T.81 Annex K.1), natural order
+const STD_QUANT_LUMA: [u8; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, //
+    12, 12, 14, 19, 26, 58, 60, 55, //
+    14, 13, 16, 24, 40, 57, 69, 56, //
+    14, 17, 22, 29, 51, 87, 80, 62, //
+    18, 22, 37, 56, 68, 109, 103, 77, //
+    24, 35, 55, 64, 81, 104, 113, 92, //
+    49, 64, 78, 87, 103, 121, 120, 101, //
+    72, 92, 95, 98, 112, 100, 103, 99, //
+];
+
+const STD_QUANT_CHROMA: [u8; 64] = [
+    17, 18, 24, 47, 99, 99, 99, 99, //
+    18, 21, 26, 66, 99, 99, 99, 99, //
+    24, 26, 56, 99, 99, 99, 99, 99, //
+    47, 66, 99, 99, 99, 99, 99, 99, //
+    99, 99, 99, 99, 99, 99, 99, 99, //
+    99, 99, 99, 99, 99, 99, 99, 99, //
+    99, 99, 99, 99, 99, 99, 99, 99, //
+    99, 99, 99, 99, 99, 99, 99, 99, //
+];
+
+// Standard Huffman tables (ITU T.81 Annex K.3)
+const STD_DC_LUMA: Huffman<'static> = Huffman {
+    counts: &[0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0],
+    symbols: &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+};
+
+const STD_DC_CHROMA: Huffman<'static> = Huffman {
+    counts: &[0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0],
+    symbols: &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+};
+
+const STD_AC_LUMA: Huffman<'static> = Huffman {
+    counts: &[0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7D],
+    symbols: &[
+        0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07, 0x22, 0x71,
+        0x14, 0x32, 0x81, 0x91, 0xA1, 0x08, 0x23, 0x42, 0xB1, 0xC1, 0x15, 0x52, 0xD1, 0xF0, 0x24, 0x33, 0x62, 0x72,
+        0x82, 0x09, 0x0A, 0x16, 0x17, 0x18, 0x19, 0x1A, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2A, 0x34, 0x35, 0x36, 0x37,
+        0x38, 0x39, 0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59,
+        0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0x83,
+        0x84, 0x85, 0x86, 0x87, 0x88, 0x89, 0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0xA2, 0xA3,
+        0xA4, 0xA5, 0xA6, 0xA7, 0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3,
+        0xC4, 0xC5, 0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA, 0xE1, 0xE2,
+        0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8, 0xF9, 0xFA,
+    ],
+};
+
+const STD_AC_CHROMA: Huffman<'static> = Huffman {
+    counts: &[0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77],
+    symbols: &[
+        0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71, 0x13, 0x22,
+        0x32, 0x81, 0x08, 0x14, 0x42, 0x91, 0xA1, 0xB1, 0xC1, 0x09, 0x23, 0x33, 0x52, 0xF0, 0x15, 0x62, 0x72, 0xD1,
+        0x0A, 0x16, 0x24, 0x34, 0xE1, 0x25, 0xF1, 0x17, 0x18, 0x19, 0x1A, 0x26, 0x27, 0x28, 0x29, 0x2A, 0x35, 0x36,
+        0x37, 0x38, 0x39, 0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58,
+        0x59, 0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A,
+        0x82, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89, 0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A,
+        0xA2, 0xA3, 0xA4, 0xA5, 0xA6, 0xA7, 0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA,
+        0xC2, 0xC3, 0xC4, 0xC5, 0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA,
+        0xE2, 0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8, 0xF9, 0xFA,
+    ],
+};
@@ -0,0 +1,559 @@
+//! Video Engine (VE): hardware JPEG / Motion-JPEG decoder and JPEG encoder.
+//!
+//! The VE is a set of codec engines sharing one register window and an
+//! SRAM block (SRAM C) that must be mapped to it before use. Decoding runs
+//! from SDRAM to SDRAM without CPU involvement:
+//!
+//! - input: a complete baseline JPEG (one MJPEG frame) in SDRAM
+//! - output: Y plane + interleaved CbCr plane, both in 32x32 tiles
+//!
+//! The encoder works the other way round: a linear NV12/NV16 frame (e.g.
+//! from the CSI) is compressed into a complete JFIF file, see
+//! [`Ve::encode_jpeg`]. The CPU writes the headers and the final EOI marker;
+//! the engine produces the entropy-coded scan in between.
+//!
+//! The tiled output is read directly by the DEFE
+//! ([`InputFormat::Nv12Tiled`](crate::display::defe::InputFormat::Nv12Tiled)
+//! / [`Nv16Tiled`](crate::display::defe::InputFormat::Nv16Tiled)), see
+//! [`JpegInfo::defe_frame`].
+//!
+//! Only the JPEG modes of the MPEG (decode) and AVC (encode) engines are
+//! driven: MJPEG is supported, H.264 is not. H.264 decoding needs slice
+//! feeding, reference lists and motion-vector buffers managed in software.
+//!
+//! Limitations of the JPEG decoder: baseline (or extended 8-bit) Huffman
+//! only, three components, 4:2:2 or 4:2:0 sampling, Cb and Cr sharing
+//! quantization and Huffman tables.
+//!
+//! The VE registers are not in the PAC, so we use raw pointer access.
+
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::task::Poll;
+
+use embassy_sync::waitqueue::AtomicWaker;
+use portable_atomic::{AtomicU32, Ordering};
+
+use crate::interrupt::typelevel::Handler;
+use crate::interrupt::Interrupt;
+use crate::rcc::{
+    ccu_clear, ccu_read, ccu_set, ccu_write, CCU_BUS_CLK_GATING1, CCU_BUS_SOFT_RST1, CCU_DRAM_GATING, CCU_PLL_VE,
+    CCU_VE_CLK,
+};
+use crate::{intc, peripherals, Peri};
+
+mod jpeg;
+
+pub use jpeg::{JpegInfo, Quantization, Subsampling};
+
+// ============================================================================
+// VE base and register offsets (not in PAC)
+// ============================================================================
+const VE_BASE: usize = 0x01C0_E000;
+
+const VE_CTRL: usize = 0x000;
+const VE_VERSION: usize = 0x0F0;
+
+// MPEG engine
+const MPEG_CTRL: usize = 0x114;
+const MPEG_TRIGGER: usize = 0x118;
+const MPEG_STATUS: usize = 0x11C;
+const MPEG_VLD_ADDR: usize = 0x128;
+const MPEG_VLD_OFFSET: usize = 0x12C;
+const MPEG_VLD_LEN: usize = 0x130;
+const MPEG_VLD_END: usize = 0x134;
+const MPEG_IQ_MIN_INPUT: usize = 0x180;
+const MPEG_JPEG_SIZE: usize = 0x1B8;
+const MPEG_JPEG_RES_INT: usize = 0x1C0;
+const MPEG_ROT_LUMA: usize = 0x1CC;
+const MPEG_ROT_CHROMA: usize = 0x1D0;
+const MPEG_SDROT_CTRL: usize = 0x1D4;
+const MPEG_RAM_WRITE_PTR: usize = 0x1E0;
+const MPEG_RAM_WRITE_DATA: usize = 0x1E4;
+
+// ISP (encoder input)
+const ISP_INPUT_SIZE: usize = 0xA00;
+const ISP_INPUT_STRIDE: usize = 0xA04;
+const ISP_CTRL: usize = 0xA08;
+const ISP_INPUT_LUMA: usize = 0xA78;
+const ISP_INPUT_CHROMA: usize = 0xA7C;
+
+// AVC engine (encoder)
+const AVC_PARAM: usize = 0xB04;
+const AVC_CTRL: usize = 0xB14;
+const AVC_TRIGGER: usize = 0xB18;
+const AVC_STATUS: usize = 0xB1C;
+const AVC_VLE_ADDR: usize = 0xB80;
+const AVC_VLE_END: usize = 0xB84;
+const AVC_VLE_OFFSET: usize = 0xB88;
+const AVC_VLE_MAX: usize = 0xB8C;
+const AVC_VLE_LENGTH: usize = 0xB90;
+const AVC_QM_INDEX: usize = 0xBE0;
+const AVC_QM_DATA: usize = 0xBE4;
+
+// CTRL bits
+/// 128-bit DDR access, 2MB reconstruct write mode
+const CTRL_DDR_MODE: u32 = 0x0013_0000;
+const CTRL_ENGINE_MPEG: u32 = 0x0;
+const CTRL_ENGINE_AVC_ENC: u32 = 0xB;
+const CTRL_ENGINE_MASK: u32 = 0xF;
+const CTRL_ENGINE_NONE: u32 = 0x7;
+
+/// Finish / error / VLD data request interrupt enables
+const MPEG_CTRL_JPEG: u32 = 0x0000_007C;
+
+// TRIGGER: decode command [7:0], JPEG sampling format [31:24]
+const TRIGGER_JPEG_DECODE: u32 = 0x0E;
+
+// MPEG STATUS bits
+const STATUS_FINISH: u32 = 1 << 0;
+const STATUS_ERROR: u32 = 1 << 1;
+const STATUS_VLD_REQ: u32 = 1 << 2;
+const STATUS_MASK: u32 = STATUS_FINISH | STATUS_ERROR | STATUS_VLD_REQ;
+const STATUS_CLEAR: u32 = 0x0000_C00F;
+
+// ISP_CTRL: input format [30:29]
+const ISP_CTRL_NV12: u32 = 0 << 29;
+const ISP_CTRL_NV16: u32 = 1 << 29;
+
+// AVC_PARAM: JPEG mode, sampling [17:16]
+const AVC_PARAM_JPEG: u32 = 1 << 31;
+const AVC_PARAM_420: u32 = 0 << 16;
+const AVC_PARAM_422: u32 = 1 << 16;
+
+/// Finish / VLE buffer full interrupt enables
+const AVC_CTRL_IRQ: u32 = 0x0000_000F;
+const AVC_TRIGGER_JPEG_ENCODE: u32 = 0x08;
+
+// AVC STATUS bits (finish shares bit 0 with the MPEG engine)
+const AVC_STATUS_VLE_FULL: u32 = 1 << 1;
+const AVC_STATUS_MASK: u32 = STATUS_FINISH | AVC_STATUS_VLE_FULL;
+
+// VLD_ADDR: 16-byte aligned address [27:4] with bits [31:28] in [3:0]
+const VLD_ADDR_FIRST: u32 = 1 << 30;
+const VLD_ADDR_LAST: u32 = 1 << 29;
+const VLD_ADDR_VALID: u32 = 1 << 28;
+
+// SRAM controller: map SRAM C to the VE
+const SRAM_CTRL0: usize = 0x01C0_0000;
+const SRAM_C_VE: u32 = 0x7FFF_FFFF;
+
+// CCU bits (raw, VE bits are shared registers)
+const BUS1_VE: u32 = 1 << 0;
+const DRAM_GATING_VE: u32 = 1 << 0;
+const PLL_ENABLE: u32 = 1 << 31;
+const PLL_LOCK: u32 = 1 << 28;
+/// SCLK gating, PLL_VE / 1 (297MHz at reset default)
+const VE_CLK_EN: u32 = 1 << 31;
+
+#[inline]
+fn reg(offset: usize) -> *mut u32 {
+    (VE_BASE + offset) as *mut u32
+}
+
+#[inline]
+unsafe fn write(offset: usize, val: u32) {
+    reg(offset).write_volatile(val);
+}
+
+#[inline]
+unsafe fn read(offset: usize) -> u32 {
+    reg(offset).read_volatile()
+}
+
+static WAKER: AtomicWaker = AtomicWaker::new();
+/// Status bits of the last finished job, 0 while running
+static DONE: AtomicU32 = AtomicU32::new(0);
+
+/// VE IRQ handler — latches the active engine's status and wakes the waiter.
+fn ve_irq_handler() {
+    let status = unsafe {
+        match read(VE_CTRL) & CTRL_ENGINE_MASK {
+            CTRL_ENGINE_MPEG => {
+                let status = read(MPEG_STATUS) & STATUS_MASK;
+                write(MPEG_STATUS, STATUS_CLEAR);
+                status
+            }
+            CTRL_ENGINE_AVC_ENC => {
+                let status = read(AVC_STATUS) & AVC_STATUS_MASK;
+                write(AVC_STATUS, status);
+                status
+            }
+            _ => 0,
+        }
+    };
+
+    if status != 0 {
+        DONE.fetch_or(status, Ordering::Release);
+        WAKER.wake();
+    }
+}
+
+/// Interrupt handler for VE job completion.
+///
+/// The handler is registered automatically by [`Ve::new`], so
+/// `bind_interrupts!` is optional. It's provided for consistency with
+/// the embassy pattern:
+/// ```ignore
+/// bind_interrupts!(struct Irqs {
+///     VE => ve::InterruptHandler;
+/// });
+/// ```
+pub struct InterruptHandler {
+    _private: (),
+}
+
+impl Handler<crate::interrupt::typelevel::VE> for InterruptHandler {
+    unsafe fn on_interrupt() {
+        ve_irq_handler();
+    }
+}
+
+/// VE error
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    /// The headers are malformed or incomplete
+    InvalidJpeg,
+    /// Valid JPEG the hardware cannot decode (progressive, grayscale, 4:4:4, ...)
+    Unsupported,
+    /// The scan ended before the image was complete
+    Truncated,
+    /// The engine reported a bitstream error
+    Decode,
+    /// Frame size the encoder cannot handle (not a multiple of the MCU size)
+    InvalidSize,
+    /// The output buffer is too small for the encoded image
+    BufferTooSmall,
+    /// A buffer does not meet the engine's alignment requirement
+    Misaligned,
+}
+
+/// Y and CbCr planes of a frame in SDRAM.
+///
+/// Decoder output sizes are given by [`JpegInfo::luma_size`] and
+/// [`JpegInfo::chroma_size`]; encoder input is linear with a `width` stride.
+#[derive(Debug, Copy, Clone)]
+pub struct Buffer {
+    pub y: *mut u8,
+    pub c: *mut u8,
+}
+
+/// JPEG encoder settings.
+#[derive(Debug, Copy, Clone)]
+pub struct EncodeConfig {
+    /// Frame size, a multiple of 16 in both directions
+    pub width: u16,
+    pub height: u16,
+    /// Input layout: 4:2:0 reads NV12, 4:2:2 reads NV16
+    pub subsampling: Subsampling,
+    pub quantization: Quantization,
+}
+
+impl EncodeConfig {
+    /// Encode an NV12 frame at the given IJG quality (1..=100).
+    pub const fn nv12(width: u16, height: u16, quality: u8) -> Self {
+        Self {
+            width,
+            height,
+            subsampling: Subsampling::Yuv420,
+            quantization: Quantization::Quality(quality),
+        }
+    }
+}
+
+/// Video Engine driver.
+pub struct Ve<'d> {
+    /// PLL_VE was off and switched on by [`Ve::new`]
+    pll_enabled: bool,
+    _peri: PhantomData<&'d mut peripherals::VE>,
+}
+
+impl<'d> Ve<'d> {
+    /// Power up the VE, start PLL_VE and map SRAM C to the engine.
+    pub fn new(_peri: Peri<'d, peripherals::VE>) -> Self {
+        let pll_enabled;
+        unsafe {
+            pll_enabled = ccu_read(CCU_PLL_VE) & PLL_ENABLE == 0;
+            if pll_enabled {
+                ccu_set(CCU_PLL_VE, PLL_ENABLE);
+                while ccu_read(CCU_PLL_VE) & PLL_LOCK == 0 {}
+            }
+
+            ccu_write(CCU_VE_CLK, VE_CLK_EN);
+            ccu_set(CCU_BUS_CLK_GATING1, BUS1_VE);
+            ccu_set(CCU_DRAM_GATING, DRAM_GATING_VE);
+            ccu_set(CCU_BUS_SOFT_RST1, BUS1_VE);
+
+            let sram = SRAM_CTRL0 as *mut u32;
+            sram.write_volatile(sram.read_volatile() | SRAM_C_VE);
+
+            write(VE_CTRL, CTRL_DDR_MODE | CTRL_ENGINE_NONE);
+            write(MPEG_STATUS, STATUS_CLEAR);
+        }
+
+        intc::set_irq_handler(Interrupt::VE.number(), ve_irq_handler);
+        intc::enable_irq(Interrupt::VE.number());
+
+        Self {
+            pll_enabled,
+            _peri: PhantomData,
+        }
+    }
+
+    /// Hardware version (upper 16 bits of the version register).
+    pub fn version(&self) -> u16 {
+        (unsafe { read(VE_VERSION) } >> 16) as u16
+    }
+
+    /// Decode one baseline JPEG (or MJPEG frame) into `out`.
+    ///
+    /// Use [`JpegInfo::parse`] first to size the output buffer; the same info
+    /// is returned on success.
+    ///
+    /// # Safety
+    /// `data` must be in SDRAM and 16-byte aligned. `out` planes must be
+    /// valid SDRAM of at least [`JpegInfo::luma_size`] /
+    /// [`JpegInfo::chroma_size`] bytes, 1 KiB aligned, and not accessed
+    /// until this returns.
+    pub async unsafe fn decode_jpeg(&mut self, data: &[u8], out: Buffer) -> Result<JpegInfo, Error> {
+        if data.as_ptr() as u32 % 16 != 0 || out.y as u32 % 1024 != 0 || out.c as u32 % 1024 != 0 {
+            return Err(Error::Misaligned);
+        }
+        let header = jpeg::Header::parse(data)?;
+        let info = header.info;
+        let base = data.as_ptr() as u32;
+        arm9::asm::clean_dcache_range(base, data.len() as u32);
+        // Drop every line of the output planes, so no dirty line can be
+        // evicted over what the engine writes
+        for (addr, len) in [(out.y as u32, info.luma_size()), (out.c as u32, info.chroma_size())] {
+            arm9::asm::clean_dcache_range(addr, len as u32);
+            arm9::asm::invalidate_dcache_range(addr, len as u32);
+        }
+
+        write(VE_CTRL, CTRL_DDR_MODE | CTRL_ENGINE_MPEG);
+        write(MPEG_JPEG_RES_INT, header.restart_interval as u32);
+
+        // Size in MCUs minus one
+        let (h, v) = header.sampling;
+        let mcu_w = (info.width as u32 - 1) / (8 * h as u32);
+        let mcu_h = (info.height as u32 - 1) / (8 * v as u32);
+        write(MPEG_JPEG_SIZE, (mcu_h << 16) | mcu_w);
+
+        write(MPEG_ROT_LUMA, out.y as u32);
+        write(MPEG_ROT_CHROMA, out.c as u32);
+        write(MPEG_SDROT_CTRL, 0);
+
+        // Bitstream: the scan, addressed as a bit offset into `data`
+        write(MPEG_VLD_END, base + header.scan.end as u32 - 1);
+        write(MPEG_CTRL, MPEG_CTRL_JPEG);
+        write(MPEG_VLD_OFFSET, header.scan.start as u32 * 8);
+        write(MPEG_VLD_LEN, header.scan.len() as u32 * 8);
+        write(
+            MPEG_VLD_ADDR,
+            (base & 0x0FFF_FFF0) | (base >> 28) | VLD_ADDR_FIRST | VLD_ADDR_LAST | VLD_ADDR_VALID,
+        );
+
+        // Quantization tables: luma at 64..128, chroma at 0..64
+        for (i, &q) in header.quant[0].iter().enumerate() {
+            write(MPEG_IQ_MIN_INPUT, ((64 + i as u32) << 8) | q as u32);
+        }
+        for (i, &q) in header.quant[1].iter().enumerate() {
+            write(MPEG_IQ_MIN_INPUT, ((i as u32) << 8) | q as u32);
+        }
+
+        write(MPEG_RAM_WRITE_PTR, 0);
+        for word in huffman_ram(&header.huffman).chunks_exact(4) {
+            write(
+                MPEG_RAM_WRITE_DATA,
+                u32::from_le_bytes([word[0], word[1], word[2], word[3]]),
+            );
+        }
+
+        let format = match info.subsampling {
+            Subsampling::Yuv422 => 0x13,
+            Subsampling::Yuv420 => 0x03,
+        };
+        let status = self.run(MPEG_TRIGGER, (format << 24) | TRIGGER_JPEG_DECODE).await;
+        Self::idle();
+
+        arm9::asm::invalidate_dcache_range(out.y as u32, info.luma_size() as u32);
+        arm9::asm::invalidate_dcache_range(out.c as u32, info.chroma_size() as u32);
+
+        if status & STATUS_ERROR != 0 {
+            Err(Error::Decode)
+        } else if status & STATUS_VLD_REQ != 0 {
+            Err(Error::Truncated)
+        } else {
+            Ok(info)
+        }
+    }
+
+    /// Encode a linear NV12/NV16 frame into a complete JFIF file in `out`.
+    ///
+    /// Returns the file length in bytes.
+    ///
+    /// # Safety
+    /// `input` planes must be valid SDRAM of `width * height` bytes (Y) and
+    /// `width * height / 2` (NV12) or `width * height` (NV16) bytes (CbCr),
+    /// with the CPU's writes to them already visible (e.g. a frame from
+    /// [`Csi::wait_frame`](crate::csi::Csi::wait_frame)). `out` must be in
+    /// SDRAM and 16-byte aligned, and should be 32-byte aligned and sized,
+    /// since its cache lines are invalidated.
+    pub async unsafe fn encode_jpeg(
+        &mut self,
+        input: Buffer,
+        config: &EncodeConfig,
+        out: &mut [u8],
+    ) -> Result<usize, Error> {
+        if config.width == 0 || config.height == 0 || config.width % 16 != 0 || config.height % 16 != 0 {
+            return Err(Error::InvalidSize);
+        }
+        if out.as_ptr() as u32 % 16 != 0 {
+            return Err(Error::Misaligned);
+        }
+
+        let (luma_q, chroma_q) = config.quantization.tables();
+        let header_len = jpeg::write_header(out, config.width, config.height, config.subsampling, &luma_q, &chroma_q)
+            .ok_or(Error::BufferTooSmall)?;
+        // Keep room for the EOI marker
+        let scan_max = out.len().checked_sub(header_len + 2).ok_or(Error::BufferTooSmall)?;
+
+        // Write the headers back and drop every line of `out`, so no dirty
+        // line can be evicted over the scan the engine writes
+        let base = out.as_mut_ptr() as u32;
+        arm9::asm::clean_dcache_range(base, out.len() as u32);
+        arm9::asm::invalidate_dcache_range(base, out.len() as u32);
+
+        write(VE_CTRL, CTRL_DDR_MODE | CTRL_ENGINE_AVC_ENC);
+
+        // Input: size and stride in macroblocks
+        let mb_w = config.width as u32 / 16;
+        let mb_h = config.height as u32 / 16;
+        write(ISP_INPUT_SIZE, (mb_w << 16) | mb_h);
+        write(ISP_INPUT_STRIDE, mb_w);
+        write(
+            ISP_CTRL,
+            match config.subsampling {
+                Subsampling::Yuv420 => ISP_CTRL_NV12,
+                Subsampling::Yuv422 => ISP_CTRL_NV16,
+            },
+        );
+        write(ISP_INPUT_LUMA, input.y as u32);
+        write(ISP_INPUT_CHROMA, input.c as u32);
+
+        // Output: the scan goes right after the headers
+        write(AVC_VLE_ADDR, base);
+        write(AVC_VLE_OFFSET, header_len as u32 * 8);
+        write(AVC_VLE_END, base + (header_len + scan_max) as u32 - 1);
+        write(AVC_VLE_MAX, scan_max as u32 * 8);
+
+        // Quantizer reciprocals (1/q in 0.16 fixed point), raster order: luma then chroma
+        write(AVC_QM_INDEX, 0);
+        for &q in luma_q.iter().chain(chroma_q.iter()) {
+            write(AVC_QM_DATA, (0x1_0000 / q as u32).min(0xFFFF));
+        }
+
+        write(
+            AVC_PARAM,
+            AVC_PARAM_JPEG
+                | match config.subsampling {
+                    Subsampling::Yuv420 => AVC_PARAM_420,
+                    Subsampling::Yuv422 => AVC_PARAM_422,
+                },
+        );
+        write(AVC_CTRL, AVC_CTRL_IRQ);
+
+        let status = self.run(AVC_TRIGGER, AVC_TRIGGER_JPEG_ENCODE).await;
+        // The engine pads the last byte with 1s
+        let scan_len = (read(AVC_VLE_LENGTH) as usize).div_ceil(8);
+        Self::idle();
+        if status & AVC_STATUS_VLE_FULL != 0 {
+            return Err(Error::BufferTooSmall);
+        }
+
+        let end = header_len + scan_len;
+        arm9::asm::invalidate_dcache_range(base, out.len() as u32);
+        out.get_mut(end..end + 2)
+            .ok_or(Error::BufferTooSmall)?
+            .copy_from_slice(&[0xFF, 0xD9]);
+        Ok(end + 2)
+    }
+
+    /// Trigger the selected engine and wait for it to finish. Returns the
+    /// status bits; the engine is reset on failure.
+    async fn run(&mut self, trigger_reg: usize, trigger: u32) -> u32 {
+        DONE.store(0, Ordering::Release);
+        unsafe { write(trigger_reg, trigger) };
+
+        let status = poll_fn(|cx| {
+            WAKER.register(cx.waker());
+            match DONE.load(Ordering::Acquire) {
+                0 => Poll::Pending,
+                s => Poll::Ready(s),
+            }
+        })
+        .await;
+
+        unsafe {
+            if status != STATUS_FINISH {
+                // A stalled or failed job leaves the engine busy; reset it
+                ccu_clear(CCU_BUS_SOFT_RST1, BUS1_VE);
+                ccu_set(CCU_BUS_SOFT_RST1, BUS1_VE);
+            }
+        }
+        status
+    }
+
+    /// Deselect the engine once its result registers have been read.
+    fn idle() {
+        unsafe { write(VE_CTRL, CTRL_DDR_MODE | CTRL_ENGINE_NONE) };
+    }
+}
+
+/// Build the 2 KiB Huffman RAM image for the four tables (DC0, AC0, DC1, AC1).
+///
+/// Per table `i`: 16 first codes (u16) at byte `i * 64`, 16 symbol offsets
+/// (u8) at `i * 64 + 32`, and the symbols at `1024 + i * 256`.
+fn huffman_ram(tables: &[jpeg::Huffman; 4]) -> [u8; 2048] {
+    let mut ram = [0u8; 2048];
+    for (i, t) in tables.iter().enumerate() {
+        let mut last = 0;
+        let mut offset = 0u32;
+        for (j, &n) in t.counts.iter().enumerate() {
+            ram[i * 64 + 32 + j] = offset as u8;
+            offset += n as u32;
+            if n != 0 {
+                last = j;
+            }
+        }
+        ram[1024 + i * 256..][..t.symbols.len()].copy_from_slice(t.symbols);
+
+        let mut code = 0u32;
+        for (j, &n) in t.counts.iter().enumerate() {
+            let first = if j <= last { code as u16 } else { 0xFFFF };
+            ram[i * 64 + j * 2..][..2].copy_from_slice(&first.to_le_bytes());
+            code = (code + n as u32) << 1;
+        }
+    }
+    ram
+}
+
+impl<'d> Drop for Ve<'d> {
+    fn drop(&mut self) {
+        intc::disable_irq(Interrupt::VE.number());
+        unsafe {
+            write(VE_CTRL, CTRL_DDR_MODE | CTRL_ENGINE_NONE);
+            ccu_clear(CCU_BUS_SOFT_RST1, BUS1_VE);
+            ccu_clear(CCU_DRAM_GATING, DRAM_GATING_VE);
+            ccu_clear(CCU_BUS_CLK_GATING1, BUS1_VE);
+            ccu_clear(CCU_VE_CLK, VE_CLK_EN);
+            if self.pll_enabled {
+                ccu_clear(CCU_PLL_VE, PLL_ENABLE);
+            }
+            // Give SRAM C back to the CPU
+            let sram = SRAM_CTRL0 as *mut u32;
+            sram.write_volatile(sram.read_volatile() & !SRAM_C_VE);
+        }
+    }
+}